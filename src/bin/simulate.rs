@@ -0,0 +1,75 @@
+// Plays solo crawls headlessly with a bot and reports how far they got and what killed them.
+//
+//     cargo run --release --bin simulate -- --crawls 5000 --bot pathing
+//
+// Options:
+//     --crawls <n>        number of crawls to play (default 1000)
+//     --bot <name>        random or pathing (default pathing)
+//     --seed <n>          seed of the first crawl, the rest count up from it (default 1)
+//     --max-actions <n>   actions a bot may try per crawl (default 20000)
+//     --yeti-kills <n>    lifetime Ice Yeti kills, to measure YETI RAGE (default 0)
+use dungeon_dash::model::*;
+use std::process::exit;
+
+fn log(_: &str) {}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!(
+        "usage: simulate [--crawls <n>] [--bot <{}>] [--seed <n>] [--max-actions <n>] [--yeti-kills <n>]",
+        CRAWL_BOTS.join("|")
+    );
+    exit(1)
+}
+
+fn main() {
+    let (mut crawls, mut bot_name, mut first_seed) = (1000, "pathing".to_string(), 1);
+    let (mut max_actions, mut yeti_kills) = (20_000, 0);
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let Some(value) = args.next() else {
+            usage(&format!("{flag} needs a value"));
+        };
+        let number = || {
+            value
+                .parse::<u32>()
+                .unwrap_or_else(|_| usage(&format!("{flag} expects a number, got {value}")))
+        };
+        match flag.as_str() {
+            "--crawls" => crawls = number(),
+            "--bot" => bot_name = value.clone(),
+            "--seed" => first_seed = number(),
+            "--max-actions" => max_actions = number() as usize,
+            "--yeti-kills" => yeti_kills = number(),
+            _ => usage(&format!("unknown option {flag}")),
+        }
+    }
+    if crawl_bot_by_name(&bot_name, 0).is_none() {
+        usage(&format!("unknown bot {bot_name}"));
+    }
+
+    let mut total_stats = DungeonStats::new();
+    total_stats.increment(DungeonStatKind::Defeated(MonsterKind::IceYeti), yeti_kills);
+
+    let mut report = BalanceReport::new();
+    for seed in (first_seed..).take(crawls as usize) {
+        // Start the crawl exactly like create_new_dungeon and a replay would
+        let replay = CrawlReplay {
+            crawl_id: seed,
+            mode: DungeonMode::Classic,
+            seed,
+            total_stats: total_stats.clone(),
+            upgrades: PlayerUpgrades::new(),
+            actions: vec![],
+        };
+        let mut dungeon = replay.start(log);
+        if let Some(mut bot) = crawl_bot_by_name(&bot_name, seed) {
+            bot.play(&mut dungeon, max_actions, log);
+        }
+        report.record(&dungeon);
+    }
+
+    println!("Bot: {bot_name}, seeds {first_seed} and up");
+    print!("{report}");
+}
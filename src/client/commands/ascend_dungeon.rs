@@ -0,0 +1,8 @@
+use super::*;
+
+pub fn exec(dungeon: &Dungeon) -> String {
+    let command = server::commands::create_new_dungeon::COMMAND;
+    let cmd = server::commands::create_new_dungeon::next_floor(dungeon);
+    let data = &cmd.try_to_vec().unwrap();
+    os::client::exec(server::PROGRAM_ID, command, data)
+}
@@ -1,8 +1,8 @@
 use super::*;
 
-pub fn exec(reset: bool) -> String {
+pub fn exec(reset: bool, daily: bool) -> String {
     let command = server::commands::create_new_dungeon::COMMAND;
-    let cmd = server::commands::create_new_dungeon::new(reset);
+    let cmd = server::commands::create_new_dungeon::new(reset, daily);
     let data = &cmd.try_to_vec().unwrap();
     os::client::exec(server::PROGRAM_ID, command, data)
 }
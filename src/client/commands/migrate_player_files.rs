@@ -0,0 +1,7 @@
+use super::*;
+
+pub fn exec() -> String {
+    use server::commands::migrate_player_files::Command;
+    let data = Command::new().try_to_vec().unwrap();
+    os::client::exec(server::PROGRAM_ID, Command::NAME, &data)
+}
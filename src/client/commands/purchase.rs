@@ -0,0 +1,7 @@
+use super::*;
+
+pub fn exec(dungeon: &Dungeon, item: ShopItem) -> String {
+    use server::commands::purchase::Command;
+    let data = Command::new(dungeon, item).try_to_vec().unwrap();
+    os::client::exec(server::PROGRAM_ID, Command::NAME, &data)
+}
@@ -0,0 +1,7 @@
+use super::*;
+
+pub fn exec(kind: UpgradeKind) -> String {
+    use server::commands::unlock_upgrade::Command;
+    let data = Command::new(kind).try_to_vec().unwrap();
+    os::client::exec(server::PROGRAM_ID, Command::NAME, &data)
+}
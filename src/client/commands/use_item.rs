@@ -0,0 +1,7 @@
+use super::*;
+
+pub fn exec(dungeon: &Dungeon, slot: usize) -> String {
+    use server::commands::use_item::Command;
+    let data = Command::new(dungeon, slot).try_to_vec().unwrap();
+    os::client::exec(server::PROGRAM_ID, Command::NAME, &data)
+}
@@ -4,6 +4,7 @@ pub mod ui;
 
 pub mod commands {
     use super::*;
+    pub mod ascend_dungeon;
     pub mod ascend_multiplayer_dungeon;
     pub mod create_multiplayer_dungeon_lobby;
     pub mod create_new_dungeon;
@@ -12,19 +13,29 @@ pub mod commands {
     pub mod delete_multiplayer_dungeon_lobby;
     pub mod join_multiplayer_dungeon_lobby;
     pub mod leave_multiplayer_dungeon_lobby;
+    pub mod migrate_player_files;
     pub mod move_multiplayer_dungeon_player;
     pub mod move_player;
+    pub mod purchase;
     pub mod reset_multiplayer_dungeon;
     pub mod start_new_multiplayer_dungeon;
+    pub mod unlock_upgrade;
+    pub mod use_item;
 }
 
 pub mod queries {
     use super::*;
+    pub mod crawl_replay;
     pub mod current_multiplayer_dungeon_crawl_id;
+    pub mod daily_leaderboard;
     pub mod global_leaderboard;
     pub mod multiplayer_dungeon;
     pub mod multiplayer_dungeon_list;
     pub mod player_achievements;
+    pub mod player_command_failure;
+    pub mod player_daily_attempt;
+    pub mod player_daily_dungeon;
     pub mod player_dungeon;
     pub mod player_dungeon_stats;
+    pub mod player_upgrades;
 }
@@ -0,0 +1,15 @@
+use super::*;
+
+// Replays of crawls still in progress stay on the server, so only archived ones can be watched
+pub fn fetch(crawl_id: u32) -> Result<CrawlReplay, std::io::Error> {
+    let filepath = server::paths::crawl_replay(crawl_id);
+    if let Some(file) = os::client::watch_file(server::PROGRAM_ID, &filepath).data {
+        if let Ok(replay) = CrawlReplay::decode(&file.contents) {
+            return Ok(replay);
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "Replay not found",
+    ))
+}
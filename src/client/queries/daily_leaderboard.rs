@@ -0,0 +1,9 @@
+use super::*;
+
+pub fn fetch(day: u32) -> Result<Leaderboard, std::io::Error> {
+    let filepath = server::paths::daily_leaderboard(day);
+    os::client::watch_file(server::PROGRAM_ID, &filepath)
+        .data
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Leaderboard unavailable"))
+        .and_then(|file| Leaderboard::decode(&file.contents))
+}
@@ -5,5 +5,5 @@ pub fn fetch() -> Result<Leaderboard, std::io::Error> {
     os::client::watch_file(server::PROGRAM_ID, &filepath)
         .data
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Leaderboard unavailable"))
-        .and_then(|file| Leaderboard::try_from_slice(&file.contents))
+        .and_then(|file| Leaderboard::decode(&file.contents))
 }
@@ -1,11 +1,12 @@
 use super::*;
 
-pub fn fetch(crawl_id: u32) -> Result<MultiplayerDungeon, std::io::Error> {
-    let filepath = server::paths::multiplayer_dungeon(crawl_id);
+pub fn fetch(user_id: &str, crawl_id: u32) -> Result<MultiplayerDungeon, std::io::Error> {
+    let filepath = server::paths::multiplayer_dungeon_view(crawl_id, user_id);
     os::client::watch_file(server::PROGRAM_ID, &filepath)
         .data
         .ok_or_else(|| {
             std::io::Error::new(std::io::ErrorKind::Other, "MultiplayerDungeon unavailable")
         })
-        .and_then(|file| MultiplayerDungeon::try_from_slice(&file.contents))
+        .and_then(|file| MultiplayerDungeonView::decode(&file.contents))
+        .map(MultiplayerDungeon::from)
 }
@@ -7,5 +7,5 @@ pub fn fetch(user_id: &str) -> Result<PlayerAchievements, std::io::Error> {
         .ok_or_else(|| {
             std::io::Error::new(std::io::ErrorKind::Other, "PlayerAchievements unavailable")
         })
-        .and_then(|file| PlayerAchievements::try_from_slice(&file.contents))
+        .and_then(|file| PlayerAchievements::decode(&file.contents))
 }
@@ -0,0 +1,10 @@
+use super::*;
+use server::results::CommandFailure;
+
+pub fn fetch(user_id: &str) -> Result<CommandFailure, std::io::Error> {
+    let filepath = server::paths::player_command_failure(user_id);
+    os::client::watch_file(server::PROGRAM_ID, &filepath)
+        .data
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "CommandFailure unavailable"))
+        .and_then(|file| CommandFailure::decode(&file.contents))
+}
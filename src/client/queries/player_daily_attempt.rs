@@ -0,0 +1,10 @@
+use super::*;
+
+// The last UTC day the player started a Daily Dash
+pub fn fetch(user_id: &str) -> Result<u32, std::io::Error> {
+    let filepath = server::paths::player_daily_attempt(user_id);
+    os::client::watch_file(server::PROGRAM_ID, &filepath)
+        .data
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Daily attempt unavailable"))
+        .and_then(|file| u32::try_from_slice(&file.contents))
+}
@@ -0,0 +1,10 @@
+use super::*;
+
+pub fn fetch(user_id: &str) -> Result<Dungeon, std::io::Error> {
+    let filepath = server::paths::player_daily_dungeon_view(user_id);
+    os::client::watch_file(server::PROGRAM_ID, &filepath)
+        .data
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Dungeon unavailable"))
+        .and_then(|file| DungeonView::decode(&file.contents))
+        .map(Dungeon::from)
+}
@@ -5,5 +5,5 @@ pub fn fetch(user_id: &str) -> Result<DungeonStats, std::io::Error> {
     os::client::watch_file(server::PROGRAM_ID, &filepath)
         .data
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "DungeonStats unavailable"))
-        .and_then(|file| DungeonStats::try_from_slice(&file.contents))
+        .and_then(|file| DungeonStats::decode(&file.contents))
 }
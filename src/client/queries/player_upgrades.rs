@@ -0,0 +1,9 @@
+use super::*;
+
+pub fn fetch(user_id: &str) -> Result<PlayerUpgrades, std::io::Error> {
+    let filepath = server::paths::player_upgrades(user_id);
+    os::client::watch_file(server::PROGRAM_ID, &filepath)
+        .data
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "PlayerUpgrades unavailable"))
+        .and_then(|file| PlayerUpgrades::decode(&file.contents))
+}
@@ -4,6 +4,7 @@ pub const TILE_SIZE: i32 = 16;
 pub const TURN_DUR: usize = 16;
 pub const FLOOR_DUR: usize = 32;
 pub const HIT_DUR: usize = 64;
+pub const COMBAT_LOG_ROWS: usize = 4;
 pub const MOVE_DUR: usize = 8;
 pub const MOVE_Y_OFFSET: i32 = 6;
 pub const MOVE_X_OFFSET: i32 = 6;
@@ -35,7 +36,7 @@ pub fn initialize() -> LocalState {
         floor: Tween::new(0).duration(FLOOR_DUR),
         turn: Tween::new(0).duration(TURN_DUR),
         last_exec_at: 0,
-        last_exec_turn: None,
+        pending_exec: None,
         players: vec![new_player_entity()],
         monsters: vec![],
         leaderboard_kind: LeaderboardKind::HighestFloor,
@@ -47,6 +48,13 @@ pub fn initialize() -> LocalState {
         last_crawl_achievements_modal: 0,
         show_stats_modal: false,
         last_channel_message: "".to_string(),
+        replay: None,
+        travel: None,
+        last_command_failure: None,
+        toast: None,
+        combat_log: CombatLog::new(),
+        floating_texts: vec![],
+        migrated_player_files: false,
     }
 }
 
@@ -87,6 +95,9 @@ pub fn render() {
         return screens::title::render(&mut state, false);
     };
 
+    // Check whether the last command failed
+    poll_command_failure(&mut state, &user_id);
+
     // Render based on chosen screen
     match state.screen.clone() {
         Screen::Title => {
@@ -109,22 +120,76 @@ pub fn render() {
                     state.players = vec![new_player_entity(); 1];
                 }
                 screens::dungeon::render(&mut state, &user_id, dungeon);
+                render_toast(&mut state);
+            } else {
+                screens::title::render(&mut state, true);
+            }
+        }
+        Screen::DailyDash => {
+            // Fetch user daily dungeon
+            if let Ok(dungeon) = &client::queries::player_daily_dungeon::fetch(&user_id) {
+                if state.players.len() != 1 {
+                    state.players = vec![new_player_entity(); 1];
+                }
+                screens::dungeon::render(&mut state, &user_id, dungeon);
+                render_toast(&mut state);
             } else {
                 screens::title::render(&mut state, true);
             }
         }
         Screen::MultiplayerDungeonLobbies(mut ctx) => {
             screens::multiplayer_dungeon_lobbies::render(&mut state, &user_id, &mut ctx);
+            render_toast(&mut state);
             if let Screen::MultiplayerDungeonLobbies(_) = state.screen {
                 state.screen = Screen::MultiplayerDungeonLobbies(ctx);
             }
         }
+        Screen::Upgrades(mut ctx) => {
+            let total_stats =
+                client::queries::player_dungeon_stats::fetch(&user_id).unwrap_or(DungeonStats::new());
+            let upgrades =
+                client::queries::player_upgrades::fetch(&user_id).unwrap_or(PlayerUpgrades::new());
+            screens::upgrades::render(&mut state, &mut ctx, &upgrades, &total_stats);
+            if let Screen::Upgrades(_) = state.screen {
+                state.screen = Screen::Upgrades(ctx);
+            }
+        }
+        Screen::Shop(mut ctx) => {
+            let dungeon = if ctx.daily {
+                client::queries::player_daily_dungeon::fetch(&user_id)
+            } else {
+                client::queries::player_dungeon::fetch(&user_id)
+            };
+            if let Ok(dungeon) = &dungeon {
+                screens::shop::render(&mut state, &mut ctx, dungeon);
+            } else {
+                screens::title::render(&mut state, true);
+            }
+            if let Screen::Shop(_) = state.screen {
+                state.screen = Screen::Shop(ctx);
+            }
+        }
+        Screen::Replay(mut ctx) => {
+            if let Ok(replay) = client::queries::crawl_replay::fetch(ctx.crawl_id) {
+                screens::replay::render(&mut state, &mut ctx, &replay);
+            } else {
+                reset_cam!();
+                text!("Loading replay...");
+                if gamepad(0).b.just_pressed() {
+                    state.screen = Screen::Dungeon;
+                }
+            }
+            if let Screen::Replay(_) = state.screen {
+                state.screen = Screen::Replay(ctx);
+            }
+        }
         Screen::MultiplayerDungeon(crawl_id) => {
-            if let Ok(dungeon) = client::queries::multiplayer_dungeon::fetch(crawl_id) {
+            if let Ok(dungeon) = client::queries::multiplayer_dungeon::fetch(&user_id, crawl_id) {
                 if state.players.len() != dungeon.player.players.len() {
                     state.players = vec![new_player_entity(); dungeon.player.players.len()];
                 }
                 screens::multiplayer_dungeon::render(&mut state, &user_id, &dungeon);
+                render_toast(&mut state);
             } else {
                 reset_cam!();
                 text!("Loading multiplayer dungeon...");
@@ -152,8 +217,107 @@ mod screens {
     pub mod dungeon;
     pub mod multiplayer_dungeon;
     pub mod multiplayer_dungeon_lobbies;
+    pub mod replay;
     pub mod select_mode;
+    pub mod shop;
     pub mod title;
+    pub mod upgrades;
+}
+
+// Toasts why the player's last command failed, if it's a failure we haven't seen yet.
+// A command that fails won't land, so the dungeon screen stops waiting on it and any auto-walk ends.
+fn poll_command_failure(state: &mut LocalState, user_id: &str) {
+    let Ok(failure) = client::queries::player_command_failure::fetch(user_id) else {
+        return;
+    };
+    let last = state.last_command_failure.replace(failure.id);
+    // Whatever failed before the game loaded is old news
+    if last == Some(failure.id) || last.is_none() && matches!(state.screen, Screen::Title) {
+        return;
+    }
+    // Stale commands were sent from an out-of-date view, which catches up on its own
+    if failure.error.is_stale() {
+        return;
+    }
+    state.toast = Some(Toast::new(&failure.error.to_string(), tick()));
+    state.pending_exec = None;
+    state.travel = None;
+}
+
+pub fn render_toast(state: &mut LocalState) {
+    let Some(toast) = &state.toast else {
+        return;
+    };
+    if toast.is_expired(tick()) {
+        state.toast = None;
+        return;
+    }
+    let [w, _h] = canvas_size!();
+    let (toast_w, toast_h) = (w - 16, 14u32);
+    let (x, y) = (8, 8);
+    #[rustfmt::skip]
+    rect!(absolute = true, x = x, y = y, w = toast_w, h = toast_h, color = BTN_NEGATIVE_COLOR, border_color = 0xffffffaa, border_radius = 3, border_width = 1);
+    text!("{}", toast.message; absolute = true, x = x + 4, y = y + 4, font = Font::M);
+}
+
+// The combat log panel's bounds in screen space
+pub fn combat_log_bounds() -> (i32, i32, u32, u32) {
+    let [w, _h] = canvas_size!();
+    (2, 2, w / 2, COMBAT_LOG_ROWS as u32 * 7 + 4)
+}
+
+// The last few turn events in a panel at the top-left.
+// Scroll it with the mouse wheel, or tap its top or bottom half to page back or forward.
+pub fn render_combat_log(log: &mut CombatLog) {
+    if log.lines.is_empty() {
+        return;
+    }
+    let (x, y, w, h) = combat_log_bounds();
+    if hovered(x, y, w, h) {
+        let wheel = mouse(0).wheel[1];
+        if wheel != 0 {
+            log.scroll_by(wheel.signum(), COMBAT_LOG_ROWS);
+        }
+    }
+    if clickable(x, y, w, h / 2) {
+        log.scroll_by(COMBAT_LOG_ROWS as i32, COMBAT_LOG_ROWS);
+    } else if clickable(x, y + h as i32 / 2, w, h - h / 2) {
+        log.scroll_by(-(COMBAT_LOG_ROWS as i32), COMBAT_LOG_ROWS);
+    }
+    #[rustfmt::skip]
+    rect!(absolute = true, x = x, y = y, w = w, h = h, color = 0x22203499, border_radius = 2);
+    for (i, line) in log.visible(COMBAT_LOG_ROWS).iter().enumerate() {
+        text!(line, absolute = true, x = x + 2, y = y + 2 + i as i32 * 7, font = Font::S);
+    }
+    // Show how far back we've scrolled
+    if log.scroll > 0 {
+        text!("+{}", log.scroll; absolute = true, x = x + w as i32 - 14, y = y + 2, font = Font::S, color = 0xfbf236ff);
+    }
+}
+
+// Damage numbers and pickups rising off their tiles, with a burst of sparks as they appear.
+// Drawn in world space, so call it while the dungeon camera is set.
+pub fn render_floating_texts(floating_texts: &mut Vec<FloatingText>) {
+    let now = tick();
+    floating_texts.retain(|floating_text| !floating_text.is_expired(now));
+    for floating_text in floating_texts.iter() {
+        let age = now.saturating_sub(floating_text.shown_at) as i32;
+        let cx = floating_text.x * TILE_SIZE + TILE_SIZE / 2;
+        let cy = floating_text.y * TILE_SIZE + TILE_SIZE / 2;
+        if age < 12 {
+            for i in 0..6 {
+                let angle = i as f32 * std::f32::consts::TAU / 6.;
+                let distance = age as f32 * 0.8;
+                let sx = cx + (angle.cos() * distance) as i32;
+                let sy = cy + (angle.sin() * distance) as i32;
+                rect!(x = sx, y = sy, w = 1, h = 1, color = floating_text.color);
+            }
+        }
+        let tx = cx - floating_text.text.len() as i32 * 2;
+        let ty = cy - 8 - age / 4;
+        text!(&floating_text.text, x = tx + 1, y = ty + 1, font = Font::S, color = 0x000000ff);
+        text!(&floating_text.text, x = tx, y = ty, font = Font::S, color = floating_text.color);
+    }
 }
 
 #[allow(arithmetic_overflow)]
@@ -188,6 +352,70 @@ pub fn negative_button(text: &str, x: i32, y: i32, w: u32) -> bool {
     button(text, BTN_NEGATIVE_COLOR, x, y, w)
 }
 
+// Draws an inventory item (bombs don't have a sprite yet)
+pub fn render_item(item: ItemKind, x: i32, y: i32, absolute: bool) {
+    match item {
+        ItemKind::Potion => sprite!("yellow_gem", x = x, y = y, absolute = absolute),
+        ItemKind::TeleportScroll => sprite!("scroll", x = x, y = y, absolute = absolute),
+        #[rustfmt::skip]
+        ItemKind::RerollScroll => sprite!("scroll", x = x, y = y, absolute = absolute, color = 0xd77bbaff),
+        ItemKind::Bomb => {
+            circ!(x = x + 3, y = y + 5, d = 10, color = 0x222034ff, absolute = absolute);
+            rect!(x = x + 10, y = y + 3, w = 2, h = 3, color = 0x8f563bff, absolute = absolute);
+            let spark: u32 = if (tick() / 4).is_multiple_of(2) { 0xfbf236ff } else { 0xdf7126ff };
+            circ!(x = x + 10, y = y + 1, d = 3, color = spark, absolute = absolute);
+        }
+    }
+}
+
+// A row of status effect icons starting at (x, y)
+pub fn render_status_effects(effects: &StatusEffects, x: i32, y: i32) {
+    for (i, effect) in effects.list.iter().enumerate() {
+        let x = x + i as i32 * 7;
+        let (icon, color): (&str, u32) = match effect.kind {
+            StatusEffectKind::Poison => ("P", 0x6abe30ff),
+            StatusEffectKind::Freeze => ("F", 0x5fcde4ff),
+            StatusEffectKind::Slow => ("S", 0x847e87ff),
+            StatusEffectKind::Burn => ("B", 0xdf7126ff),
+        };
+        rect!(x = x, y = y, w = 6, h = 7, color = color, border_radius = 2);
+        text!(icon, x = x + 2, y = y + 1, font = Font::S, color = 0x222034ff);
+    }
+}
+
+// Traps, doors, crates and other non-wall obstacles
+pub fn render_hazard(kind: ObstacleKind, x: i32, y: i32) {
+    let size = TILE_SIZE as u32;
+    match kind {
+        ObstacleKind::WallA | ObstacleKind::WallB => {}
+        ObstacleKind::SpikeTrap => {
+            rect!(x = x + 1, y = y + 1, w = size - 2, h = size - 2, color = 0x59565688, border_radius = 2);
+            for (dx, dy) in [(3, 3), (9, 3), (3, 9), (9, 9)] {
+                rect!(x = x + dx, y = y + dy, w = 4, h = 4, color = 0xcbdbfcff, border_radius = 1);
+            }
+        }
+        ObstacleKind::PressurePlate => {
+            rect!(x = x + 2, y = y + 2, w = size - 4, h = size - 4, color = 0x847e87ff, border_radius = 2);
+            rect!(x = x + 4, y = y + 4, w = size - 8, h = size - 8, color = 0x9badb7ff, border_radius = 1);
+        }
+        ObstacleKind::Door => sprite!("metal_block", x = x, y = y),
+        ObstacleKind::IceFloor => {
+            rect!(x = x, y = y, w = size, h = size, color = 0xcbdbfc66);
+        }
+        ObstacleKind::Crate => sprite!("crate", x = x, y = y),
+        ObstacleKind::OneWayDoor(direction) => {
+            let arrow = match direction {
+                Direction::Up => "^",
+                Direction::Down => "v",
+                Direction::Left => "<",
+                Direction::Right => ">",
+            };
+            rect!(x = x + 1, y = y + 1, w = size - 2, h = size - 2, color = 0x8f563bff, border_radius = 2);
+            text!(arrow, x = x + 6, y = y + 5, font = Font::M, color = 0xfbf236ff);
+        }
+    }
+}
+
 pub fn clickable(x: i32, y: i32, w: u32, h: u32) -> bool {
     let m = mouse(0);
     m.intersects_abs(x, y, w, h) && m.left.just_pressed()
@@ -197,3 +425,52 @@ pub fn hovered(x: i32, y: i32, w: u32, h: u32) -> bool {
     let m = mouse(0);
     m.intersects_abs(x, y, w, h)
 }
+
+pub fn render_leaderboard_entries(
+    leaderboard: &Leaderboard,
+    crawl_id: u32,
+    mut i: i32,
+    kind: LeaderboardKind,
+    name: &str,
+    x: i32,
+    y: i32, // 9
+) {
+    let mut rank = 0;
+    let mut prev_value = if kind.is_most() { u32::MAX } else { 0 };
+    for entry in leaderboard.entries(kind) {
+        // Only increment rank if the value changes
+        if kind.is_most() && entry.score < prev_value {
+            rank += 1;
+            prev_value = entry.score;
+        } else if !kind.is_most() && entry.score > prev_value {
+            rank += 1;
+            prev_value = entry.score;
+        } else if rank == 0 {
+            rank += 1;
+            prev_value = entry.score;
+        }
+        let color: u32 = if crawl_id == entry.crawl_id && tick() % 16 < 8 {
+            0x1e6f50ff
+        } else if entry.name == name {
+            0x6ecb62ff
+        } else {
+            0xacaabdff
+        };
+        text!(
+            "{} {}{:.8} {:>11} ",
+            rank,
+            if rank > 9 { "" } else { " " },
+            entry.name,
+            &format!("{}", entry.score);
+            absolute = true,
+            x = x + 8,
+            y = y + i * 10,
+            color = color
+        );
+
+        i += 1;
+        if i - 3 > 10 {
+            break;
+        }
+    }
+}
@@ -72,6 +72,16 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
     // Update turn
     state.turn.set(dungeon.turn);
 
+    // Log what happened on a new turn and float its damage numbers
+    let turn_key = (dungeon.crawl_id, dungeon.floor, dungeon.turn);
+    if state.combat_log.record(turn_key, &dungeon.events, tick()) {
+        let floating_texts = dungeon
+            .events
+            .iter()
+            .filter_map(|event| FloatingText::from_event(event, tick()));
+        state.floating_texts.extend(floating_texts);
+    }
+
     // Update achievements modal
     if dungeon.player.health == 0
         && state.turn.done()
@@ -104,6 +114,9 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
 
     // Update monster tweens
     if state.floor.get() != dungeon.floor || state.monsters.len() != dungeon.monsters.len() {
+        if state.floor.get() != dungeon.floor {
+            state.travel = None;
+        }
         state.floor.set(dungeon.floor);
         state.monsters.clear();
         for monster in &dungeon.monsters {
@@ -128,10 +141,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         }
     }
     if state.players[0].is_idle() {
-        for (monster, entity) in dungeon
+        for (i, (monster, entity)) in dungeon
             .monsters
             .iter()
             .zip(state.monsters.iter_mut())
+            .enumerate()
             .collect::<Vec<(_, _)>>()
         {
             entity.x.set(monster.x * TILE_SIZE);
@@ -139,9 +153,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
 
             // Monster "nudge" animation
             if !state.turn.done() && entity.x.done() && entity.y.done() {
-                let is_monster_stunned = monster.stun_dur > 0;
-                let is_player_hit = state.players[0].hp.get() > dungeon.player.health;
-                if is_player_hit && !is_monster_stunned {
+                let did_attack = dungeon
+                    .events
+                    .iter()
+                    .any(|event| matches!(event, TurnEvent::MonsterAttacked { monster, .. } if *monster == i));
+                if did_attack {
                     if dungeon.is_player(monster.x, monster.y - 1) {
                         entity.offset_y.set(-MOVE_Y_OFFSET);
                     }
@@ -168,61 +184,113 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         }
     }
 
-    // let did_turn_transition_end = state.turn.done();
-    // let was_last_exec_on_diff_turn = state.last_exec_turn.map_or(true, |t| t != dungeon.turn);
-    let did_exec_timeout = (tick() - state.last_exec_at) >= EXEC_TIMEOUT_DUR;
-    // let is_ready_to_exec =
-    //     did_turn_transition_end && (was_last_exec_on_diff_turn || did_exec_timeout);
-    let is_ready_to_exec = did_exec_timeout;
+    // A command is in flight until the view moves past the crawl, floor and action it was sent for.
+    // One that gets turned away never lands, so `poll_command_failure` stops waiting on it.
+    let exec_key = (dungeon.crawl_id, dungeon.floor, dungeon.actions);
+    let is_exec_pending = state.pending_exec == Some(exec_key);
+    let is_ready_to_exec = state.turn.done() && !is_exec_pending;
     let is_alive = dungeon.player.health > 0;
+    let daily = dungeon.mode.is_daily();
 
     // Handle player input
     let gp = gamepad(0);
 
-    // Hard reset game
-    if gp.start.just_pressed() && gp.select.pressed() {
-        client::commands::create_new_dungeon::exec(true);
-        state.last_exec_at = tick();
-        state.last_exec_turn = Some(dungeon.turn);
+    // Tap a tile to auto-walk there (the menubar and pause button have their own taps)
+    let m = mouse(0);
+    let (log_x, log_y, log_w, log_h) = combat_log_bounds();
+    let is_on_hud = m.position[1] - cam!().1 + (h / 2) as i32 >= (h - menubar_h as u32) as i32
+        || hovered(w as i32 - 18, 2, 16, 16)
+        || !state.combat_log.lines.is_empty() && hovered(log_x, log_y, log_w, log_h);
+    let is_modal_open = state.achievements_modal.is_some() || state.show_stats_modal;
+    if m.left.just_pressed() && is_alive && !is_on_hud && !is_modal_open {
+        let x = m.position[0].div_euclid(TILE_SIZE);
+        let y = m.position[1].div_euclid(TILE_SIZE);
+        if x >= 0 && y >= 0 && x < dungeon.width as i32 && y < dungeon.height as i32 {
+            state.travel = Some(TravelTarget::Tile(x, y));
+        }
+    }
+
+    // Hard reset game (Daily Dash only gets one attempt)
+    if gp.start.just_pressed() && gp.select.pressed() && !daily {
+        client::commands::create_new_dungeon::exec(true, false);
+        state.pending_exec = Some(exec_key);
+        state.travel = None;
     }
     // Dungeon controls
     else if is_ready_to_exec {
+        // Auto-walk to the exit key or stairs. Pressing START again heads for the nearest treasure instead.
+        if gp.start.just_pressed()
+            && state.achievements_modal.is_none()
+            && is_alive
+            && !dungeon.is_exit(dungeon.player.x, dungeon.player.y)
+        {
+            let objective = TravelTarget::objective(dungeon);
+            state.travel = if state.travel == Some(objective) {
+                Some(TravelTarget::NearestTreasure)
+            } else {
+                Some(objective)
+            };
+        }
         // Next floor or restart
-        if gp.start.just_pressed() && state.achievements_modal.is_none() {
-            client::commands::create_new_dungeon::exec(dungeon.player.health == 0);
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+        else if gp.start.just_pressed() && state.achievements_modal.is_none() {
+            if daily && !is_alive {
+                state.screen = Screen::SelectMode;
+            } else if is_alive {
+                client::commands::ascend_dungeon::exec(dungeon);
+            } else {
+                client::commands::create_new_dungeon::exec(true, daily);
+            }
+            state.pending_exec = Some(exec_key);
         }
         // Move
         else if gp.up.pressed() && is_alive {
-            client::commands::move_player::exec(Direction::Up);
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_player::exec(dungeon, Direction::Up);
+            state.pending_exec = Some(exec_key);
+            state.travel = None;
             if dungeon.is_position_blocked(dungeon.player.x, dungeon.player.y - 1) {
                 state.players[0].offset_y.set(-MOVE_Y_OFFSET);
             }
         } else if gp.down.pressed() && is_alive {
-            client::commands::move_player::exec(Direction::Down);
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_player::exec(dungeon, Direction::Down);
+            state.pending_exec = Some(exec_key);
+            state.travel = None;
             if dungeon.is_position_blocked(dungeon.player.x, dungeon.player.y + 1) {
                 state.players[0].offset_y.set(MOVE_Y_OFFSET);
             }
         } else if gp.left.pressed() && is_alive {
-            client::commands::move_player::exec(Direction::Left);
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_player::exec(dungeon, Direction::Left);
+            state.pending_exec = Some(exec_key);
+            state.travel = None;
             if dungeon.is_position_blocked(dungeon.player.x - 1, dungeon.player.y) {
                 state.players[0].offset_x.set(-MOVE_X_OFFSET);
             }
         } else if gp.right.pressed() && is_alive {
-            client::commands::move_player::exec(Direction::Right);
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_player::exec(dungeon, Direction::Right);
+            state.pending_exec = Some(exec_key);
+            state.travel = None;
             if dungeon.is_position_blocked(dungeon.player.x + 1, dungeon.player.y) {
                 state.players[0].offset_x.set(MOVE_X_OFFSET);
             }
         }
+        // Use item
+        else if let Some(slot) = [gp.a, gp.b, gp.x, gp.y]
+            .iter()
+            .position(|btn| btn.just_pressed())
+            .filter(|slot| *slot < dungeon.player.inventory.len() && is_alive)
+        {
+            client::commands::use_item::exec(dungeon, slot);
+            state.pending_exec = Some(exec_key);
+        }
+        // Auto-walk one move at a time, waiting for each move to land before planning the next
+        else if let Some(target) = state.travel {
+            match target.next_step(dungeon) {
+                Some(direction) => {
+                    client::commands::move_player::exec(dungeon, direction);
+                    state.pending_exec = Some(exec_key);
+                }
+                None => state.travel = None,
+            }
+        }
     }
 
     // Center camera on player
@@ -256,6 +324,28 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         sprite!("stairs_up", x = exit.0 * TILE_SIZE, y = exit.1 * TILE_SIZE)
     }
 
+    // Mark where the player is auto-walking to
+    if let Some((x, y)) = state.travel.and_then(|target| target.position(dungeon)) {
+        sprite!(
+            "dotted_tile_border",
+            x = x * TILE_SIZE,
+            y = y * TILE_SIZE,
+            opacity = 0.5,
+            fps = fps::FAST,
+        );
+    }
+
+    // Draw tiles monsters are about to hit
+    let danger_color: u32 = if (tick() / 8).is_multiple_of(2) {
+        0xea323c88
+    } else {
+        0xea323c44
+    };
+    for tile in &dungeon.danger_tiles {
+        #[rustfmt::skip]
+        rect!(x = tile.x * TILE_SIZE + 1, y = tile.y * TILE_SIZE + 1, w = TILE_SIZE - 2, h = TILE_SIZE - 2, color = danger_color, border_radius = 2);
+    }
+
     // Draw obstacles
     for obstacle in &dungeon.obstacles {
         match obstacle.kind {
@@ -274,12 +364,17 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                     fps = fps::MEDIUM
                 );
             }
+            kind => render_hazard(kind, obstacle.x * TILE_SIZE, obstacle.y * TILE_SIZE),
         }
     }
 
     // Draw player
     if dungeon.player.health > 0 {
-        let is_hit = state.players[0].hp.get() > dungeon.player.health;
+        let is_hit = tick().saturating_sub(state.combat_log.updated_at) < HIT_DUR
+            && dungeon
+                .events
+                .iter()
+                .any(|event| matches!(event, TurnEvent::PlayerDamaged { player: 0, .. }));
         let should_blink = is_hit && tick() % 12 < 6;
         let x = state.players[0].x.get();
         let y = state.players[0].y.get();
@@ -317,6 +412,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                 opacity = if should_blink { 0.1 } else { 1.0 }
             );
         }
+        render_status_effects(&dungeon.player.effects, x, y - 6);
     } else {
         sprite!(
             "tombstone",
@@ -342,6 +438,24 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         let x = entity.x.get() + entity.offset_x.get();
         let y = entity.y.get() + entity.offset_y.get() - 6;
         match monster.kind {
+            MonsterKind::YetiKing => {
+                let size = TILE_SIZE * monster.size();
+                ellipse!(
+                    x = x + 4,
+                    y = y + 20,
+                    w = size - 8,
+                    h = size - 16,
+                    color = SHADOW_COLOR,
+                );
+                sprite!(
+                    "ice_yeti",
+                    x = x,
+                    y = y - 6,
+                    fps = fps::MEDIUM,
+                    opacity = opacity,
+                    scale = monster.size()
+                );
+            }
             MonsterKind::IceYeti => {
                 ellipse!(
                     x = x + 2,
@@ -642,6 +756,47 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                     fps = fps::MEDIUM + 3,
                 );
             }
+            TreasureKind::Item(item) => {
+                ellipse!(
+                    x = (treasure.x * TILE_SIZE + 5) as f32 + (y_offset / 4.),
+                    y = (y + 12) as f32 + (y_offset / 4.),
+                    w = (TILE_SIZE - 9) as f32 - (y_offset / 2.),
+                    h = (TILE_SIZE - 12) as f32 - (y_offset / 2.),
+                    color = SHADOW_COLOR,
+                );
+                render_item(item, treasure.x * TILE_SIZE, y + y_offset as i32, false);
+            }
+            TreasureKind::Chest => {
+                ellipse!(
+                    x = (treasure.x * TILE_SIZE + 2) as f32 + (y_offset / 4.),
+                    y = (y + 12) as f32 + (y_offset / 4.),
+                    w = (TILE_SIZE - 4) as f32 - (y_offset / 2.),
+                    h = (TILE_SIZE - 12) as f32 - (y_offset / 2.),
+                    color = SHADOW_COLOR,
+                );
+                sprite!(
+                    "treasure_chest",
+                    x = treasure.x * TILE_SIZE,
+                    y = y as f32 + y_offset - 2.,
+                );
+            }
+        }
+    }
+
+    // Draw fog over tiles the player can't see, darker where they've never been
+    let fov = dungeon.field_of_view();
+    for y in 0..dungeon.height as i32 {
+        for x in 0..dungeon.width as i32 {
+            if fov.is_visible(x, y) {
+                continue;
+            }
+            let color = if dungeon.explored.contains(&(x, y)) {
+                0x00000088
+            } else {
+                0x000000ff
+            };
+            #[rustfmt::skip]
+            rect!(x = x * TILE_SIZE, y = y * TILE_SIZE, w = TILE_SIZE, h = TILE_SIZE, color = color);
         }
     }
 
@@ -682,12 +837,13 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         }
         let x = entity.x.get();
         let y = entity.y.get() - 8;
+        let bar_w = TILE_SIZE * monster.size();
 
         // Draw health bar background (black)
         rect!(
             x = x,
             y = y - 5,
-            w = TILE_SIZE,
+            w = bar_w,
             h = 5,
             color = 0x000000fa,
             border_radius = 2
@@ -697,7 +853,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         let spacing = 1;
         let segment_width = 2;
         let min_segment_width = 1;
-        let max_segments = ((TILE_SIZE + spacing) / (segment_width + spacing)) as i32;
+        let max_segments = ((bar_w + spacing) / (segment_width + spacing)) as i32;
 
         // Draw health segments
         let mut remaining_health = monster.health as i32;
@@ -746,8 +902,14 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
             remaining_strength -= segments_this_layer;
             layer += 1;
         }
+
+        // Draw status effects above the health bar
+        render_status_effects(&monster.effects, x, y - 13);
     }
 
+    // Damage numbers and pickups
+    render_floating_texts(&mut state.floating_texts);
+
     // Rain weather effect
     if dungeon.theme == DungeonThemeKind::Pirate {
         let t = tick();
@@ -835,7 +997,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
             if gp.left.just_pressed() {
                 state.leaderboard_kind = state.leaderboard_kind.next();
             }
-            if let Ok(leaderboard) = client::queries::global_leaderboard::fetch() {
+            let leaderboard = match dungeon.mode {
+                DungeonMode::Classic => client::queries::global_leaderboard::fetch(),
+                DungeonMode::Daily(day) => client::queries::daily_leaderboard::fetch(day),
+            };
+            if let Ok(leaderboard) = leaderboard {
                 rect!(absolute = true, w = w, h = h, color = 0x000000fa);
 
                 let slide_dot_y = h as i32 - (menubar_h + 34);
@@ -878,7 +1044,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                             let leaderboard_y = leaderboard_y + 4;
                             text!("#  PLAYER {:>7} FLOOR", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
                             i += 1;
-                            leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+                            render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
                         }
                     #[rustfmt::skip]
                         LeaderboardKind::MostGold => {
@@ -887,7 +1053,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                             let leaderboard_y = leaderboard_y + 4;
                             text!("#  PLAYER {:>8} GOLD", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
                             i += 1;
-                            leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+                            render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
                         }
                     #[rustfmt::skip]
                         LeaderboardKind::MostKills => {
@@ -896,7 +1062,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                             let leaderboard_y = leaderboard_y + 4;
                             text!("#  PLAYER {:>7} KILLS", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
                             i += 1;
-                            leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+                            render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
                         }
                     #[rustfmt::skip]
                         LeaderboardKind::LeastSteps => {
@@ -905,7 +1071,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
                             let leaderboard_y = leaderboard_y + 4;
                             text!("#  PLAYER {:>7} STEPS", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
                             i += 1;
-                            leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+                            render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
                         }
                 }
             }
@@ -1014,6 +1180,38 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         );
     }
 
+    // Hotbar
+    let slot_size = 18;
+    let hotbar_y = y - slot_size as i32 - 4;
+    for (slot, label) in ["A", "B", "X", "Y"].iter().enumerate() {
+        let x = 4 + (slot as i32 * (slot_size as i32 + 2));
+        rect!(
+            absolute = true,
+            x = x,
+            y = hotbar_y,
+            w = slot_size,
+            h = slot_size,
+            color = 0x000000aa,
+            border_radius = 2,
+            border_width = 1,
+            border_color = 0xacaabdff,
+        );
+        if let Some(item) = dungeon.player.inventory.get(slot) {
+            render_item(*item, x + 1, hotbar_y + 1, true);
+            if is_alive && is_ready_to_exec && clickable(x, hotbar_y, slot_size, slot_size) {
+                client::commands::use_item::exec(dungeon, slot);
+                state.pending_exec = Some(exec_key);
+            }
+        }
+        #[rustfmt::skip]
+        text!(label, absolute = true, x = x + 1, y = hotbar_y + 1, font = Font::S, color = 0xffffffaa);
+    }
+
+    // Shop (every few floors, at the exit)
+    if dungeon.is_shop_open() && primary_button("SHOP", w as i32 - 44, hotbar_y + 3, 40) {
+        state.screen = Screen::Shop(ShopContext { daily, cursor: 0 });
+    }
+
     // Menubar background
     rect!(
         absolute = true,
@@ -1080,7 +1278,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
             y = action_btn_text_y,
             font = Font::M,
         );
-        let action_btn_text = "Try again?";
+        let action_btn_text = if daily { "Back to menu" } else { "Try again?" };
         let action_btn_text_len = action_btn_text.len() as u32;
         let action_btn_text_w = action_btn_text_len * 5;
         let action_btn_text_x = 1 + action_btn_x + (action_btn_w / 2) - (action_btn_text_w / 2);
@@ -1105,7 +1303,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         let is_in_btn = mx >= hit_x0 && mx < hit_x1 && my >= hit_y0 && my < hit_y1;
         let is_modal_closed = state.achievements_modal.is_none();
         if m.left.just_pressed() && is_in_btn && is_modal_closed {
-            client::commands::create_new_dungeon::exec(true);
+            if daily {
+                state.screen = Screen::SelectMode;
+            } else {
+                client::commands::create_new_dungeon::exec(true, false);
+            }
         }
     }
     // Next floor button
@@ -1173,7 +1375,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         let hit_y1 = (action_btn_y + action_btn_h) as i32;
         let is_in_btn = mx >= hit_x0 && mx < hit_x1 && my >= hit_y0 && my < hit_y1;
         if m.left.just_pressed() && is_in_btn {
-            client::commands::create_new_dungeon::exec(false);
+            client::commands::ascend_dungeon::exec(dungeon);
         }
     }
     // CTA: Find exit
@@ -1226,7 +1428,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
             y = cta_text_y,
             font = Font::L,
         );
-        let cta_text = "Get the key";
+        let cta_text = if dungeon.exit_key.is_none() && dungeon.is_boss_floor() {
+            "Slay the boss"
+        } else {
+            "Get the key"
+        };
         let cta_text_len = cta_text.len() as u32;
         let cta_text_w = cta_text_len * 5;
         let cta_text_x = 1 + cta_x + (cta_w / 2) - (cta_text_w / 2);
@@ -1280,6 +1486,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         );
     }
 
+    // Combat log
+    if !state.show_stats_modal && state.achievements_modal.is_none() {
+        render_combat_log(&mut state.combat_log);
+    }
+
     // Achievements Modal
     if let Some(mut modal) = state.achievements_modal.take() {
         // current tick
@@ -1555,7 +1766,23 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
             text!("No stats yet!", absolute = true, font = Font::M, x = x, y = y, color = 0xe1e5d8ff);
         }
 
-        let mut y = h as i32 - 36;
+        let mut y = h as i32 - 52;
+
+        // Watch the crawl once it's over (replays are archived when the player dies)
+        if dungeon.player.health == 0 {
+            if secondary_button("WATCH REPLAY", modal_x, y, w - 8) {
+                state.screen = Screen::Replay(ReplayContext {
+                    crawl_id: dungeon.crawl_id,
+                    step: 0,
+                    paused: false,
+                    speed: 1,
+                    last_step_at: 0,
+                });
+                state.replay = None;
+                state.show_stats_modal = false;
+            }
+            y += 16;
+        }
 
         // Back to Select Mode screen
         if secondary_button("< BACK TO SELECT MODE", modal_x, y, w - 8) {
@@ -1564,20 +1791,22 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &Dungeon) {
         }
         y += 16;
 
-        // End crawl button
-        if negative_button("END CRAWL", modal_x, y, w - 8) {
-            client::commands::delete_dungeon::exec();
-            state.screen = Screen::SelectMode;
+        // End crawl button (Daily Dash runs can't be abandoned)
+        if !daily {
+            if negative_button("END CRAWL", modal_x, y, w - 8) {
+                client::commands::delete_dungeon::exec();
+                state.screen = Screen::SelectMode;
+            }
+            y += 14;
+            text!(
+                "(PROGRESS WILL BE LOST!)",
+                absolute = true,
+                x = modal_x + 3,
+                y = y,
+                font = Font::S,
+                color = 0xffffffaa
+            );
         }
-        y += 14;
-        text!(
-            "(PROGRESS WILL BE LOST!)",
-            absolute = true,
-            x = modal_x + 3,
-            y = y,
-            font = Font::S,
-            color = 0xffffffaa
-        );
     }
 
     // Swipe transition
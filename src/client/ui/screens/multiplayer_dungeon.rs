@@ -101,6 +101,16 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     // Update turn
     state.turn.set(dungeon.turn);
 
+    // Log what happened since the last move and float its damage numbers
+    let turn_key = (dungeon.crawl_id, dungeon.floor, dungeon.turn);
+    if state.combat_log.record(turn_key, &dungeon.events, tick()) {
+        let floating_texts = dungeon
+            .events
+            .iter()
+            .filter_map(|event| FloatingText::from_event(event, tick()));
+        state.floating_texts.extend(floating_texts);
+    }
+
     // // Update achievements modal
     // if dungeon.player.health == 0
     //     && state.turn.done()
@@ -158,12 +168,13 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
             })
         }
     }
-    for (i, ctx) in dungeon.player.players.values().enumerate() {
+    for i in 0..dungeon.player.players.len() {
         if state.players[i].is_idle() {
-            for (monster, entity) in dungeon
+            for (j, (monster, entity)) in dungeon
                 .monsters
                 .iter()
                 .zip(state.monsters.iter_mut())
+                .enumerate()
                 .collect::<Vec<(_, _)>>()
             {
                 entity.x.set(monster.x * TILE_SIZE);
@@ -171,9 +182,10 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
 
                 // Monster "nudge" animation
                 if !state.turn.done() && entity.x.done() && entity.y.done() {
-                    let is_monster_stunned = monster.stun_dur > 0;
-                    let is_player_hit = state.players[i].hp.get() < ctx.player.health;
-                    if is_player_hit && !is_monster_stunned {
+                    let did_attack = dungeon.events.iter().any(|event| {
+                        matches!(event, TurnEvent::MonsterAttacked { monster, player, .. } if *monster == j && *player == i)
+                    });
+                    if did_attack {
                         if dungeon.is_player(monster.x, monster.y - 1) {
                             entity.offset_y.set(-MOVE_Y_OFFSET);
                         }
@@ -208,11 +220,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     let Some(player_index) = dungeon.player.get_index(&user_id) else {
         return;
     };
-    let did_turn_transition_end = state.turn.done();
-    let was_last_exec_on_diff_turn = state.last_exec_turn.map_or(true, |t| t != dungeon.turn);
-    let did_exec_timeout = (tick() - state.last_exec_at) >= EXEC_TIMEOUT_DUR;
-    let is_ready_to_exec =
-        did_turn_transition_end && (was_last_exec_on_diff_turn || did_exec_timeout);
+    // A command is in flight until the view moves past the crawl, floor and round it was sent for.
+    // Only the player's own moves bump their next round, so other players' moves don't count.
+    let exec_key = (dungeon.crawl_id, dungeon.floor, ctx.next_round);
+    let is_exec_pending = state.pending_exec == Some(exec_key);
+    let is_ready_to_exec = state.turn.done() && !is_exec_pending;
     let is_alive = ctx.player.health > 0;
     let can_move_this_round = ctx.next_round == dungeon.round;
 
@@ -222,8 +234,8 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     // Hard reset game
     if gp.start.just_pressed() && gp.select.pressed() {
         client::commands::reset_multiplayer_dungeon::exec(dungeon.crawl_id);
-        state.last_exec_at = tick();
-        state.last_exec_turn = Some(dungeon.turn);
+        // A fresh crawl can look just like this one, so there's nothing to wait on
+        state.pending_exec = None;
         state.show_stats_modal = false;
     }
     // Dungeon controls
@@ -235,47 +247,30 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
             } else {
                 client::commands::ascend_multiplayer_dungeon::exec(dungeon.crawl_id);
             }
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            state.pending_exec = Some(exec_key);
         }
         // Move
         else if gp.up.pressed() && is_alive && can_move_this_round {
-            client::commands::move_multiplayer_dungeon_player::exec(
-                dungeon.crawl_id,
-                Direction::Up,
-            );
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_multiplayer_dungeon_player::exec(dungeon, Direction::Up);
+            state.pending_exec = Some(exec_key);
             if dungeon.is_position_blocked(ctx.player.x, ctx.player.y - 1) {
                 state.players[player_index].offset_y.set(-MOVE_Y_OFFSET);
             }
         } else if gp.down.pressed() && is_alive && can_move_this_round {
-            client::commands::move_multiplayer_dungeon_player::exec(
-                dungeon.crawl_id,
-                Direction::Down,
-            );
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_multiplayer_dungeon_player::exec(dungeon, Direction::Down);
+            state.pending_exec = Some(exec_key);
             if dungeon.is_position_blocked(ctx.player.x, ctx.player.y + 1) {
                 state.players[player_index].offset_y.set(MOVE_Y_OFFSET);
             }
         } else if gp.left.pressed() && is_alive && can_move_this_round {
-            client::commands::move_multiplayer_dungeon_player::exec(
-                dungeon.crawl_id,
-                Direction::Left,
-            );
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_multiplayer_dungeon_player::exec(dungeon, Direction::Left);
+            state.pending_exec = Some(exec_key);
             if dungeon.is_position_blocked(ctx.player.x - 1, ctx.player.y) {
                 state.players[player_index].offset_x.set(-MOVE_X_OFFSET);
             }
         } else if gp.right.pressed() && is_alive && can_move_this_round {
-            client::commands::move_multiplayer_dungeon_player::exec(
-                dungeon.crawl_id,
-                Direction::Right,
-            );
-            state.last_exec_at = tick();
-            state.last_exec_turn = Some(dungeon.turn);
+            client::commands::move_multiplayer_dungeon_player::exec(dungeon, Direction::Right);
+            state.pending_exec = Some(exec_key);
             if dungeon.is_position_blocked(ctx.player.x + 1, ctx.player.y) {
                 state.players[player_index].offset_x.set(MOVE_X_OFFSET);
             }
@@ -313,6 +308,17 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
         sprite!("stairs_up", x = exit.0 * TILE_SIZE, y = exit.1 * TILE_SIZE)
     }
 
+    // Draw tiles monsters are about to hit
+    let danger_color: u32 = if (tick() / 8).is_multiple_of(2) {
+        0xea323c88
+    } else {
+        0xea323c44
+    };
+    for tile in &dungeon.danger_tiles {
+        #[rustfmt::skip]
+        rect!(x = tile.x * TILE_SIZE + 1, y = tile.y * TILE_SIZE + 1, w = TILE_SIZE - 2, h = TILE_SIZE - 2, color = danger_color, border_radius = 2);
+    }
+
     // Draw obstacles
     for obstacle in &dungeon.obstacles {
         match obstacle.kind {
@@ -331,13 +337,17 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
                     fps = fps::MEDIUM
                 );
             }
+            kind => render_hazard(kind, obstacle.x * TILE_SIZE, obstacle.y * TILE_SIZE),
         }
     }
 
     // Draw players
     for (i, (player_id, ctx)) in dungeon.player.players.iter().enumerate() {
         if ctx.player.health > 0 {
-            let is_hit = state.players[i].hp.get() > ctx.player.health;
+            let is_hit = tick().saturating_sub(state.combat_log.updated_at) < HIT_DUR
+                && dungeon.events.iter().any(|event| {
+                    matches!(event, TurnEvent::PlayerDamaged { player, .. } if *player == i)
+                });
             let should_blink = is_hit && tick() % 12 < 6;
             let x = state.players[i].x.get();
             let y = state.players[i].y.get();
@@ -742,6 +752,47 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
                     fps = fps::MEDIUM + 3,
                 );
             }
+            TreasureKind::Item(item) => {
+                ellipse!(
+                    x = (treasure.x * TILE_SIZE + 5) as f32 + (y_offset / 4.),
+                    y = (y + 12) as f32 + (y_offset / 4.),
+                    w = (TILE_SIZE - 9) as f32 - (y_offset / 2.),
+                    h = (TILE_SIZE - 12) as f32 - (y_offset / 2.),
+                    color = SHADOW_COLOR,
+                );
+                render_item(item, treasure.x * TILE_SIZE, y + y_offset as i32, false);
+            }
+            TreasureKind::Chest => {
+                ellipse!(
+                    x = (treasure.x * TILE_SIZE + 2) as f32 + (y_offset / 4.),
+                    y = (y + 12) as f32 + (y_offset / 4.),
+                    w = (TILE_SIZE - 4) as f32 - (y_offset / 2.),
+                    h = (TILE_SIZE - 12) as f32 - (y_offset / 2.),
+                    color = SHADOW_COLOR,
+                );
+                sprite!(
+                    "treasure_chest",
+                    x = treasure.x * TILE_SIZE,
+                    y = y as f32 + y_offset - 2.,
+                );
+            }
+        }
+    }
+
+    // Draw fog over tiles the party can't see, darker where they've never been
+    let fov = dungeon.field_of_view();
+    for y in 0..dungeon.height as i32 {
+        for x in 0..dungeon.width as i32 {
+            if fov.is_visible(x, y) {
+                continue;
+            }
+            let color = if dungeon.explored.contains(&(x, y)) {
+                0x00000088
+            } else {
+                0x000000ff
+            };
+            #[rustfmt::skip]
+            rect!(x = x * TILE_SIZE, y = y * TILE_SIZE, w = TILE_SIZE, h = TILE_SIZE, color = color);
         }
     }
 
@@ -848,6 +899,9 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
         }
     }
 
+    // Damage numbers and pickups
+    render_floating_texts(&mut state.floating_texts);
+
     // Draw player emotes
     for player in &mut state.players {
         let x = player.x.get();
@@ -987,7 +1041,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     //                         let leaderboard_y = leaderboard_y + 4;
     //                         text!("#  PLAYER {:>7} FLOOR", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
     //                         i += 1;
-    //                         leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+    //                         render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
     //                     }
     //                 #[rustfmt::skip]
     //                     LeaderboardKind::MostGold => {
@@ -996,7 +1050,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     //                         let leaderboard_y = leaderboard_y + 4;
     //                         text!("#  PLAYER {:>8} GOLD", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
     //                         i += 1;
-    //                         leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+    //                         render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
     //                     }
     //                 #[rustfmt::skip]
     //                     LeaderboardKind::MostKills => {
@@ -1005,7 +1059,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     //                         let leaderboard_y = leaderboard_y + 4;
     //                         text!("#  PLAYER {:>7} KILLS", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
     //                         i += 1;
-    //                         leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+    //                         render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
     //                     }
     //                 #[rustfmt::skip]
     //                     LeaderboardKind::LeastSteps => {
@@ -1014,7 +1068,7 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
     //                         let leaderboard_y = leaderboard_y + 4;
     //                         text!("#  PLAYER {:>7} STEPS", ""; absolute = true, x = leaderboard_x + 8, y = leaderboard_y + i * 10);
     //                         i += 1;
-    //                         leaderboard.render_entries(dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
+    //                         render_leaderboard_entries(&leaderboard, dungeon.crawl_id, i, state.leaderboard_kind, user_id, leaderboard_x, leaderboard_y);
     //                     }
     //             }
     //         }
@@ -1410,6 +1464,11 @@ pub fn render(state: &mut LocalState, user_id: &str, dungeon: &MultiplayerDungeo
         );
     }
 
+    // Combat log
+    if !state.show_stats_modal {
+        render_combat_log(&mut state.combat_log);
+    }
+
     // // Achievements Modal
     // if let Some(mut modal) = state.achievements_modal.take() {
     //     // current tick
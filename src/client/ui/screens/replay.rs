@@ -0,0 +1,198 @@
+use super::*;
+
+// Playback speeds (actions per replay turn)
+const SPEEDS: &[u32] = &[1, 2, 4, 8];
+
+pub fn render(state: &mut LocalState, ctx: &mut ReplayContext, replay: &CrawlReplay) {
+    let [w, h] = canvas_size!();
+    let menubar_h = 40;
+    let gp = gamepad(0);
+
+    // Rebuild the simulated crawl when entering the screen or restarting
+    let mut dungeon = match state.replay.take() {
+        Some(dungeon) if dungeon.crawl_id == replay.crawl_id => dungeon,
+        _ => replay.simulate(ctx.step, |_| {}),
+    };
+
+    // Playback controls
+    if gp.a.just_pressed() {
+        ctx.paused = !ctx.paused;
+    }
+    if gp.up.just_pressed() {
+        ctx.speed = next_speed(ctx.speed);
+    }
+    let mut should_step = ctx.paused && gp.right.just_pressed();
+    if !ctx.paused && tick().saturating_sub(ctx.last_step_at) >= TURN_DUR / ctx.speed as usize {
+        should_step = true;
+    }
+    if should_step && ctx.step < replay.actions.len() {
+        replay.actions[ctx.step].apply(&mut dungeon, |_| {});
+        ctx.step += 1;
+        ctx.last_step_at = tick();
+    }
+
+    // Center camera on player
+    set_cam!(
+        x = dungeon.player.x * TILE_SIZE + (TILE_SIZE / 2),
+        y = dungeon.player.y * TILE_SIZE + (TILE_SIZE / 2) + menubar_h,
+    );
+
+    // Draw dungeon floor
+    let dungeon_theme = dungeon.theme.theme();
+    sprite!(
+        dungeon_theme.floor_sprite,
+        w = dungeon.width * TILE_SIZE as u32,
+        h = dungeon.height * TILE_SIZE as u32,
+        repeat = true,
+    );
+
+    // Draw exit
+    if let Some(exit) = &dungeon.exit {
+        sprite!("stairs_up", x = exit.0 * TILE_SIZE, y = exit.1 * TILE_SIZE)
+    }
+
+    // Draw obstacles
+    for obstacle in &dungeon.obstacles {
+        let name = match obstacle.kind {
+            ObstacleKind::WallA => dungeon_theme.block_a_sprite,
+            ObstacleKind::WallB => dungeon_theme.block_b_sprite,
+            kind => {
+                render_hazard(kind, obstacle.x * TILE_SIZE, obstacle.y * TILE_SIZE);
+                continue;
+            }
+        };
+        sprite!(name, x = obstacle.x * TILE_SIZE, y = obstacle.y * TILE_SIZE);
+    }
+
+    // Draw exit key
+    if let Some(exit_key) = &dungeon.exit_key {
+        sprite!(
+            "boss_key",
+            x = exit_key.0 * TILE_SIZE,
+            y = exit_key.1 * TILE_SIZE - 4
+        );
+    }
+
+    // Draw treasures
+    for treasure in &dungeon.treasures {
+        let name = match (&treasure.kind, treasure.value) {
+            (TreasureKind::Gold, 10) => "purple_gem",
+            (TreasureKind::Gold, _) => "coin",
+            (TreasureKind::Heal, _) => "full_heart",
+            (TreasureKind::HealthUp, _) => "super_heart",
+            (TreasureKind::Chest, _) => "treasure_chest",
+            (TreasureKind::Item(item), _) => {
+                render_item(
+                    *item,
+                    treasure.x * TILE_SIZE,
+                    treasure.y * TILE_SIZE - 4,
+                    false,
+                );
+                continue;
+            }
+        };
+        sprite!(
+            name,
+            x = treasure.x * TILE_SIZE,
+            y = treasure.y * TILE_SIZE - 4
+        );
+    }
+
+    // Draw monsters
+    for monster in &dungeon.monsters {
+        if monster.health == 0 {
+            continue;
+        }
+        let opacity = if monster.stun_dur > 0 { 0.5 } else { 1.0 };
+        sprite!(
+            monster_sprite(monster.kind),
+            x = monster.x * TILE_SIZE,
+            y = monster.y * TILE_SIZE - 6 * monster.size(),
+            fps = fps::FAST,
+            opacity = opacity,
+            scale = monster.size()
+        );
+    }
+
+    // Draw player
+    let (x, y) = (dungeon.player.x * TILE_SIZE, dungeon.player.y * TILE_SIZE);
+    if dungeon.player.health > 0 {
+        sprite!("hero", x = x, y = y - 4, fps = fps::FAST);
+    } else {
+        sprite!("tombstone", x = x, y = y - 5);
+    }
+
+    // Menubar
+    rect!(absolute = true, w = w, h = menubar_h, color = 0x000000ff);
+    #[rustfmt::skip]
+    text!("REPLAY #{:.8}", replay.crawl_id; absolute = true, x = 4, y = 4, font = Font::L);
+    #[rustfmt::skip]
+    text!("FLOOR {}  TURN {}", dungeon.floor + 1, dungeon.turn; absolute = true, x = 4, y = 16);
+    #[rustfmt::skip]
+    text!("HP {}/{}  ${}", dungeon.player.health, dungeon.player.max_health, dungeon.player.gold; absolute = true, x = 4, y = 26);
+
+    // Playback bar
+    let y = h as i32 - 28;
+    rect!(absolute = true, y = y, w = w, h = 28, color = 0x000000ff);
+    #[rustfmt::skip]
+    text!("{}/{}  {}x", ctx.step, replay.actions.len(), ctx.speed; absolute = true, x = 4, y = y + 2);
+    let y = y + 12;
+    let bw = (w - 16) / 4;
+    if secondary_button("< BACK", 4, y, bw) || gp.b.just_pressed() {
+        state.screen = if replay.mode.is_daily() {
+            Screen::DailyDash
+        } else {
+            Screen::Dungeon
+        };
+        return;
+    }
+    let label = if ctx.paused { "PLAY" } else { "PAUSE" };
+    if primary_button(label, 8 + bw as i32, y, bw) {
+        ctx.paused = !ctx.paused;
+    }
+    if secondary_button("STEP", 12 + bw as i32 * 2, y, bw) && ctx.step < replay.actions.len() {
+        ctx.paused = true;
+        replay.actions[ctx.step].apply(&mut dungeon, |_| {});
+        ctx.step += 1;
+    }
+    if secondary_button(
+        &format!("{}X", next_speed(ctx.speed)),
+        16 + bw as i32 * 3,
+        y,
+        bw,
+    ) {
+        ctx.speed = next_speed(ctx.speed);
+    }
+
+    // Restart from the first floor once playback finishes
+    if ctx.step >= replay.actions.len() && gp.start.just_pressed() {
+        ctx.step = 0;
+        return;
+    }
+
+    state.replay = Some(dungeon);
+}
+
+fn next_speed(speed: u32) -> u32 {
+    let i = SPEEDS.iter().position(|s| *s == speed).unwrap_or(0);
+    SPEEDS[(i + 1) % SPEEDS.len()]
+}
+
+fn monster_sprite(kind: MonsterKind) -> &'static str {
+    match kind {
+        MonsterKind::IceYeti => "ice_yeti",
+        MonsterKind::Snowman => "snowman",
+        MonsterKind::BlueBlob => "blue_blob",
+        MonsterKind::YellowBlob => "yellow_blob",
+        MonsterKind::RedBlob => "red_blob",
+        MonsterKind::OrangeGoblin => "orange_goblin",
+        MonsterKind::GreenGoblin => "goblin",
+        MonsterKind::Shade => "shade",
+        MonsterKind::Spider => "spider",
+        MonsterKind::Ghost => "ghost",
+        MonsterKind::SpectralGhost => "spectral_ghost",
+        MonsterKind::Zombie => "zombie",
+        MonsterKind::EvilTurbi => "evil_turbi",
+        MonsterKind::YetiKing => "ice_yeti",
+    }
+}
@@ -17,12 +17,37 @@ pub fn render(state: &mut LocalState, user_id: &str) {
         }
     } else {
         if secondary_button("1P DUNGEON CRAWL", x, y, w - 8) {
-            client::commands::create_new_dungeon::exec(true);
+            client::commands::create_new_dungeon::exec(true, false);
             state.screen = Screen::Dungeon;
         }
     }
     y += 16;
 
+    // One Daily Dash attempt per UTC day
+    let today = DungeonMode::day((time::now() / 1000) as u32);
+    let daily_dungeon = client::queries::player_daily_dungeon::fetch(user_id);
+    let attempted_today =
+        client::queries::player_daily_attempt::fetch(user_id).is_ok_and(|day| day == today);
+    match daily_dungeon {
+        Ok(dungeon) if dungeon.mode == DungeonMode::Daily(today) && dungeon.player.health > 0 => {
+            if primary_button("RESUME DAILY DASH", x, y, w - 8) {
+                state.screen = Screen::DailyDash;
+            }
+        }
+        _ if attempted_today => {
+            if secondary_button("DAILY DASH RESULTS", x, y, w - 8) {
+                state.screen = Screen::DailyDash;
+            }
+        }
+        _ => {
+            if secondary_button("DAILY DASH", x, y, w - 8) {
+                client::commands::create_new_dungeon::exec(true, true);
+                state.screen = Screen::DailyDash;
+            }
+        }
+    }
+    y += 16;
+
     if let Ok(crawl_id) = client::queries::current_multiplayer_dungeon_crawl_id::fetch(&user_id) {
         // Resume
         if primary_button("RESUME ONLINE CO-OP", x, y, w - 8) {
@@ -36,4 +61,10 @@ pub fn render(state: &mut LocalState, user_id: &str) {
             });
         }
     }
+    y += 16;
+
+    // Permanent upgrades for classic crawls
+    if secondary_button("UPGRADES", x, y, w - 8) {
+        state.screen = Screen::Upgrades(UpgradesContext { cursor: 0 });
+    }
 }
@@ -0,0 +1,65 @@
+use super::*;
+
+pub fn render(state: &mut LocalState, ctx: &mut ShopContext, dungeon: &Dungeon) {
+    reset_cam!();
+    let [w, h] = canvas_size!();
+    let gp = gamepad(0);
+
+    // The shop closes once the player moves on
+    let back = if ctx.daily {
+        Screen::DailyDash
+    } else {
+        Screen::Dungeon
+    };
+    if !dungeon.is_shop_open() {
+        state.screen = back;
+        return;
+    }
+
+    #[rustfmt::skip]
+    text!("SHOP", absolute = true, x = 4, y = 4, font = Font::L);
+    sprite!("coin", absolute = true, x = w as i32 - 56, y = 2);
+    #[rustfmt::skip]
+    text!("${:0>4}", dungeon.player.gold; absolute = true, x = w as i32 - 40, y = 6, font = Font::L);
+    #[rustfmt::skip]
+    text!("HP {}/{}  STR {}  ITEMS {}/{}", dungeon.player.health, dungeon.player.max_health, dungeon.player.strength, dungeon.player.inventory.len(), Player::MAX_ITEMS; absolute = true, x = 4, y = 20, font = Font::S);
+
+    // Keyboard / gamepad navigation
+    let len = ShopItem::ALL.len();
+    if gp.up.just_pressed() {
+        ctx.cursor = (ctx.cursor + len - 1) % len;
+    }
+    if gp.down.just_pressed() {
+        ctx.cursor = (ctx.cursor + 1) % len;
+    }
+
+    // Wares
+    let mut y = 32;
+    // Wait for each purchase to land before buying again (see the dungeon screen)
+    let exec_key = (dungeon.crawl_id, dungeon.floor, dungeon.actions);
+    let is_ready_to_exec = state.pending_exec != Some(exec_key);
+    for (i, item) in ShopItem::ALL.iter().enumerate() {
+        let price = item.price();
+        let label = format!("{:<12} ${:>3}", item.name(), price);
+        let can_afford = dungeon.player.gold >= price;
+        let did_click = if !can_afford {
+            negative_button(&label, 4, y, w - 8)
+        } else if i == ctx.cursor {
+            primary_button(&label, 4, y, w - 8)
+        } else {
+            secondary_button(&label, 4, y, w - 8)
+        };
+        let did_press = i == ctx.cursor && gp.a.just_pressed();
+        if (did_click || did_press) && can_afford && is_ready_to_exec {
+            ctx.cursor = i;
+            client::commands::purchase::exec(dungeon, *item);
+            state.pending_exec = Some(exec_key);
+        }
+        y += 16;
+    }
+
+    // Leave the shop
+    if secondary_button("< BACK", 4, h as i32 - 16, w - 8) || gp.b.just_pressed() {
+        state.screen = back;
+    }
+}
@@ -92,6 +92,11 @@ pub fn render(state: &mut LocalState, is_logged_in: bool) {
 
     // Handle user input
     if gamepad(0).start.just_pressed() || mouse(0).left.just_pressed() {
+        // Upgrade any files saved with older schema versions (once a session is enough)
+        if !state.migrated_player_files {
+            client::commands::migrate_player_files::exec();
+            state.migrated_player_files = true;
+        }
         state.screen = Screen::SelectMode;
         // state.screen = Screen::MultiplayerDungeonLobbies(MultiplayerDungeonLobbiesContext {
         //     cursor: 0,
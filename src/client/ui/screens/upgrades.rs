@@ -0,0 +1,68 @@
+use super::*;
+
+pub fn render(
+    state: &mut LocalState,
+    ctx: &mut UpgradesContext,
+    upgrades: &PlayerUpgrades,
+    total_stats: &DungeonStats,
+) {
+    reset_cam!();
+    let [w, h] = canvas_size!();
+    let gp = gamepad(0);
+
+    #[rustfmt::skip]
+    text!("UPGRADES", absolute = true, x = 4, y = 4, font = Font::L);
+    let essence = upgrades.essence(total_stats);
+    #[rustfmt::skip]
+    text!("ESSENCE {}", essence; absolute = true, x = 4, y = 20, font = Font::S);
+    #[rustfmt::skip]
+    text!("Earn 1 per floor cleared or kill", absolute = true, x = 4, y = 28, font = Font::S, color = 0xffffff80);
+
+    // Keyboard / gamepad navigation
+    let len = UpgradeKind::ALL.len();
+    if gp.up.just_pressed() {
+        ctx.cursor = (ctx.cursor + len - 1) % len;
+    }
+    if gp.down.just_pressed() {
+        ctx.cursor = (ctx.cursor + 1) % len;
+    }
+
+    // Upgrade tree
+    let mut y = 40;
+    let is_ready_to_exec = (tick() - state.last_exec_at) >= EXEC_TIMEOUT_DUR;
+    for (i, kind) in UpgradeKind::ALL.iter().enumerate() {
+        let level = upgrades.level(*kind);
+        let is_maxed = level >= kind.max_level();
+        let cost = kind.cost(level);
+        let can_unlock = !is_maxed && essence >= cost;
+        let label = format!("{} {}/{}", kind.name(), level, kind.max_level());
+        let did_click = if !can_unlock {
+            negative_button(&label, 4, y, w - 8)
+        } else if i == ctx.cursor {
+            primary_button(&label, 4, y, w - 8)
+        } else {
+            secondary_button(&label, 4, y, w - 8)
+        };
+        #[rustfmt::skip]
+        text!(kind.description(), absolute = true, x = 4, y = y + 14, font = Font::S);
+        let cost_text = if is_maxed {
+            "MAXED".to_string()
+        } else {
+            format!("COST {cost}")
+        };
+        #[rustfmt::skip]
+        text!(&cost_text, absolute = true, x = 4, y = y + 22, font = Font::S, color = 0xffffff80);
+        let did_press = i == ctx.cursor && gp.a.just_pressed();
+        if (did_click || did_press) && can_unlock && is_ready_to_exec {
+            ctx.cursor = i;
+            client::commands::unlock_upgrade::exec(*kind);
+            state.last_exec_at = tick();
+        }
+        y += 32;
+    }
+
+    // Back to mode select
+    if secondary_button("< BACK", 4, h as i32 - 16, w - 8) || gp.b.just_pressed() {
+        state.screen = Screen::SelectMode;
+    }
+}
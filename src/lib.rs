@@ -5,7 +5,7 @@ mod client;
 
 mod server;
 
-mod model;
+pub mod model;
 use model::*;
 
 turbo::cfg! {r#"
@@ -24,16 +24,31 @@ turbo::init! {
             Title,
             SelectMode,
             Dungeon,
+            DailyDash,
             MultiplayerDungeonLobbies(struct MultiplayerDungeonLobbiesContext {
                 cursor: usize,
                 selected: bool,
             }),
             MultiplayerDungeon(u32),
+            Upgrades(struct UpgradesContext {
+                cursor: usize,
+            }),
+            Shop(struct ShopContext {
+                daily: bool,
+                cursor: usize,
+            }),
+            Replay(struct ReplayContext {
+                crawl_id: u32,
+                step: usize,
+                paused: bool,
+                speed: u32,
+                last_step_at: usize,
+            }),
         },
         floor: Tween<u32>,
         turn: Tween<u32>,
         last_exec_at: usize,
-        last_exec_turn: Option<u32>,
+        pending_exec: Option<(u32, u32, u32)>,
         players: Vec<Entity>,
         monsters: Vec<Entity>,
         leaderboard_kind: LeaderboardKind,
@@ -45,6 +60,13 @@ turbo::init! {
         last_crawl_achievements_modal: u32,
         show_stats_modal: bool,
         last_channel_message: String,
+        replay: Option<Dungeon>,
+        travel: Option<TravelTarget>,
+        last_command_failure: Option<u32>,
+        toast: Option<Toast>,
+        combat_log: CombatLog,
+        floating_texts: Vec<FloatingText>,
+        migrated_player_files: bool,
     } = {
         client::ui::initialize()
     }
@@ -0,0 +1,106 @@
+use super::*;
+
+use std::fmt;
+
+// How a batch of bot crawls turned out
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BalanceReport {
+    // Floor each crawl ended on, counting from 1
+    pub floors: Vec<u32>,
+    pub gold: Vec<u32>,
+    pub steps: Vec<u32>,
+    // Number of crawls ended by each cause of death
+    pub deaths: BTreeMap<String, u32>,
+}
+impl BalanceReport {
+    pub fn new() -> Self {
+        Self {
+            floors: vec![],
+            gold: vec![],
+            steps: vec![],
+            deaths: BTreeMap::new(),
+        }
+    }
+    pub fn record(&mut self, dungeon: &Dungeon) {
+        self.floors.push(dungeon.floor + 1);
+        self.gold
+            .push(dungeon.stats.get(DungeonStatKind::GoldCollected));
+        self.steps
+            .push(dungeon.stats.get(DungeonStatKind::StepsMoved));
+        *self
+            .deaths
+            .entry(Self::cause_of_death(dungeon))
+            .or_insert(0) += 1;
+    }
+    // The monster that landed the final blow, or whatever else ended the crawl
+    pub fn cause_of_death(dungeon: &Dungeon) -> String {
+        if dungeon.player.health > 0 {
+            return "Survived".to_string();
+        }
+        MonsterKind::ALL
+            .iter()
+            .find(|kind| dungeon.stats.deaths_by_monster(**kind) > 0)
+            .map_or("Hazards and effects", |kind| kind.abbrev())
+            .to_string()
+    }
+}
+
+// (mean, median, 90th percentile, max)
+fn summarize(values: &[u32]) -> (f32, u32, u32, u32) {
+    if values.is_empty() {
+        return (0.0, 0, 0, 0);
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mean = sorted.iter().map(|n| *n as f32).sum::<f32>() / sorted.len() as f32;
+    let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+    (
+        mean,
+        percentile(50),
+        percentile(90),
+        sorted[sorted.len() - 1],
+    )
+}
+
+impl fmt::Display for BalanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let crawls = self.floors.len();
+        writeln!(f, "{crawls} crawls")?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<14}{:>8}{:>8}{:>8}{:>8}",
+            "", "mean", "median", "p90", "max"
+        )?;
+        for (name, values) in [
+            ("Floor reached", &self.floors),
+            ("Gold", &self.gold),
+            ("Steps", &self.steps),
+        ] {
+            let (mean, median, p90, max) = summarize(values);
+            writeln!(f, "{name:<14}{mean:>8.1}{median:>8}{p90:>8}{max:>8}")?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Floor reached")?;
+        let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+        for floor in &self.floors {
+            *counts.entry(*floor).or_insert(0) += 1;
+        }
+        let most = counts.values().copied().max().unwrap_or(1);
+        for (floor, count) in counts {
+            let bar = "#".repeat((count * 40).div_ceil(most));
+            writeln!(f, "{floor:>4} | {bar:<40} {count}")?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Cause of death")?;
+        let mut deaths: Vec<_> = self.deaths.iter().collect();
+        deaths.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+        for (cause, count) in deaths {
+            let share = *count as f32 * 100.0 / crawls.max(1) as f32;
+            writeln!(f, "  {cause:<22}{count:>6}{share:>7.1}%")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+// The pattern of tiles a boss hits when it attacks
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BossAttack {
+    // Every tile around it
+    Slam,
+    // Shockwaves along its rows and columns until they hit a wall
+    Shockwave,
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// How a boss fights until it drops below half health, or after
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BossPhase {
+    pub attack: BossAttack,
+    // Turns it lumbers towards players on between attacks
+    #[serde(default)]
+    pub pace: MonsterPace,
+}
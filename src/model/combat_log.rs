@@ -0,0 +1,48 @@
+use super::*;
+
+// Turn events the player has seen this crawl, oldest first, for the combat log panel
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Default)]
+pub struct CombatLog {
+    pub lines: Vec<String>,
+    // How many lines the panel is scrolled back from the newest
+    pub scroll: usize,
+    // When the last turn's events came in
+    pub updated_at: usize,
+    // The (crawl, floor, turn) the last events came from, so each turn is only logged once
+    last_turn: Option<(u32, u32, u32)>,
+}
+impl CombatLog {
+    pub const MAX_LINES: usize = 50;
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // Logs a turn's events unless they already were. Returns whether the turn was new.
+    pub fn record(&mut self, turn: (u32, u32, u32), events: &[TurnEvent], now: usize) -> bool {
+        if self.last_turn == Some(turn) {
+            return false;
+        }
+        // A new crawl starts with a clean log
+        if self.last_turn.map(|(crawl_id, _, _)| crawl_id) != Some(turn.0) {
+            self.lines.clear();
+        }
+        self.last_turn = Some(turn);
+        self.updated_at = now;
+        if !events.is_empty() {
+            self.lines.extend(events.iter().map(TurnEvent::to_string));
+            let overflow = self.lines.len().saturating_sub(Self::MAX_LINES);
+            self.lines.drain(..overflow);
+            self.scroll = 0;
+        }
+        true
+    }
+    // Scrolls back (positive) or forward (negative), keeping a full panel of `rows` in view
+    pub fn scroll_by(&mut self, delta: i32, rows: usize) {
+        let max_scroll = self.lines.len().saturating_sub(rows) as i32;
+        self.scroll = (self.scroll as i32 + delta).clamp(0, max_scroll) as usize;
+    }
+    // The lines that fit in `rows` at the current scroll, oldest first
+    pub fn visible(&self, rows: usize) -> &[String] {
+        let end = self.lines.len().saturating_sub(self.scroll);
+        &self.lines[end.saturating_sub(rows)..end]
+    }
+}
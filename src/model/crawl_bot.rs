@@ -0,0 +1,38 @@
+use super::*;
+
+// A strategy that plays solo crawls on its own, used to measure game balance
+pub trait CrawlBot {
+    fn name(&self) -> &'static str;
+    // The next action to try. Actions that fail are dropped, so bots shouldn't keep repeating them.
+    fn next_action(&mut self, dungeon: &Dungeon) -> CrawlAction;
+    // Plays until the player dies or `max_actions` actions have been tried.
+    // Returns the actions that went through, in the order of a `CrawlReplay`.
+    fn play(
+        &mut self,
+        dungeon: &mut Dungeon,
+        max_actions: usize,
+        log: fn(&str),
+    ) -> Vec<CrawlAction> {
+        let mut actions = vec![];
+        for _ in 0..max_actions {
+            if dungeon.player.health == 0 {
+                break;
+            }
+            let action = self.next_action(dungeon);
+            if action.apply(dungeon, log) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+}
+
+pub const CRAWL_BOTS: &[&str] = &["random", "pathing"];
+
+pub fn crawl_bot_by_name(name: &str, seed: u32) -> Option<Box<dyn CrawlBot>> {
+    match name {
+        "random" => Some(Box::new(RandomBot::new(seed))),
+        "pathing" => Some(Box::new(PathingBot::new(seed))),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub enum CrawlAction {
+    Move(Direction),
+    NextFloor,
+    UseItem(usize),
+    Purchase(ShopItem),
+}
+impl CrawlAction {
+    // Applies the action the same way the server commands do
+    pub fn apply(&self, dungeon: &mut Dungeon, log: fn(&str)) -> bool {
+        match self {
+            Self::Move(direction) => dungeon.take_turn(*direction, log).is_ok(),
+            Self::NextFloor => {
+                if !dungeon.is_exit(dungeon.player.x, dungeon.player.y) {
+                    log("P1 has not reached the exit.");
+                    return false;
+                }
+                dungeon.next_floor();
+                dungeon.populate_floor(log);
+                true
+            }
+            Self::UseItem(slot) => dungeon.use_item(*slot, log).is_ok(),
+            Self::Purchase(item) => dungeon.purchase(*item, log).is_ok(),
+        }
+    }
+}
+
+// Everything needed to re-simulate a solo crawl from its first floor
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct CrawlReplay {
+    pub crawl_id: u32,
+    pub mode: DungeonMode,
+    pub seed: u32,
+    // Lifetime stats when the crawl began (YETI RAGE depends on them)
+    pub total_stats: DungeonStats,
+    // Permanent upgrades applied when the crawl began
+    pub upgrades: PlayerUpgrades,
+    pub actions: Vec<CrawlAction>,
+}
+impl CrawlReplay {
+    pub fn new(dungeon: &Dungeon, upgrades: &PlayerUpgrades) -> Self {
+        Self {
+            crawl_id: dungeon.crawl_id,
+            mode: dungeon.mode,
+            seed: dungeon.seed,
+            total_stats: dungeon.total_stats.clone(),
+            upgrades: upgrades.clone(),
+            actions: vec![],
+        }
+    }
+    // Recreates the first floor of the crawl
+    pub fn start(&self, log: fn(&str)) -> Dungeon {
+        let mut dungeon = Dungeon::new(
+            self.crawl_id,
+            self.seed,
+            self.total_stats.clone(),
+            PlayerAchievements::empty(),
+        );
+        dungeon.mode = self.mode;
+        self.upgrades.apply(&mut dungeon);
+        dungeon.populate_floor(log);
+        dungeon
+    }
+    // Recreates the crawl after the first `n` actions
+    pub fn simulate(&self, n: usize, log: fn(&str)) -> Dungeon {
+        let mut dungeon = self.start(log);
+        for action in self.actions.iter().take(n) {
+            action.apply(&mut dungeon, log);
+        }
+        dungeon
+    }
+    // Re-simulates the crawl and returns every leaderboard entry whose score doesn't match
+    pub fn verify(&self, leaderboard: &Leaderboard, log: fn(&str)) -> Vec<ScoreMismatch> {
+        let dungeon = self.simulate(self.actions.len(), log);
+        let is_over = dungeon.player.health == 0;
+        LeaderboardKind::ALL
+            .iter()
+            .filter_map(|kind| {
+                let entry = leaderboard.find(*kind, |e| e.crawl_id == self.crawl_id)?;
+                let replayed = is_over.then(|| kind.score(&dungeon.stats));
+                (replayed != Some(entry.score)).then_some(ScoreMismatch {
+                    kind: *kind,
+                    submitted: entry.score,
+                    replayed,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Schema for CrawlReplay {
+    const VERSION: u16 = 1;
+}
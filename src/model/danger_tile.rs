@@ -0,0 +1,12 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// A tile a ranged monster attack will hit on the next monster turn
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct DangerTile {
+    pub x: i32,
+    pub y: i32,
+    pub damage: u32,
+    pub kind: MonsterKind,
+}
@@ -0,0 +1,10 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// A monster's pace from `from_floor` (counting from 0) on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DeepPace {
+    pub from_floor: u32,
+    pub pace: MonsterPace,
+}
@@ -2,10 +2,31 @@ use super::*;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq,
+)]
 pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
+impl Direction {
+    pub const ALL: &'static [Self] = &[Self::Up, Self::Down, Self::Left, Self::Right];
+    // Position one step away from (x, y) in this direction
+    pub fn step(&self, x: i32, y: i32) -> (i32, i32) {
+        match self {
+            Self::Up => (x, y - 1),
+            Self::Down => (x, y + 1),
+            Self::Left => (x - 1, y),
+            Self::Right => (x + 1, y),
+        }
+    }
+    // Direction of a single step from one tile to an adjacent one
+    pub fn between(from: (i32, i32), to: (i32, i32)) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|dir| dir.step(from.0, from.1) == to)
+    }
+}
@@ -2,7 +2,7 @@ use super::*;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub struct Dungeon {
     pub crawl_id: u32,
     pub theme: DungeonThemeKind,
@@ -21,714 +21,338 @@ pub struct Dungeon {
     // TODO: move achievements to own struct/file
     pub unlocked: PlayerAchievements,
     pub all_unlocked: PlayerAchievements,
+    pub mode: DungeonMode,
+    pub seed: u32,
+    pub rng: Rng,
+    pub danger_tiles: Vec<DangerTile>,
+    // Tiles the player has seen on this floor
+    pub explored: BTreeSet<(i32, i32)>,
+    // Actions taken this crawl. Unlike `turn` it counts purchases and descents too and never resets,
+    // so commands can say which action they follow.
+    pub actions: u32,
+    // What happened on the last turn
+    pub events: Vec<TurnEvent>,
+    // False for crawls from before replays were recorded. They carry on unrecorded and unverified.
+    pub replayable: bool,
 }
 impl Dungeon {
-    pub fn move_player(&mut self, direction: Direction, log: fn(&str)) -> bool {
-        if self.player.health == 0 {
-            log("P1 is dead.");
-            return false;
+    // The shop opens at the exit of every Nth floor
+    pub const SHOP_INTERVAL: u32 = 5;
+    // Every Nth floor is a boss floor
+    pub const BOSS_INTERVAL: u32 = 10;
+    pub const MAX_HEALTH_LIMIT: u32 = 99;
+    // How many tiles away the player can see
+    pub const SIGHT_RADIUS: i32 = 4;
+    pub fn new(
+        crawl_id: u32,
+        seed: u32,
+        total_stats: DungeonStats,
+        all_unlocked: PlayerAchievements,
+    ) -> Self {
+        let w = 5;
+        let h = 5;
+        let is_winter = true;
+        let mut rng = Rng::new(seed);
+        Self {
+            crawl_id,
+            theme: if is_winter {
+                DungeonThemeKind::Arctic
+            } else {
+                DungeonThemeKind::Castle
+            },
+            floor: 0,
+            turn: 0,
+            width: w,
+            height: h,
+            player: Player {
+                x: rng.range(w) as i32,
+                y: rng.range(h) as i32,
+                health: 10,
+                max_health: 10,
+                strength: 1,
+                gold: 0,
+                direction: Direction::Down,
+                inventory: vec![],
+                effects: StatusEffects::new(),
+            },
+            monsters: vec![],
+            treasures: vec![],
+            obstacles: vec![],
+            exit: None,
+            exit_key: None,
+            stats: DungeonStats::new(),
+            total_stats,
+            unlocked: PlayerAchievements::empty(),
+            all_unlocked,
+            mode: DungeonMode::Classic,
+            seed,
+            rng,
+            danger_tiles: vec![],
+            explored: BTreeSet::new(),
+            actions: 0,
+            events: vec![],
+            replayable: true,
         }
-
-        let Player { x, y, .. } = self.player;
-        let (new_x, new_y) = match direction {
-            Direction::Up => (x, y - 1),
-            Direction::Down => (x, y + 1),
-            Direction::Left => (x - 1, y),
-            Direction::Right => (x + 1, y),
+    }
+    // Daily Dash crawls share the day's seed and ignore lifetime stats so every run is equal
+    pub fn daily(crawl_id: u32, day: u32, all_unlocked: PlayerAchievements) -> Self {
+        let seed = DungeonMode::daily_seed(day);
+        let mut dungeon = Self::new(crawl_id, seed, DungeonStats::new(), all_unlocked);
+        dungeon.mode = DungeonMode::Daily(day);
+        dungeon
+    }
+    // Clears the current floor and moves down to the next one
+    pub fn next_floor(&mut self) {
+        // Remove exit
+        self.exit = None;
+
+        // Clear monsters, treasures, and obstacles
+        self.monsters.clear();
+        self.treasures.clear();
+        self.obstacles.clear();
+        self.danger_tiles.clear();
+        self.explored.clear();
+        self.events.clear();
+
+        // Reroll scrolls only work on the first floor
+        self.player
+            .inventory
+            .retain(|item| *item != ItemKind::RerollScroll);
+
+        // Increase floor
+        self.floor += 1;
+        self.increment_stats(DungeonStatKind::FloorsCleared, 1);
+
+        // Each floor is generated from the seed and floor number alone
+        self.rng = Rng::for_floor(self.seed, self.floor);
+
+        // Update dungeon theme
+        let is_winter = true;
+        self.theme = if is_winter {
+            DungeonThemeKind::WINTER[self.rng.index(DungeonThemeKind::WINTER.len())]
+        } else {
+            DungeonThemeKind::ALL[self.rng.index(DungeonThemeKind::ALL.len())]
         };
 
-        if self.is_out_of_bounds(new_x, new_y) {
-            log("P1 cannot move out-of-bounds");
-            return false;
+        // Embiggen every 4 floors
+        if self.floor.is_multiple_of(4) {
+            self.width += 1;
+            self.height += 1;
         }
 
-        if self.is_obstacle(new_x, new_y) {
-            let did_kill_super_ghost = self
-                .monsters
-                .iter()
-                .any(|m| m.kind == MonsterKind::SpectralGhost && m.health == 0);
-            if !did_kill_super_ghost {
-                log("P1 cannot move through obstacle");
-                return false;
-            }
-        }
-
-        // do an attack if cooldown
-        if self.is_monster(new_x, new_y) {
-            let self_ptr = self as *mut Self;
-            if let Some(monster) = self
-                .monsters
-                .iter_mut()
-                .find(|m| m.x == new_x && m.y == new_y && m.health > 0)
-            {
-                // Swap positions with the stunned monsters
-                if monster.stun_dur > 0 {
-                    std::mem::swap(&mut self.player.x, &mut monster.x);
-                    std::mem::swap(&mut self.player.y, &mut monster.y);
-                    return true;
-                }
+        // Reset turn
+        self.turn = 0;
+        self.actions += 1;
 
-                let monster_name = monster.kind.abbrev();
-                let msg = format!("P1 attacks {}!", monster_name);
-                log(&msg);
-                let amount = self.player.strength;
-                let msg = format!("P1 did {amount} damage.");
-                log(&msg);
-                monster.stun_dur = 2;
-                let prev_monster_health = monster.health;
-                monster.health = monster.health.saturating_sub(amount);
-                let damage = prev_monster_health.abs_diff(monster.health);
-
-                // Use unsafe to bypass the borrow checker and safely call other mut methods
-                unsafe {
-                    (*self_ptr).increment_stats(DungeonStatKind::DamageDealt, damage);
-
-                    if monster.health == 0 {
-                        let msg = format!("{} defeated!", monster_name);
-                        log(&msg);
-                        (*self_ptr).increment_stats(DungeonStatKind::Defeated(monster.kind), 1);
-                    }
-                }
-            }
-
-            // If all monsters are defeated, spawn a treasure
-            if self.monsters.iter().all(|m| m.health == 0) {
-                let max_hp_limit = 99;
-                let player_max_hp_limit = max_hp_limit.min(if self.player.strength == 1 {
-                    // players w normal strength can get max hp up the current floor
-                    self.floor + 1
-                } else {
-                    // players w increased strength can only get max hp 1/2 the current floor
-                    (self.floor + 1) / 2
-                });
-                if self.player.health < player_max_hp_limit {
-                    self.treasures.push(Treasure {
-                        x: new_x,
-                        y: new_y,
-                        value: 1,
-                        kind: TreasureKind::HealthUp,
-                    });
-                } else {
-                    self.treasures.push(Treasure {
-                        x: new_x,
-                        y: new_y,
-                        value: 2,
-                        kind: TreasureKind::Heal,
-                    });
-                }
-                // match os::server::random_number::<u8>() % 4 {
-                //     3 | 2 => {
-                //         self.treasures.push(Treasure {
-                //             x: new_x,
-                //             y: new_y,
-                //             value: 2,
-                //             kind: TreasureKind::HealthUp,
-                //         });
-                //     }
-                //     1 => {
-                //         self.treasures.push(Treasure {
-                //             x: new_x,
-                //             y: new_y,
-                //             value: 50,
-                //             kind: TreasureKind::Gold,
-                //         });
-                //     }
-                //     _ => {
-                //         self.treasures.push(Treasure {
-                //             x: new_x,
-                //             y: new_y,
-                //             value: 1,
-                //             kind: TreasureKind::Heal,
-                //         });
-                //     }
-                // }
-            }
-            return true; // Player doesn't move into the monster's position
+        // Daily Dash players all start each floor from the same spot
+        if self.mode.is_daily() {
+            self.player.x = self.rng.range(self.width) as i32;
+            self.player.y = self.rng.range(self.height) as i32;
         }
-
-        // Player moved
-        let msg = format!("P1 moved {direction:?}.");
-        log(&msg);
-
-        self.player.x = new_x;
-        self.player.y = new_y;
-        self.player.direction = direction;
-        self.increment_stats(DungeonStatKind::StepsMoved, 1);
-
-        // Player collected treasure
-        if self.is_treasure(new_x, new_y) {
-            if let Some(treasure) = self.treasures.iter().find(|m| m.x == new_x && m.y == new_y) {
-                let amount = treasure.value;
-                match treasure.kind {
-                    TreasureKind::Gold => {
-                        self.player.gold += amount;
-                        self.increment_stats(DungeonStatKind::GoldCollected, amount);
-                        let msg = format!("Got treasure! +${amount}");
-                        log(&msg);
-                    }
-                    TreasureKind::Heal => {
-                        let prev_player_health = self.player.health;
-                        self.player.health =
-                            (self.player.health + amount).min(self.player.max_health);
-                        let recovered_health = prev_player_health.abs_diff(self.player.health);
-                        self.increment_stats(DungeonStatKind::HealthRecovered, recovered_health);
-                        let msg = format!("Recovered {} HP!", recovered_health);
-                        log(&msg);
-                    }
-                    TreasureKind::HealthUp => {
-                        self.player.max_health += 1;
-                        let prev_player_health = self.player.health;
-                        self.player.health =
-                            (self.player.health + amount).min(self.player.max_health);
-                        let recovered_health = prev_player_health.abs_diff(self.player.health);
-                        self.increment_stats(DungeonStatKind::HealthRecovered, recovered_health);
-                        let msg = format!("Health Up! Recovered {} HP!", recovered_health);
-                        log(&msg);
-                    }
-                }
+    }
+    // Adds monsters, treasures, the exit key, and obstacles to the current floor
+    pub fn populate_floor(&mut self, log: fn(&str)) {
+        // YETI RAGE
+        if self.total_stats.monster_kills(MonsterKind::IceYeti) >= 50 {
+            if self.floor == 19 && self.player.max_health <= 10 {
+                log("YETI RAGE ACTIVATED");
+                self.player.strength = 1 + (self.player.gold / 100);
             }
-            self.treasures.retain_mut(|t| t.x != new_x || t.y != new_y);
-        }
-
-        if self.is_exit_key(new_x, new_y) {
-            let msg = "Found exit key.".to_string();
-            log(&msg);
-            self.exit_key = None;
-            let (max_x, max_y) = self.bounds();
-            // Initialize exit position at least 8 tiles away from player
-            let min_distance = (self.width.min(self.height) / 2) as i32;
-            loop {
-                let x = os::server::random_number::<i32>().abs() % max_x;
-                let y = os::server::random_number::<i32>().abs() % max_y;
-                let dx = (x - new_x).abs();
-                let dy = (y - new_y).abs();
-                if dx + dy >= min_distance && !self.is_position_occupied(x, y) {
-                    self.exit = Some((x, y));
-                    break;
-                }
+            if self.floor > 19 && self.player.strength > 1 {
+                log("YETI RAGE INCREASED");
+                self.player.strength = 1 + (self.player.gold / 100);
             }
-            let msg = "Hidden stairs appeared!".to_string();
-            log(&msg);
         }
 
-        return true;
-    }
-    pub fn move_monsters(&mut self, log: fn(&str)) {
-        let mut player = self.player.clone();
-        let mut monsters = self.monsters.clone();
-        let mut n = 0;
-
-        monsters.retain_mut(|monster| {
-            n += 1;
-            let i = n - 1;
-            let (mx, my) = (monster.x, monster.y);
-
-            // Skip dead monsters (but leave them in the dungeon)
-            if monster.health == 0 {
-                return true;
-            }
-            // Killed mid-loop during another monster action
-            if self.monsters[i].health == 0 {
-                return true;
-            }
+        // Get the dungeon bounds
+        let (max_x, max_y) = self.bounds();
 
-            // Skip stunned monsters
-            if monster.stun_dur > 0 {
-                monster.stun_dur = monster.stun_dur.saturating_sub(1);
-                self.monsters[i] = monster.clone();
-                return true;
-            }
+        let magic_ratio = ((max_x * max_y) / 40) as usize;
+
+        // Boss floors are an open arena where the boss guards the exit key
+        if self.is_boss_floor() {
+            log("Summoning the boss...");
+            self.spawn_boss(MonsterKind::YetiKing);
+            log("Randomizing treasures...");
+            let num_treasures = magic_ratio + (self.floor as usize / 2);
+            self.spawn_treasures(num_treasures);
+            self.explore();
+            return;
+        }
 
-            // If the monster is adjacent to the player, it attacks
-            if (mx - player.x).abs() + (my - player.y).abs() == 1 {
-                let monster_name = monster.kind.abbrev();
-                let msg = format!("{} attacks!", monster_name);
-                log(&msg);
-                if self.is_player(mx, my - 1) {
-                    monster.direction = Direction::Up;
+        // After first floor, add monsters and treasures
+        if self.floor > 0 {
+            log("Randomizing monsters...");
+            let num_monsters = 2 + magic_ratio;
+            let is_winter = true;
+            let monster_weights =
+                MonsterDef::spawn_weights(SpawnTable::Solo, self.theme, self.floor, is_winter);
+            self.spawn_monsters(num_monsters, &monster_weights);
+
+            for monster in self.monsters.iter_mut() {
+                // Yetis power scale with the floor
+                if let MonsterKind::IceYeti = monster.kind {
+                    monster.strength += self.floor / 10;
                 }
-                if self.is_player(mx, my + 1) {
-                    monster.direction = Direction::Down;
-                }
-                if self.is_player(mx - 1, my) {
-                    monster.direction = Direction::Left;
-                }
-                if self.is_player(mx + 1, my) {
-                    monster.direction = Direction::Right;
-                }
-                let prev_player_health = player.health;
-                player.health = player.health.saturating_sub(monster.strength);
-                let damage = prev_player_health.abs_diff(player.health);
-                self.increment_stats(DungeonStatKind::DamageTaken, damage);
 
-                let msg = format!("{} did {} damage.", monster_name, damage);
-                log(&msg);
-                if player.health == 0 {
-                    let msg = "P1 died.".to_string();
-                    log(&msg);
-                    self.increment_stats(DungeonStatKind::DefeatedBy(monster.kind), 1);
-                }
-                return true;
+                // After level 30, everything has +1 hp
+                monster.strength += self.floor / 30;
             }
 
-            // Movement based on monster kind
-            let k = if monster.kind == MonsterKind::EvilTurbi {
-                MonsterKind::by_index(os::server::random_number())
-            } else {
-                monster.kind
-            };
-            let (dir, mx, my) = match k {
-                MonsterKind::BlueBlob | MonsterKind::YellowBlob | MonsterKind::RedBlob => {
-                    let dx = player.x - mx;
-                    let dy = player.y - my;
-
-                    // When player is 2 or fewer spaces away, chase them
-                    if dx.abs() <= 2 && dy.abs() <= 2 {
-                        let (dir, mx, my) = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                            (false, _, false) => (Direction::Up, mx, my - 1),
-                            (false, _, true) => (Direction::Down, mx, my + 1),
-                            (true, false, _) => (Direction::Left, mx - 1, my),
-                            (true, true, _) => (Direction::Right, mx + 1, my),
-                        };
-                        if self.is_position_occupied(mx, my) {
-                            return true;
-                        }
-                        (dir, mx, my)
-                    }
-                    // Otherwise, move in a random direction
-                    else {
-                        let (dir, mx, my) = match os::server::random_number::<usize>() % 4 {
-                            0 => (Direction::Up, mx, my - 1),
-                            1 => (Direction::Down, mx, my + 1),
-                            2 => (Direction::Left, mx - 1, my),
-                            _ => (Direction::Right, mx + 1, my),
-                        };
-                        if self.is_position_occupied(mx, my) {
-                            return true;
-                        }
-                        (dir, mx, my)
-                    }
-                }
-                MonsterKind::Spider => {
-                    // Moves up to 3 spaces in one direction towards the player every 3 turns
-                    if self.turn % 3 != 0 {
-                        return true;
-                    }
-
-                    let dx = player.x - mx;
-                    let dy = player.y - my;
-
-                    // Attempt to move up to 3 spaces towards player
-                    let steps = 3.min(dx.abs().max(dy.abs()));
-
-                    let mut new_mx = mx;
-                    let mut new_my = my;
-                    let mut dir = monster.direction;
-
-                    for s in (1..=steps).rev() {
-                        let (dir_next, mx_next, my_next) =
-                            match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                                (false, _, false) => (Direction::Up, mx, my - s),
-                                (false, _, true) => (Direction::Down, mx, my + s),
-                                (true, false, _) => (Direction::Left, mx - s, my),
-                                (true, true, _) => (Direction::Right, mx + s, my),
-                            };
-
-                        if !self.is_position_occupied(mx_next, my_next) {
-                            new_mx = mx_next;
-                            new_my = my_next;
-                            dir = dir_next;
-                            break;
-                        }
-                    }
-
-                    (dir, new_mx, new_my)
-                }
-                // Moves towards the player every other turn
-                // Can phase through obstacles
-                MonsterKind::Shade => {
-                    if self.turn % 2 != 0 {
-                        return true;
-                    }
-
-                    let dx = player.x - mx;
-                    let dy = player.y - my;
-
-                    let steps = 1;
-
-                    let (dir, mx, my) = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                        (false, _, false) => (Direction::Up, mx, my - steps),
-                        (false, _, true) => (Direction::Down, mx, my + steps),
-                        (true, false, _) => (Direction::Left, mx - steps, my),
-                        (true, true, _) => (Direction::Right, mx + steps, my),
-                    };
-
-                    if self.is_monster(mx, my) {
-                        return true;
-                    }
-
-                    (dir, mx, my)
-                }
-                // Ghosts moves away from the player
-                // Spectral Ghosts move towards the player if they are nearby
-                // Both can phase through obstacles
-                // Move towards any adjacent ghosts, then prioritize moving in relation to the player
-                MonsterKind::Ghost | MonsterKind::SpectralGhost => {
-                    let mut dir = Direction::Down;
-                    let mut mx = monster.x;
-                    let mut my = monster.y;
-
-                    // First, check for nearby ghosts to absorb
-                    let mut did_find_nearby_ghost = false;
-                    for dx in -3..=3_i32 {
-                        for dy in -3..=3_i32 {
-                            if dx == 0 && dy == 0 {
-                                continue;
-                            }
-                            let dir_ = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                                (false, _, false) => Direction::Up,
-                                (false, _, true) => Direction::Down,
-                                (true, false, _) => Direction::Left,
-                                (true, true, _) => Direction::Right,
-                            };
-                            let mx_ = monster.x + dx;
-                            let my_ = monster.y + dy;
-                            if self.monsters.iter().any(|m| {
-                                m.health > 0
-                                    && m.kind == MonsterKind::Ghost
-                                    && m.x == mx_
-                                    && m.y == my_
-                            }) {
-                                did_find_nearby_ghost = true;
-                                // Move one space towards nearby ghost
-                                dir = dir_;
-                                let dx = dx.checked_div(dx.abs()).unwrap_or(0);
-                                if dx != 0 {
-                                    mx = monster.x + dx;
-                                } else {
-                                    let dy = dy.checked_div(dy.abs()).unwrap_or(0);
-                                    my = monster.y + dy;
-                                }
-                                break;
-                            }
-                        }
-                    }
-
-                    // Either run from or move towards the player
-                    // let mut did_react_to_player = false;
-                    if !did_find_nearby_ghost {
-                        let (steps, dist, range) = if monster.kind == MonsterKind::Ghost {
-                            if self.turn % 4 == 0 {
-                                return true;
-                            }
-                            (1, 1, 1)
-                        } else {
-                            if self.turn % 4 == 0 {
-                                return true;
-                            }
-                            (1, 1, 4)
-                        };
-                        for _ in 0..steps {
-                            let dx = player.x - mx;
-                            let dy = player.y - my;
-                            let dx_abs = dx.abs();
-                            let dy_abs = dy.abs();
-                            if dx_abs > range && dy_abs > range {
-                                continue;
-                            }
-                            let x_or_y = if dx_abs == dy_abs {
-                                os::server::random_number::<i32>().abs() % 2 == 0
-                            } else {
-                                dx_abs > dy_abs
-                            };
-                            let (dir_, mx_, my_) = match (x_or_y, dx > 0, dy > 0) {
-                                (false, _, false) => (Direction::Up, mx, my - dist),
-                                (false, _, true) => (Direction::Down, mx, my + dist),
-                                (true, false, _) => (Direction::Left, mx - dist, my),
-                                (true, true, _) => (Direction::Right, mx + dist, my),
-                            };
-
-                            dir = dir_;
-                            mx = mx_;
-                            my = my_;
-                        }
-                    }
-
-                    // Didn't move
-                    if monster.x == mx && monster.y == my {
-                        return true;
-                    }
-
-                    if self.is_player(mx, my) || self.is_exit(mx, my) {
-                        return true;
-                    }
-
-                    // "Absorb" any non-dead ghost in the same position
-                    if let Some(idx) = self.monsters.iter().position(|m| {
-                        m.health > 0 && m.x == mx && m.y == my && m.kind == MonsterKind::Ghost
-                    }) {
-                        // Remove ghost
-                        self.monsters[idx].health = 0;
-                        // Increase stats and upgrade to Spectral Ghost
-                        if monster.kind != MonsterKind::EvilTurbi {
-                            monster.kind = MonsterKind::SpectralGhost;
-                        }
-                        monster.strength *= 2;
-                        monster.max_health *= 2;
-                        monster.health = monster.max_health;
-                    }
-
-                    // Returns false for dead monsters
-                    if self.is_monster(mx, my) {
-                        return true;
-                    }
-
-                    (dir, mx, my)
-                }
-                // Chase players within range, otherwise, moves randomly every other turn
-                MonsterKind::Zombie => {
-                    let range = 4;
-                    // Moves towards the player each turn
-                    let dx = player.x - mx;
-                    let dy = player.y - my;
-                    let dx_abs = dx.abs();
-                    let dy_abs = dy.abs();
-                    if dx_abs + dy_abs > range {
-                        if self.turn % 2 == 0 {
-                            return true;
-                        }
-                        let (dir, mx, my) = match os::server::random_number::<usize>() % 4 {
-                            0 => (Direction::Up, mx, my - 1),
-                            1 => (Direction::Down, mx, my + 1),
-                            2 => (Direction::Left, mx - 1, my),
-                            _ => (Direction::Right, mx + 1, my),
-                        };
-                        if self.is_position_occupied(mx, my) {
-                            return true;
-                        }
-                        (dir, mx, my)
-                    } else {
-                        let move_y = || {
-                            if dy < 0 {
-                                (Direction::Up, mx, my - 1)
-                            } else {
-                                (Direction::Down, mx, my + 1)
-                            }
-                        };
-                        let move_x = || {
-                            if dx < 0 {
-                                (Direction::Left, mx - 1, my)
-                            } else {
-                                (Direction::Right, mx + 1, my)
-                            }
-                        };
-                        let all = if dx.abs() > dy.abs() {
-                            [move_x(), move_y()]
-                        } else {
-                            [move_y(), move_x()]
-                        };
-                        if let Some(a) = all.iter().find(|a| !self.is_position_occupied(a.1, a.2)) {
-                            *a
-                        } else {
-                            return true;
-                        }
-                    }
-                }
-                MonsterKind::IceYeti => {
-                    match self.floor + 1 >= 75 {
-                        // Stop every third turn
-                        true => {
-                            if self.turn % 3 == 0 {
-                                return true;
-                            }
-                        }
-                        // Move every third turn
-                        false => {
-                            if self.turn % 3 != 0 {
-                                return true;
-                            }
-                        }
-                    }
-
-                    let dx = player.x - mx;
-                    let dy = player.y - my;
-
-                    // Attempt to move up to 2 spaces towards player
-                    let steps = 2.min(dx.abs().max(dy.abs()));
-
-                    let mut new_mx = mx;
-                    let mut new_my = my;
-                    let mut dir = monster.direction;
-
-                    for s in (1..=steps).rev() {
-                        let (try_dir, try_x, try_y) = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                            (false, _, false) => (Direction::Up, mx, my - s),
-                            (false, _, true) => (Direction::Down, mx, my + s),
-                            (true, false, _) => (Direction::Left, mx - s, my),
-                            (true, true, _) => (Direction::Right, mx + s, my),
-                        };
-
-                        if !self.is_position_occupied(try_x, try_y) {
-                            new_mx = try_x;
-                            new_my = try_y;
-                            dir = try_dir;
-                            break;
-                        }
-                    }
-
-                    // If no change occurred, fallback to previous single-step logic
-                    if new_mx == mx && new_my == my {
-                        let x_move = if dx < 0 {
-                            (Direction::Left, mx - 1, my)
-                        } else {
-                            (Direction::Right, mx + 1, my)
-                        };
-                        let y_move = if dy < 0 {
-                            (Direction::Up, mx, my - 1)
-                        } else {
-                            (Direction::Down, mx, my + 1)
-                        };
-
-                        let moves = if dx.abs() > dy.abs() {
-                            [x_move, y_move]
-                        } else {
-                            [y_move, x_move]
-                        };
-
-                        if let Some(next) = moves
-                            .iter()
-                            .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
-                            .copied()
-                        {
-                            next
-                        } else {
-                            return true;
-                        }
-                    } else {
-                        (dir, new_mx, new_my)
-                    }
-                }
-                MonsterKind::Snowman => {
-                    // Move every other turn
-                    if self.turn % 2 != 0 {
-                        return true;
-                    }
-
-                    // Moves towards the exit, the stairs, or the player
-                    let mut next = (monster.direction, mx, my);
-                    for (tx, ty) in [
-                        self.exit.unwrap_or((player.x, player.y)),
-                        self.exit_key.unwrap_or((player.x, player.y)),
-                        (player.x, player.y),
-                    ] {
-                        let dx = tx - mx;
-                        let dy = ty - my;
-
-                        let x = if dx < 0 {
-                            (Direction::Left, mx - 1, my)
-                        } else {
-                            (Direction::Right, mx + 1, my)
-                        };
-                        let y = if dy < 0 {
-                            (Direction::Up, mx, my - 1)
-                        } else {
-                            (Direction::Down, mx, my + 1)
-                        };
-
-                        let moves = if dx.abs() > dy.abs() { [x, y] } else { [y, x] };
-
-                        let Some((d, x, y)) = moves
-                            .iter()
-                            .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
-                            .copied()
-                        else {
-                            continue;
-                        };
-                        next = (d, x, y);
-                    }
-                    next
-                }
-                _ => {
-                    // Moves towards the player each turn
-                    let dx = player.x - mx;
-                    let dy = player.y - my;
-
-                    let x = if dx < 0 {
-                        (Direction::Left, mx - 1, my)
-                    } else {
-                        (Direction::Right, mx + 1, my)
-                    };
-                    let y = if dy < 0 {
-                        (Direction::Up, mx, my - 1)
-                    } else {
-                        (Direction::Down, mx, my + 1)
-                    };
-
-                    let moves = if dx.abs() > dy.abs() { [x, y] } else { [y, x] };
+            log("Randomizing treasures...");
+            let num_treasures = magic_ratio + (self.floor as usize / 2);
+            self.spawn_treasures(num_treasures);
+        }
 
-                    let Some(next) = moves
-                        .iter()
-                        .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
-                        .copied()
-                    else {
-                        return true;
-                    };
-                    next
-                }
-            };
+        // Initialize exit_key position away from the player
+        log("Initializing exit key position...");
+        self.spawn_exit_key();
 
-            if self.is_out_of_bounds(mx, my) {
-                return true;
-            }
+        log("Randomizing obstacles...");
+        self.spawn_obstacles();
 
-            monster.x = mx;
-            monster.y = my;
-            monster.direction = dir;
-            self.monsters[i] = monster.clone();
+        log("Stamping prefab rooms...");
+        self.spawn_prefab_room();
 
-            return true;
-        });
+        log("Placing hazards...");
+        self.spawn_hazards();
 
-        self.player = player;
+        self.explore();
     }
-    pub fn is_player(&self, x: i32, y: i32) -> bool {
-        self.player.x == x && self.player.y == y
+    pub fn move_player(&mut self, direction: Direction, log: fn(&str)) -> bool {
+        self.player_turn(0, direction, log).is_ok()
     }
-    pub fn bounds(&self) -> (i32, i32) {
-        let max_x = self.width as i32 - 1;
-        let max_y = self.height as i32 - 1;
-        (max_x, max_y)
+    pub fn move_monsters(&mut self, log: fn(&str)) {
+        self.monsters_turn(log)
     }
-    pub fn is_out_of_bounds(&self, x: i32, y: i32) -> bool {
-        let (min_x, min_y) = (0, 0);
-        let (max_x, max_y) = self.bounds();
-        x < min_x || y < min_y || x > max_x || y > max_y
+    // Turns away commands sent for another crawl, or for an action that was already taken
+    pub fn check_command(&self, crawl_id: u32, actions: u32) -> Result<(), StaleCommand> {
+        if crawl_id != self.crawl_id {
+            return Err(StaleCommand::WrongCrawl {
+                expected: self.crawl_id,
+                received: crawl_id,
+            });
+        }
+        if actions < self.actions {
+            return Err(StaleCommand::Duplicate { received: actions });
+        }
+        if actions > self.actions {
+            return Err(StaleCommand::OutOfOrder {
+                expected: self.actions,
+                received: actions,
+            });
+        }
+        Ok(())
     }
-    pub fn is_obstacle(&self, x: i32, y: i32) -> bool {
-        self.obstacles.iter().any(|obs| obs.x == x && obs.y == y)
+    // Moves the player, then the monsters unless the player reached the exit
+    pub fn take_turn(&mut self, direction: Direction, log: fn(&str)) -> Result<(), &'static str> {
+        self.events.clear();
+        self.player_turn(0, direction, log)?;
+        self.end_turn(log);
+        Ok(())
     }
-    pub fn is_monster(&self, x: i32, y: i32) -> bool {
-        self.monsters
-            .iter()
-            .any(|mon| mon.x == x && mon.y == y && mon.health > 0)
+    // Uses the item in a hotbar slot, then moves the monsters
+    pub fn use_item(&mut self, slot: usize, log: fn(&str)) -> Result<(), &'static str> {
+        let is_reroll = self.player.inventory.get(slot) == Some(&ItemKind::RerollScroll);
+        self.events.clear();
+        self.player_use_item(0, slot, log)?;
+        // A rerolled floor starts over, so it doesn't cost a turn
+        if !is_reroll {
+            self.end_turn(log);
+        } else {
+            self.actions += 1;
+        }
+        Ok(())
     }
-    pub fn is_treasure(&self, x: i32, y: i32) -> bool {
-        self.treasures.iter().any(|t| t.x == x && t.y == y)
+    pub fn is_boss_floor(&self) -> bool {
+        (self.floor + 1).is_multiple_of(Self::BOSS_INTERVAL)
     }
-    pub fn is_exit_key(&self, x: i32, y: i32) -> bool {
-        self.exit_key.map_or(false, |a| a.0 == x && a.1 == y)
+    // Players can visit the shop while standing on the exit of every Nth floor
+    pub fn is_shop_open(&self) -> bool {
+        self.player.health > 0
+            && self.is_exit(self.player.x, self.player.y)
+            && (self.floor + 1).is_multiple_of(Self::SHOP_INTERVAL)
     }
-    pub fn is_exit(&self, x: i32, y: i32) -> bool {
-        self.exit.map_or(false, |a| a.0 == x && a.1 == y)
+    // Spends gold on a shop item. Doesn't take a turn.
+    pub fn purchase(&mut self, item: ShopItem, log: fn(&str)) -> Result<(), &'static str> {
+        if !self.is_shop_open() {
+            log("The shop is closed.");
+            return Err("The shop is closed");
+        }
+        if !ShopItem::ALL.contains(&item) {
+            log(&format!("{} is not for sale", item.name()));
+            return Err("That isn't for sale");
+        }
+        let price = item.price();
+        if self.player.gold < price {
+            log(&format!("Not enough gold for {}", item.name()));
+            return Err("Not enough gold");
+        }
+        let player = &mut self.player;
+        match item {
+            ShopItem::Heal => {
+                if player.health >= player.max_health {
+                    log("P1 is already at full health");
+                    return Err("Already at full health");
+                }
+                let recovered_health = player.max_health - player.health;
+                player.health = player.max_health;
+                self.increment_stats(DungeonStatKind::HealthRecovered, recovered_health);
+            }
+            ShopItem::MaxHealth => {
+                if player.max_health >= Self::MAX_HEALTH_LIMIT {
+                    log("P1 can't get any healthier");
+                    return Err("Max HP can't go any higher");
+                }
+                player.max_health += 1;
+                player.health += 1;
+            }
+            ShopItem::Strength => player.strength += 1,
+            ShopItem::Item(kind) => {
+                if player.inventory.len() >= Player::MAX_ITEMS {
+                    log("Inventory full!");
+                    return Err("Inventory full");
+                }
+                player.inventory.push(kind);
+            }
+        }
+        self.player.gold -= price;
+        self.increment_stats(DungeonStatKind::GoldSpent, price);
+        self.increment_stats(DungeonStatKind::ItemsPurchased, 1);
+        self.actions += 1;
+        log(&format!("Bought {} for ${price}", item.name()));
+        Ok(())
     }
-    pub fn is_position_blocked(&self, x: i32, y: i32) -> bool {
-        self.is_obstacle(x, y) || self.is_monster(x, y) || self.is_player(x, y)
+    fn end_turn(&mut self, log: fn(&str)) {
+        if !self.is_exit(self.player.x, self.player.y) {
+            log("Moving monsters...");
+            self.move_monsters(log);
+        } else {
+            log("P1 reached exit.");
+        }
+        self.turn += 1;
+        self.actions += 1;
+        self.explore();
+    }
+    // What the player can see from where they stand
+    pub fn field_of_view(&self) -> FieldOfView {
+        let origin = (self.player.x, self.player.y);
+        let is_opaque = |x, y| {
+            self.obstacle_at(x, y)
+                .is_some_and(|obs| obs.kind.is_solid())
+        };
+        FieldOfView::new(
+            self.width,
+            self.height,
+            origin,
+            Self::SIGHT_RADIUS,
+            is_opaque,
+        )
     }
-    pub fn is_position_occupied(&self, x: i32, y: i32) -> bool {
-        self.is_position_blocked(x, y)
-            || self.is_treasure(x, y)
-            || self.is_exit_key(x, y)
-            || self.is_exit(x, y)
+    // Remembers every tile in sight
+    pub fn explore(&mut self) {
+        let visible: Vec<(i32, i32)> = self.field_of_view().tiles().collect();
+        self.explored.extend(visible);
     }
     pub fn increment_stats(&mut self, kind: DungeonStatKind, amount: u32) {
         if amount > 0 {
@@ -737,3 +361,106 @@ impl Dungeon {
         }
     }
 }
+impl DungeonRules for Dungeon {
+    fn clock(&self) -> u32 {
+        self.turn
+    }
+    fn floor(&self) -> u32 {
+        self.floor
+    }
+    fn theme(&self) -> DungeonThemeKind {
+        self.theme
+    }
+    fn width(&self) -> u32 {
+        self.width
+    }
+    fn height(&self) -> u32 {
+        self.height
+    }
+    fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+    fn obstacles_mut(&mut self) -> &mut Vec<Obstacle> {
+        &mut self.obstacles
+    }
+    fn monsters(&self) -> &[Monster] {
+        &self.monsters
+    }
+    fn monsters_mut(&mut self) -> &mut Vec<Monster> {
+        &mut self.monsters
+    }
+    fn treasures(&self) -> &[Treasure] {
+        &self.treasures
+    }
+    fn treasures_mut(&mut self) -> &mut Vec<Treasure> {
+        &mut self.treasures
+    }
+    fn exit_key(&self) -> Option<(i32, i32)> {
+        self.exit_key
+    }
+    fn set_exit_key(&mut self, exit_key: Option<(i32, i32)>) {
+        self.exit_key = exit_key;
+    }
+    fn exit(&self) -> Option<(i32, i32)> {
+        self.exit
+    }
+    fn set_exit(&mut self, exit: Option<(i32, i32)>) {
+        self.exit = exit;
+    }
+    fn player_count(&self) -> usize {
+        1
+    }
+    fn player(&self, _i: usize) -> &Player {
+        &self.player
+    }
+    fn player_mut(&mut self, _i: usize) -> &mut Player {
+        &mut self.player
+    }
+    fn increment_player_stats(&mut self, _i: usize, kind: DungeonStatKind, amount: u32) {
+        self.increment_stats(kind, amount);
+    }
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+    fn danger_tiles(&self) -> &[DangerTile] {
+        &self.danger_tiles
+    }
+    fn danger_tiles_mut(&mut self) -> &mut Vec<DangerTile> {
+        &mut self.danger_tiles
+    }
+    fn events_mut(&mut self) -> &mut Vec<TurnEvent> {
+        &mut self.events
+    }
+    fn reroll_floor(&mut self, log: fn(&str)) -> bool {
+        self.monsters.clear();
+        self.treasures.clear();
+        self.obstacles.clear();
+        self.danger_tiles.clear();
+        self.explored.clear();
+        self.exit = None;
+        self.exit_key = None;
+        self.populate_floor(log);
+        true
+    }
+}
+
+impl Schema for Dungeon {
+    // v2 added seeds, inventories, status effects and everything else a crawl tracks since.
+    // v1 crawls get a fresh seed and carry on unrecorded.
+    const VERSION: u16 = 2;
+    fn migrate(version: u16, data: &[u8]) -> std::io::Result<Self> {
+        let migrated = match version {
+            1 => DungeonV1::try_from_slice(data).map(Self::from),
+            2 => return Self::try_from_slice(data),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported dungeon version {version}"),
+            )),
+        };
+        // Older crawls start out knowing only what's in sight
+        migrated.map(|mut dungeon| {
+            dungeon.explore();
+            dungeon
+        })
+    }
+}
@@ -0,0 +1,25 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq,
+)]
+pub enum DungeonMode {
+    Classic,
+    // Daily Dash for the given UTC day (days since the unix epoch)
+    Daily(u32),
+}
+impl DungeonMode {
+    pub const SECS_PER_DAY: u32 = 86_400;
+    pub fn is_daily(&self) -> bool {
+        matches!(self, Self::Daily(_))
+    }
+    pub fn day(secs_since_unix_epoch: u32) -> u32 {
+        secs_since_unix_epoch / Self::SECS_PER_DAY
+    }
+    // Every Daily Dash crawl on the same UTC day shares this seed
+    pub fn daily_seed(day: u32) -> u32 {
+        Rng::new(day).next_u32()
+    }
+}
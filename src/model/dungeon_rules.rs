@@ -0,0 +1,1574 @@
+use super::*;
+
+use std::collections::HashSet;
+
+// Movement, combat, and monster AI shared by single-player and multiplayer dungeons.
+// Each dungeon type exposes its floor state and players, and gets the rules for free.
+pub trait DungeonRules {
+    // Turn counter that drives monster movement cadence (turn or round)
+    fn clock(&self) -> u32;
+    fn floor(&self) -> u32;
+    fn theme(&self) -> DungeonThemeKind;
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+    fn obstacles(&self) -> &[Obstacle];
+    fn obstacles_mut(&mut self) -> &mut Vec<Obstacle>;
+    fn monsters(&self) -> &[Monster];
+    fn monsters_mut(&mut self) -> &mut Vec<Monster>;
+    fn treasures(&self) -> &[Treasure];
+    fn treasures_mut(&mut self) -> &mut Vec<Treasure>;
+    fn exit_key(&self) -> Option<(i32, i32)>;
+    fn set_exit_key(&mut self, exit_key: Option<(i32, i32)>);
+    fn exit(&self) -> Option<(i32, i32)>;
+    fn set_exit(&mut self, exit: Option<(i32, i32)>);
+    fn player_count(&self) -> usize;
+    fn player(&self, i: usize) -> &Player;
+    fn player_mut(&mut self, i: usize) -> &mut Player;
+    fn increment_player_stats(&mut self, i: usize, kind: DungeonStatKind, amount: u32);
+    fn rng(&mut self) -> &mut Rng;
+    // Tiles ranged monsters will hit on the next monster turn
+    fn danger_tiles(&self) -> &[DangerTile];
+    fn danger_tiles_mut(&mut self) -> &mut Vec<DangerTile>;
+    // What happened this turn, for clients to show
+    fn events_mut(&mut self) -> &mut Vec<TurnEvent>;
+    // Whether floors can drop items for the inventory
+    fn spawns_items(&self) -> bool {
+        true
+    }
+    // 1 in N floors get a prefab room
+    fn prefab_room_odds(&self) -> u32 {
+        3
+    }
+    // Regenerates the current floor from scratch (returns false if unsupported)
+    fn reroll_floor(&mut self, _log: fn(&str)) -> bool {
+        false
+    }
+
+    fn is_player(&self, x: i32, y: i32) -> bool {
+        (0..self.player_count()).any(|i| {
+            let player = self.player(i);
+            player.x == x && player.y == y
+        })
+    }
+    fn bounds(&self) -> (i32, i32) {
+        let max_x = self.width() as i32 - 1;
+        let max_y = self.height() as i32 - 1;
+        (max_x, max_y)
+    }
+    fn is_out_of_bounds(&self, x: i32, y: i32) -> bool {
+        let (min_x, min_y) = (0, 0);
+        let (max_x, max_y) = self.bounds();
+        x < min_x || y < min_y || x > max_x || y > max_y
+    }
+    fn obstacle_at(&self, x: i32, y: i32) -> Option<&Obstacle> {
+        self.obstacles().iter().find(|obs| obs.x == x && obs.y == y)
+    }
+    // Whether a solid obstacle blocks (x, y)
+    fn is_obstacle(&self, x: i32, y: i32) -> bool {
+        self.obstacle_at(x, y)
+            .is_some_and(|obs| obs.kind.is_solid())
+    }
+    fn is_monster(&self, x: i32, y: i32) -> bool {
+        self.monsters()
+            .iter()
+            .any(|mon| mon.occupies(x, y) && mon.health > 0)
+    }
+    fn is_treasure(&self, x: i32, y: i32) -> bool {
+        self.treasures().iter().any(|t| t.x == x && t.y == y)
+    }
+    fn is_exit_key(&self, x: i32, y: i32) -> bool {
+        self.exit_key().is_some_and(|a| a.0 == x && a.1 == y)
+    }
+    fn is_exit(&self, x: i32, y: i32) -> bool {
+        self.exit().is_some_and(|a| a.0 == x && a.1 == y)
+    }
+    fn is_firepit(&self, x: i32, y: i32) -> bool {
+        self.obstacle_at(x, y)
+            .is_some_and(|obs| obs.kind == ObstacleKind::WallB)
+    }
+    fn is_position_blocked(&self, x: i32, y: i32) -> bool {
+        self.is_obstacle(x, y) || self.is_monster(x, y) || self.is_player(x, y)
+    }
+    fn is_position_occupied(&self, x: i32, y: i32) -> bool {
+        self.is_position_blocked(x, y)
+            || self.obstacle_at(x, y).is_some()
+            || self.is_treasure(x, y)
+            || self.is_exit_key(x, y)
+            || self.is_exit(x, y)
+    }
+
+    // Player `i` takes their turn, then their status effects tick
+    fn player_turn(
+        &mut self,
+        i: usize,
+        direction: Direction,
+        log: fn(&str),
+    ) -> Result<(), &'static str> {
+        let name = format!("P{}", i + 1);
+
+        if self.player(i).health == 0 {
+            log(&format!("{name} is dead."));
+            return Err("Player is dead");
+        }
+
+        // Frozen and slowed players lose their turn
+        if self.player(i).effects.is_holding() {
+            log(&format!("{name} can't move!"));
+        } else {
+            self.player_step(i, direction, log)?;
+        }
+        self.tick_player_effects(i, log);
+        Ok(())
+    }
+
+    // Moves player `i` one tile, attacking or swapping with any monster in the way
+    fn player_step(
+        &mut self,
+        i: usize,
+        direction: Direction,
+        log: fn(&str),
+    ) -> Result<(), &'static str> {
+        let name = format!("P{}", i + 1);
+        let (x, y) = (self.player(i).x, self.player(i).y);
+        let (new_x, new_y) = direction.step(x, y);
+
+        if self.is_out_of_bounds(new_x, new_y) {
+            log(&format!("{name} cannot move out-of-bounds"));
+            return Err("Player cannot move out-of-bounds");
+        }
+
+        // Crates get pushed ahead of the player, and one-way doors only open in their direction
+        let is_passable = match self.obstacle_at(new_x, new_y).map(|obs| obs.kind) {
+            Some(ObstacleKind::Crate) => self.push_crate(new_x, new_y, direction, log),
+            Some(ObstacleKind::OneWayDoor(dir)) => dir == direction,
+            Some(kind) => !kind.is_solid(),
+            None => true,
+        };
+
+        // Defeating a Spectral Ghost lets players walk through walls
+        if !is_passable {
+            let did_kill_super_ghost = self
+                .monsters()
+                .iter()
+                .any(|m| m.kind == MonsterKind::SpectralGhost && m.health == 0);
+            // Touching a firepit sets the player alight
+            if !did_kill_super_ghost && self.is_firepit(new_x, new_y) {
+                log(&format!("{name} touched the fire!"));
+                self.afflict_player(i, StatusEffectKind::Burn, log);
+                return Ok(());
+            }
+            if !did_kill_super_ghost {
+                log(&format!("{name} cannot move through obstacle"));
+                return Err("Player is blocked by an obstacle");
+            }
+        }
+
+        if self.is_player(new_x, new_y) {
+            log(&format!("{name} is blocked by another player"));
+            return Err("Player is blocked by another player");
+        }
+
+        // Attack (or swap places with) the monster in the way
+        if let Some(idx) = self
+            .monsters()
+            .iter()
+            .position(|m| m.occupies(new_x, new_y) && m.health > 0)
+        {
+            let strength = self.player(i).strength;
+            let monster = &mut self.monsters_mut()[idx];
+
+            // Swap positions with stunned monsters
+            if monster.stun_dur > 0 {
+                monster.x = x;
+                monster.y = y;
+                let monster_name = monster.kind.abbrev();
+                let player = self.player_mut(i);
+                player.x = new_x;
+                player.y = new_y;
+                log(&format!("{name} swapped positions with {monster_name}"));
+                return Ok(());
+            }
+
+            let monster_name = monster.kind.abbrev();
+            log(&format!("{name} attacks {monster_name}!"));
+            // Bosses can't be stunned
+            if !monster.kind.is_boss() {
+                monster.stun_dur = Monster::STUN_DUR;
+            }
+            let prev_monster_health = monster.health;
+            monster.health = monster.health.saturating_sub(strength);
+            let damage = prev_monster_health.abs_diff(monster.health);
+            let (kind, is_defeated) = (monster.kind, monster.health == 0);
+            let (mx, my) = (monster.x, monster.y);
+            log(&format!("{name} did {damage} damage."));
+            self.increment_player_stats(i, DungeonStatKind::DamageDealt, damage);
+            let events = self.events_mut();
+            events.push(TurnEvent::PlayerAttacked {
+                player: i,
+                monster: idx,
+                kind,
+            });
+            events.push(TurnEvent::MonsterDamaged {
+                monster: idx,
+                kind,
+                x: mx,
+                y: my,
+                damage,
+            });
+            if is_defeated {
+                self.defeat_monster(i, idx, new_x, new_y, log);
+            }
+            return Ok(()); // Player doesn't move into the monster's position
+        }
+
+        // Player moved
+        log(&format!("{name} moved {direction:?}."));
+        let player = self.player_mut(i);
+        player.x = new_x;
+        player.y = new_y;
+        player.direction = direction;
+        self.increment_player_stats(i, DungeonStatKind::StepsMoved, 1);
+        self.player_enter(i, direction, log);
+
+        Ok(())
+    }
+
+    // Resolves the tile player `i` just stepped onto, sliding them along any ice
+    fn player_enter(&mut self, i: usize, direction: Direction, log: fn(&str)) {
+        let name = format!("P{}", i + 1);
+        loop {
+            let (x, y) = (self.player(i).x, self.player(i).y);
+            let kind = self.obstacle_at(x, y).map(|obs| obs.kind);
+
+            // Walking through a firepit burns
+            if kind == Some(ObstacleKind::WallB) {
+                self.afflict_player(i, StatusEffectKind::Burn, log);
+            }
+
+            // Spikes hurt
+            if kind == Some(ObstacleKind::SpikeTrap) {
+                log(&format!("{name} stepped on spikes!"));
+                self.hurt_player(i, ObstacleKind::SPIKE_DAMAGE, log);
+            }
+
+            // Pressure plates open every door on the floor
+            if kind == Some(ObstacleKind::PressurePlate) {
+                let prev_obstacles = self.obstacles().len();
+                self.obstacles_mut()
+                    .retain(|obs| obs.kind != ObstacleKind::Door);
+                if self.obstacles().len() < prev_obstacles {
+                    log("The doors swung open!");
+                }
+            }
+
+            // Player collected treasure
+            if let Some(treasure) = self
+                .treasures()
+                .iter()
+                .find(|t| t.x == x && t.y == y)
+                .cloned()
+            {
+                if self.collect_treasure(i, &treasure, log) {
+                    self.treasures_mut().retain(|t| t.x != x || t.y != y);
+                    self.events_mut().push(TurnEvent::TreasureCollected {
+                        player: i,
+                        kind: treasure.kind,
+                        value: treasure.value,
+                        x,
+                        y,
+                    });
+                }
+            }
+
+            // Player found the exit key
+            if self.is_exit_key(x, y) {
+                log("Found exit key.");
+                self.set_exit_key(None);
+                self.events_mut()
+                    .push(TurnEvent::ExitKeyFound { player: i, x, y });
+                self.reveal_exit(x, y);
+                log("Hidden stairs appeared!");
+            }
+
+            // Keep sliding across ice until something is in the way
+            if kind != Some(ObstacleKind::IceFloor) || self.player(i).health == 0 {
+                break;
+            }
+            let (next_x, next_y) = direction.step(x, y);
+            if self.is_out_of_bounds(next_x, next_y) || self.is_position_blocked(next_x, next_y) {
+                break;
+            }
+            log(&format!("{name} slid {direction:?}."));
+            let player = self.player_mut(i);
+            player.x = next_x;
+            player.y = next_y;
+        }
+    }
+
+    // Pushes the crate at (x, y) one tile in `direction` if the tile behind it is free
+    fn push_crate(&mut self, x: i32, y: i32, direction: Direction, log: fn(&str)) -> bool {
+        let (to_x, to_y) = direction.step(x, y);
+        if self.is_out_of_bounds(to_x, to_y) || self.is_position_occupied(to_x, to_y) {
+            log("The crate won't budge.");
+            return false;
+        }
+        if let Some(obs) = self
+            .obstacles_mut()
+            .iter_mut()
+            .find(|obs| obs.x == x && obs.y == y)
+        {
+            obs.x = to_x;
+            obs.y = to_y;
+        }
+        log(&format!("Pushed the crate {direction:?}."));
+        true
+    }
+
+    // Applies a treasure's effect to player `i` (items stay on the floor when the inventory is full)
+    fn collect_treasure(&mut self, i: usize, treasure: &Treasure, log: fn(&str)) -> bool {
+        let amount = treasure.value;
+        let player = self.player_mut(i);
+        match treasure.kind {
+            TreasureKind::Gold => {
+                player.gold += amount;
+                self.increment_player_stats(i, DungeonStatKind::GoldCollected, amount);
+                log(&format!("Got treasure! +${amount}"));
+            }
+            TreasureKind::Heal => {
+                let prev_player_health = player.health;
+                player.health = (player.health + amount).min(player.max_health);
+                let recovered_health = prev_player_health.abs_diff(player.health);
+                self.increment_player_stats(i, DungeonStatKind::HealthRecovered, recovered_health);
+                log(&format!("Recovered {recovered_health} HP!"));
+            }
+            TreasureKind::HealthUp => {
+                player.max_health += 1;
+                let prev_player_health = player.health;
+                player.health = (player.health + amount).min(player.max_health);
+                let recovered_health = prev_player_health.abs_diff(player.health);
+                self.increment_player_stats(i, DungeonStatKind::HealthRecovered, recovered_health);
+                log(&format!("Health Up! Recovered {recovered_health} HP!"));
+            }
+            TreasureKind::Item(item) => {
+                if player.inventory.len() >= Player::MAX_ITEMS {
+                    log("Inventory full!");
+                    return false;
+                }
+                player.inventory.push(item);
+                log(&format!("Got {}!", item.name()));
+            }
+            TreasureKind::Chest => {
+                player.gold += amount;
+                self.increment_player_stats(i, DungeonStatKind::GoldCollected, amount);
+                log(&format!("Opened a treasure chest! +${amount}"));
+            }
+        }
+        true
+    }
+
+    // Uses the item in player `i`'s hotbar slot
+    fn player_use_item(
+        &mut self,
+        i: usize,
+        slot: usize,
+        log: fn(&str),
+    ) -> Result<(), &'static str> {
+        let name = format!("P{}", i + 1);
+        let player = self.player(i);
+        if player.health == 0 {
+            log(&format!("{name} is dead."));
+            return Err("Player is dead");
+        }
+        let Some(&item) = player.inventory.get(slot) else {
+            log(&format!("{name} has no item in slot {}", slot + 1));
+            return Err("No item in that slot");
+        };
+        let (x, y) = (player.x, player.y);
+        match item {
+            ItemKind::Potion => {
+                if player.health >= player.max_health {
+                    log(&format!("{name} is already at full health"));
+                    return Err("Player is already at full health");
+                }
+                let player = self.player_mut(i);
+                let prev_player_health = player.health;
+                player.health = (player.health + ItemKind::POTION_HEAL).min(player.max_health);
+                let recovered_health = prev_player_health.abs_diff(player.health);
+                self.increment_player_stats(i, DungeonStatKind::HealthRecovered, recovered_health);
+                log(&format!(
+                    "{name} drank a potion. Recovered {recovered_health} HP!"
+                ));
+            }
+            ItemKind::Bomb => {
+                // Destroys every solid obstacle in the 8 surrounding tiles
+                let prev_obstacles = self.obstacles().len();
+                self.obstacles_mut()
+                    .retain(|o| !o.kind.is_solid() || (o.x - x).abs() > 1 || (o.y - y).abs() > 1);
+                let destroyed = prev_obstacles - self.obstacles().len();
+                if destroyed == 0 {
+                    log("There's nothing to blow up");
+                    return Err("No adjacent obstacles");
+                }
+                log(&format!("{name} blew up {destroyed} walls!"));
+            }
+            ItemKind::TeleportScroll => {
+                let free: Vec<(i32, i32)> = (0..self.height() as i32)
+                    .flat_map(|ty| (0..self.width() as i32).map(move |tx| (tx, ty)))
+                    .filter(|&(tx, ty)| !self.is_position_occupied(tx, ty))
+                    .collect();
+                if free.is_empty() {
+                    log(&format!("{name} has nowhere to teleport"));
+                    return Err("Nowhere to teleport");
+                }
+                let (tx, ty) = free[self.rng().index(free.len())];
+                let player = self.player_mut(i);
+                player.x = tx;
+                player.y = ty;
+                log(&format!("{name} teleported!"));
+            }
+            ItemKind::RerollScroll => {
+                if self.floor() > 0 || self.clock() > 0 {
+                    log("Only the first floor can be rerolled, before the first step");
+                    return Err("Floor already started");
+                }
+                if !self.reroll_floor(log) {
+                    log("This floor can't be rerolled");
+                    return Err("Floor can't be rerolled");
+                }
+                log(&format!("{name} rerolled the floor!"));
+            }
+        }
+        self.player_mut(i).inventory.remove(slot);
+        self.increment_player_stats(i, DungeonStatKind::ItemsUsed, 1);
+        Ok(())
+    }
+
+    // Player `i` gets the credit for defeating monster `idx`, last hit at (x, y).
+    // Bosses drop their rewards, and clearing the floor leaves a treasure at (x, y).
+    fn defeat_monster(&mut self, i: usize, idx: usize, x: i32, y: i32, log: fn(&str)) {
+        let monster = self.monsters()[idx].clone();
+        let monster_name = monster.kind.abbrev();
+        log(&format!("{monster_name} defeated!"));
+        self.events_mut().push(TurnEvent::MonsterDefeated {
+            monster: idx,
+            kind: monster.kind,
+            x: monster.x,
+            y: monster.y,
+        });
+        self.increment_player_stats(i, DungeonStatKind::Defeated(monster.kind), 1);
+        if monster.kind.is_boss() {
+            self.spawn_boss_reward(&monster, x, y);
+            log(&format!("{monster_name} dropped the exit key!"));
+        }
+
+        // If all monsters are defeated, spawn a treasure
+        if self.monsters().iter().all(|m| m.health == 0) {
+            self.spawn_clear_reward(i, x, y);
+        }
+    }
+
+    // Drops a reward where the last monster on the floor was defeated
+    fn spawn_clear_reward(&mut self, i: usize, x: i32, y: i32) {
+        let max_hp_limit = 99;
+        let player = self.player(i);
+        let player_max_hp_limit = max_hp_limit.min(if player.strength == 1 {
+            // players w normal strength can get max hp up the current floor
+            self.floor() + 1
+        } else {
+            // players w increased strength can only get max hp 1/2 the current floor
+            self.floor().div_ceil(2)
+        });
+        let treasure = if player.health < player_max_hp_limit {
+            Treasure {
+                x,
+                y,
+                value: 1,
+                kind: TreasureKind::HealthUp,
+            }
+        } else {
+            Treasure {
+                x,
+                y,
+                value: 2,
+                kind: TreasureKind::Heal,
+            }
+        };
+        self.treasures_mut().push(treasure);
+    }
+
+    // Reveals the stairs on an open tile that can be walked to from (x, y), away from it when there's room
+    fn reveal_exit(&mut self, x: i32, y: i32) {
+        let min_distance = (self.width().min(self.height()) / 2) as i32;
+        let mut paths = Pathfinder::new(self.width(), self.height());
+        for obstacle in self.obstacles() {
+            if obstacle.kind.is_solid() {
+                paths.block(obstacle.x, obstacle.y);
+            }
+        }
+        let distances = paths.distances((x, y));
+        let open: Vec<(i32, i32)> = (0..self.height() as i32)
+            .flat_map(|ey| (0..self.width() as i32).map(move |ex| (ex, ey)))
+            .filter(|&(ex, ey)| paths.distance(&distances, ex, ey).is_some())
+            .filter(|&(ex, ey)| !self.is_position_occupied(ex, ey))
+            .collect();
+        let far: Vec<(i32, i32)> = open
+            .iter()
+            .copied()
+            .filter(|(ex, ey)| (ex - x).abs() + (ey - y).abs() >= min_distance)
+            .collect();
+        let candidates = if far.is_empty() { open } else { far };
+        // Stairs go right under the player when the floor is packed
+        let exit = if candidates.is_empty() {
+            (x, y)
+        } else {
+            candidates[self.rng().index(candidates.len())]
+        };
+        self.set_exit(Some(exit));
+        self.events_mut().push(TurnEvent::ExitAppeared {
+            x: exit.0,
+            y: exit.1,
+        });
+    }
+
+    // Every living monster attacks an adjacent player or moves towards the closest one
+    fn monsters_turn(&mut self, log: fn(&str)) {
+        // Ranged and boss attacks announced last turn land before anything moves
+        self.resolve_danger_tiles(log);
+        let paths = self.pathfinder();
+        for i in 0..self.monsters().len() {
+            let monster = &self.monsters()[i];
+            let (mx, my) = (monster.x, monster.y);
+
+            // Skip dead monsters (but leave them in the dungeon)
+            if monster.health == 0 {
+                continue;
+            }
+
+            // Status effects tick first, and frozen or slowed monsters lose their turn
+            if !monster.effects.list.is_empty() {
+                let is_held = monster.effects.is_holding();
+                self.tick_monster_effects(i, log);
+                if is_held || self.monsters()[i].health == 0 {
+                    continue;
+                }
+            }
+            let monster = &self.monsters()[i];
+
+            // Skip stunned monsters
+            if monster.stun_dur > 0 {
+                let monster = &mut self.monsters_mut()[i];
+                monster.stun_dur = monster.stun_dur.saturating_sub(1);
+                continue;
+            }
+
+            // Bosses follow their own attack patterns
+            if monster.kind.is_boss() {
+                self.boss_turn(i, log);
+                continue;
+            }
+
+            // If the monster is adjacent to a player, it attacks
+            let living = (0..self.player_count()).filter(|p| self.player(*p).health > 0);
+            let adjacent = living.clone().find(|p| {
+                let player = self.player(*p);
+                monster.distance_to(player.x, player.y) == 1
+            });
+            if let Some(p) = adjacent {
+                self.monster_attack(i, p, log);
+                continue;
+            }
+
+            // Ranged monsters take aim at a player in their line of fire
+            if let Some(tile) = self.ranged_aim(i) {
+                let monster = &mut self.monsters_mut()[i];
+                let to = (mx + (tile.x - mx).signum(), my + (tile.y - my).signum());
+                monster.direction = Direction::between((mx, my), to).unwrap_or(monster.direction);
+                let shot = match monster.kind {
+                    MonsterKind::Spider => "shoots a web",
+                    _ => "throws a snowball",
+                };
+                log(&format!("{} {shot}!", monster.kind.abbrev()));
+                self.danger_tiles_mut().push(tile);
+                continue;
+            }
+
+            // Otherwise, move towards the closest player
+            let Some(p) = living.min_by_key(|p| {
+                let player = self.player(*p);
+                let dx = player.x - mx;
+                let dy = player.y - my;
+                dx * dx + dy * dy // Squared distance for comparison
+            }) else {
+                continue;
+            };
+            let target = (self.player(p).x, self.player(p).y);
+            let Some((dir, x, y)) = self.monster_step(&paths, i, target, log) else {
+                continue;
+            };
+            if self.is_out_of_bounds(x, y) {
+                continue;
+            }
+            let monster = &mut self.monsters_mut()[i];
+            monster.x = x;
+            monster.y = y;
+            monster.direction = dir;
+
+            // Monsters phasing through a firepit catch fire
+            if self.is_firepit(x, y) {
+                self.afflict_monster(i, StatusEffectKind::Burn, log);
+            }
+        }
+    }
+
+    // Boss `i` winds up, marking the tiles in its attack pattern as danger tiles, then
+    // stays put while they land. Between attacks, it hits adjacent players or lumbers
+    // towards the closest one.
+    fn boss_turn(&mut self, i: usize, log: fn(&str)) {
+        let clock = self.clock();
+        let boss = self.monsters()[i].clone();
+        let boss_name = boss.kind.abbrev();
+        if Monster::is_boss_attack_turn(clock) {
+            log(&format!("{boss_name} unleashes its attack!"));
+            return;
+        }
+        if Monster::is_boss_attack_turn(clock + 1) {
+            log(&format!("{boss_name} is winding up..."));
+            let tiles = self.boss_attack_tiles(&boss);
+            self.danger_tiles_mut()
+                .extend(tiles.into_iter().map(|(x, y)| DangerTile {
+                    x,
+                    y,
+                    damage: boss.strength,
+                    kind: boss.kind,
+                }));
+            return;
+        }
+
+        let living = (0..self.player_count()).filter(|p| self.player(*p).health > 0);
+        let Some(p) = living.min_by_key(|p| {
+            let player = self.player(*p);
+            boss.distance_to(player.x, player.y)
+        }) else {
+            return;
+        };
+        let (tx, ty) = (self.player(p).x, self.player(p).y);
+        if boss.distance_to(tx, ty) == 1 {
+            self.monster_attack(i, p, log);
+            return;
+        }
+
+        // Lumbers along at its current phase's pace
+        let pace = boss
+            .kind
+            .def()
+            .boss_phase(boss.phase())
+            .map(|phase| phase.pace);
+        if !pace.unwrap_or_default().moves_on(clock) {
+            return;
+        }
+        let Some((dir, x, y)) = self.approach(boss.x, boss.y, tx - boss.x, ty - boss.y) else {
+            return;
+        };
+        let moved = Monster {
+            x,
+            y,
+            ..boss.clone()
+        };
+        let is_clear = moved.tiles().into_iter().all(|(x, y)| {
+            !self.is_out_of_bounds(x, y)
+                && (boss.occupies(x, y) || !self.is_position_occupied(x, y))
+        });
+        if is_clear {
+            let boss = &mut self.monsters_mut()[i];
+            boss.x = x;
+            boss.y = y;
+            boss.direction = dir;
+        }
+    }
+
+    // Tiles a boss hits when it attacks
+    fn boss_attack_tiles(&self, boss: &Monster) -> Vec<(i32, i32)> {
+        let mut tiles = vec![];
+        let Some(phase) = boss.kind.def().boss_phase(boss.phase()) else {
+            return tiles;
+        };
+        match phase.attack {
+            BossAttack::Slam => {
+                for y in boss.y - 1..=boss.y + boss.size() {
+                    for x in boss.x - 1..=boss.x + boss.size() {
+                        if !boss.occupies(x, y)
+                            && !self.is_out_of_bounds(x, y)
+                            && !self.is_obstacle(x, y)
+                        {
+                            tiles.push((x, y));
+                        }
+                    }
+                }
+            }
+            BossAttack::Shockwave => {
+                for (bx, by) in boss.tiles() {
+                    for dir in Direction::ALL {
+                        let (mut x, mut y) = dir.step(bx, by);
+                        while !self.is_out_of_bounds(x, y) && !self.is_obstacle(x, y) {
+                            if !boss.occupies(x, y) && !tiles.contains(&(x, y)) {
+                                tiles.push((x, y));
+                            }
+                            (x, y) = dir.step(x, y);
+                        }
+                    }
+                }
+            }
+        }
+        tiles
+    }
+
+    // Where ranged monster `i` aims this turn, if a living player is in its line of fire
+    fn ranged_aim(&self, i: usize) -> Option<DangerTile> {
+        let monster = &self.monsters()[i];
+        let (range, every) = monster.kind.ranged_attack()?;
+        if self.clock() % every != 1 {
+            return None;
+        }
+        (0..self.player_count()).find_map(|p| {
+            let player = self.player(p);
+            let (dx, dy) = (player.x - monster.x, player.y - monster.y);
+            if player.health == 0 || (dx != 0 && dy != 0) || dx.abs() + dy.abs() > range {
+                return None;
+            }
+            // Walls and other monsters block the shot
+            let (sx, sy) = (dx.signum(), dy.signum());
+            let (mut x, mut y) = (monster.x + sx, monster.y + sy);
+            while (x, y) != (player.x, player.y) {
+                if self.is_obstacle(x, y) || self.is_monster(x, y) {
+                    return None;
+                }
+                (x, y) = (x + sx, y + sy);
+            }
+            Some(DangerTile {
+                x: player.x,
+                y: player.y,
+                damage: monster.strength,
+                kind: monster.kind,
+            })
+        })
+    }
+
+    // Ranged attacks announced last turn hit any player still standing on their tiles
+    fn resolve_danger_tiles(&mut self, log: fn(&str)) {
+        let tiles = std::mem::take(self.danger_tiles_mut());
+        for tile in tiles {
+            for p in 0..self.player_count() {
+                let player = self.player(p);
+                if player.health > 0 && player.x == tile.x && player.y == tile.y {
+                    log(&format!("{}'s attack hits P{}!", tile.kind.abbrev(), p + 1));
+                    self.damage_player(p, tile.kind, tile.damage, log);
+                    if let Some(effect) = tile.kind.ranged_effect() {
+                        self.afflict_player(p, effect, log);
+                    }
+                }
+            }
+        }
+    }
+
+    // Gives player `i` a status effect unless they already have it (or are dead)
+    fn afflict_player(&mut self, i: usize, kind: StatusEffectKind, log: fn(&str)) {
+        let player = self.player_mut(i);
+        if player.health > 0 && player.effects.add(kind) {
+            log(&format!("P{} is {}!", i + 1, kind.name()));
+            self.increment_player_stats(i, DungeonStatKind::Afflicted(kind), 1);
+        }
+    }
+
+    // Gives monster `i` a status effect unless it already has it
+    fn afflict_monster(&mut self, i: usize, kind: StatusEffectKind, log: fn(&str)) {
+        let monster = &mut self.monsters_mut()[i];
+        if monster.health > 0 && monster.effects.add(kind) {
+            log(&format!("{} is {}!", monster.kind.abbrev(), kind.name()));
+        }
+    }
+
+    // Counts down player `i`'s status effects, dealing any damage over time
+    fn tick_player_effects(&mut self, i: usize, log: fn(&str)) {
+        let damage = self.player_mut(i).effects.tick();
+        if damage > 0 {
+            log(&format!("P{} suffers from status effects.", i + 1));
+            self.hurt_player(i, damage, log);
+        }
+    }
+
+    // Deals damage to player `i` that doesn't come from a monster
+    fn hurt_player(&mut self, i: usize, damage: u32, log: fn(&str)) {
+        let name = format!("P{}", i + 1);
+        let player = self.player_mut(i);
+        if player.health == 0 {
+            return;
+        }
+        let prev_player_health = player.health;
+        player.health = player.health.saturating_sub(damage);
+        let damage = prev_player_health.abs_diff(player.health);
+        let (x, y, is_dead) = (player.x, player.y, player.health == 0);
+        self.increment_player_stats(i, DungeonStatKind::DamageTaken, damage);
+        log(&format!("{name} took {damage} damage."));
+        self.events_mut().push(TurnEvent::PlayerDamaged {
+            player: i,
+            x,
+            y,
+            damage,
+            by: None,
+        });
+        if is_dead {
+            log(&format!("{name} died."));
+            self.events_mut()
+                .push(TurnEvent::PlayerDied { player: i, x, y });
+        }
+    }
+
+    // Counts down monster `i`'s status effects, dealing any damage over time
+    fn tick_monster_effects(&mut self, i: usize, log: fn(&str)) {
+        let monster = &mut self.monsters_mut()[i];
+        let damage = monster.effects.tick();
+        if damage == 0 {
+            return;
+        }
+        monster.health = monster.health.saturating_sub(damage);
+        let monster_name = monster.kind.abbrev();
+        let (kind, x, y, is_defeated) = (monster.kind, monster.x, monster.y, monster.health == 0);
+        log(&format!(
+            "{monster_name} took {damage} damage from status effects."
+        ));
+        self.events_mut().push(TurnEvent::MonsterDamaged {
+            monster: i,
+            kind,
+            x,
+            y,
+            damage,
+        });
+        if is_defeated {
+            // Nobody landed the blow, so the nearest player gets the credit
+            let monster = &self.monsters()[i];
+            let p = (0..self.player_count())
+                .min_by_key(|p| monster.distance_to(self.player(*p).x, self.player(*p).y))
+                .unwrap_or(0);
+            self.defeat_monster(p, i, x, y, log);
+        }
+    }
+
+    // Monster `i` hits player `p`
+    fn monster_attack(&mut self, i: usize, p: usize, log: fn(&str)) {
+        let (px, py) = (self.player(p).x, self.player(p).y);
+        let monster = &mut self.monsters_mut()[i];
+        monster.direction = match (px - monster.x, py - monster.y) {
+            (_, -1) => Direction::Up,
+            (_, 1) => Direction::Down,
+            (-1, _) => Direction::Left,
+            _ => Direction::Right,
+        };
+        let (kind, strength) = (monster.kind, monster.strength);
+        log(&format!("{} attacks!", kind.abbrev()));
+        self.events_mut().push(TurnEvent::MonsterAttacked {
+            monster: i,
+            kind,
+            player: p,
+        });
+        self.damage_player(p, kind, strength, log);
+        if let Some(effect) = kind.melee_effect() {
+            self.afflict_player(p, effect, log);
+        }
+    }
+
+    // A monster of `kind` deals `strength` damage to player `p`
+    fn damage_player(&mut self, p: usize, kind: MonsterKind, strength: u32, log: fn(&str)) {
+        let monster_name = kind.abbrev();
+        let player = self.player_mut(p);
+        let prev_player_health = player.health;
+        player.health = player.health.saturating_sub(strength);
+        let damage = prev_player_health.abs_diff(player.health);
+        let (x, y, is_dead) = (player.x, player.y, player.health == 0);
+        self.increment_player_stats(p, DungeonStatKind::DamageTaken, damage);
+        log(&format!("{monster_name} did {damage} damage."));
+        self.events_mut().push(TurnEvent::PlayerDamaged {
+            player: p,
+            x,
+            y,
+            damage,
+            by: Some(kind),
+        });
+        if is_dead {
+            log(&format!("P{} died.", p + 1));
+            self.events_mut()
+                .push(TurnEvent::PlayerDied { player: p, x, y });
+            self.increment_player_stats(p, DungeonStatKind::DefeatedBy(kind), 1);
+        }
+    }
+
+    // Decides where monster `i` moves this turn based on its definition
+    // Returns None when the monster stays put
+    fn monster_step(
+        &mut self,
+        paths: &Pathfinder,
+        i: usize,
+        target: (i32, i32),
+        log: fn(&str),
+    ) -> Option<(Direction, i32, i32)> {
+        let monster = self.monsters()[i].clone();
+
+        // Mimics move like a random monster each turn
+        let mut kind = monster.kind;
+        if kind.def().movement == MonsterMovement::Mimic {
+            kind = MonsterKind::by_index(self.rng().next_u32() as usize);
+            log(&format!("{:?} is feeling like a {:?}", monster.kind, kind));
+        }
+
+        if !kind.def().pace(self.floor()).moves_on(self.clock()) {
+            return None;
+        }
+        let movement = kind.movement(monster.health < monster.max_health);
+        self.movement_step(paths, i, movement, target, log)
+    }
+
+    // Where monster `i` moves this turn under a movement policy
+    fn movement_step(
+        &mut self,
+        paths: &Pathfinder,
+        i: usize,
+        movement: MonsterMovement,
+        target: (i32, i32),
+        log: fn(&str),
+    ) -> Option<(Direction, i32, i32)> {
+        let monster = self.monsters()[i].clone();
+        let from = (monster.x, monster.y);
+        let (mx, my) = from;
+        let (tx, ty) = target;
+        match movement {
+            // Mimics that pick themselves just chase
+            MonsterMovement::Chase | MonsterMovement::Mimic => self.path_step(paths, from, target),
+            MonsterMovement::Flee { range } => {
+                // Step to whichever neighbor is furthest (by walking) from the player
+                let distances = paths.distances(target);
+                let current = paths.distance(&distances, mx, my)?;
+                if current > range {
+                    return None;
+                }
+                Direction::ALL
+                    .iter()
+                    .filter_map(|dir| {
+                        let (x, y) = dir.step(mx, my);
+                        if self.is_position_occupied(x, y) {
+                            return None;
+                        }
+                        let d = paths.distance(&distances, x, y)?;
+                        (d > current).then_some((d, *dir, x, y))
+                    })
+                    .max_by_key(|(d, ..)| *d)
+                    .map(|(_, dir, x, y)| (dir, x, y))
+            }
+            MonsterMovement::Patrol { range, rest_every } => {
+                // Chase players within walking range
+                let path = paths.find_path(from, target);
+                if path.is_some_and(|path| path.len() as u32 <= range) {
+                    return self.path_step(paths, from, target);
+                }
+                // Otherwise, wander in a random direction
+                if rest_every > 1 && self.clock().is_multiple_of(rest_every) {
+                    return None;
+                }
+                let dir = Direction::ALL[self.rng().index(4)];
+                let (x, y) = dir.step(mx, my);
+                if self.is_position_occupied(x, y) {
+                    return None;
+                }
+                Some((dir, x, y))
+            }
+            MonsterMovement::Ambush { leap } => {
+                // Lunge along the path until something is in the way
+                let path = paths.find_path(from, target).unwrap_or_default();
+                let mut next = None;
+                let mut prev = from;
+                for (x, y) in path.into_iter().take(leap as usize) {
+                    if self.is_position_occupied(x, y) {
+                        break;
+                    }
+                    next = Some((Direction::between(prev, (x, y))?, x, y));
+                    prev = (x, y);
+                }
+                next.or_else(|| self.approach(mx, my, tx - mx, ty - my))
+            }
+            MonsterMovement::Phase => {
+                let (dir, x, y) = match ((tx - mx).abs() > (ty - my).abs(), tx > mx, ty > my) {
+                    (false, _, false) => (Direction::Up, mx, my - 1),
+                    (false, _, true) => (Direction::Down, mx, my + 1),
+                    (true, false, _) => (Direction::Left, mx - 1, my),
+                    (true, true, _) => (Direction::Right, mx + 1, my),
+                };
+                if self.is_monster(x, y) || self.is_player(x, y) {
+                    return None;
+                }
+                Some((dir, x, y))
+            }
+            MonsterMovement::Haunt { range, rest_every } => {
+                self.haunt_step(i, range, rest_every, target, log)
+            }
+            MonsterMovement::Guard => {
+                // Moves towards the exit, the exit key, or the player
+                let mut next = (monster.direction, mx, my);
+                for (gx, gy) in [
+                    self.exit().unwrap_or(target),
+                    self.exit_key().unwrap_or(target),
+                    target,
+                ] {
+                    if let Some(step) = self.path_step(paths, from, (gx, gy)) {
+                        next = step;
+                    }
+                }
+                Some(next)
+            }
+        }
+    }
+
+    // Haunting monster `i` drifts through walls towards monsters it can absorb,
+    // otherwise towards the player once they're within `range` tiles on either axis
+    fn haunt_step(
+        &mut self,
+        i: usize,
+        range: i32,
+        rest_every: u32,
+        target: (i32, i32),
+        log: fn(&str),
+    ) -> Option<(Direction, i32, i32)> {
+        let monster = self.monsters()[i].clone();
+        let (mx, my) = (monster.x, monster.y);
+        let (tx, ty) = target;
+        let can_absorb = |m: &Monster| m.health > 0 && m.kind.def().absorbed_into.is_some();
+        let mut dir = Direction::Down;
+        let mut x = mx;
+        let mut y = my;
+
+        // First, check for nearby monsters to absorb
+        let mut did_find_nearby = false;
+        for dx in -3..=3_i32 {
+            for dy in -3..=3_i32 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let (gx, gy) = (mx + dx, my + dy);
+                if self
+                    .monsters()
+                    .iter()
+                    .any(|m| can_absorb(m) && m.x == gx && m.y == gy)
+                {
+                    did_find_nearby = true;
+                    // Move one space towards it
+                    dir = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
+                        (false, _, false) => Direction::Up,
+                        (false, _, true) => Direction::Down,
+                        (true, false, _) => Direction::Left,
+                        (true, true, _) => Direction::Right,
+                    };
+                    if dx != 0 {
+                        x = mx + dx.signum();
+                    } else {
+                        y = my + dy.signum();
+                    }
+                    break;
+                }
+            }
+        }
+
+        // Otherwise, move towards the player if they're close enough
+        if !did_find_nearby {
+            if self.clock().is_multiple_of(rest_every) {
+                return None;
+            }
+            let dx = tx - x;
+            let dy = ty - y;
+            let dx_abs = dx.abs();
+            let dy_abs = dy.abs();
+            if dx_abs <= range || dy_abs <= range {
+                let x_or_y = if dx_abs == dy_abs {
+                    self.rng().one_in(2)
+                } else {
+                    dx_abs > dy_abs
+                };
+                (dir, x, y) = match (x_or_y, dx > 0, dy > 0) {
+                    (false, _, false) => (Direction::Up, x, y - 1),
+                    (false, _, true) => (Direction::Down, x, y + 1),
+                    (true, false, _) => (Direction::Left, x - 1, y),
+                    (true, true, _) => (Direction::Right, x + 1, y),
+                };
+            }
+        }
+
+        // Didn't move
+        if mx == x && my == y {
+            return None;
+        }
+
+        if self.is_player(x, y) || self.is_exit(x, y) {
+            return None;
+        }
+
+        // "Absorb" any living monster it can in the same position
+        if let Some(idx) = self
+            .monsters()
+            .iter()
+            .position(|m| can_absorb(m) && m.x == x && m.y == y)
+        {
+            let absorbed = self.monsters()[idx].kind;
+            log(&format!(
+                "{:?} is absorbing a {:?}!",
+                monster.kind, absorbed
+            ));
+            let monsters = self.monsters_mut();
+            // Remove the absorbed monster
+            monsters[idx].health = 0;
+            // Increase stats and transform, unless it's only mimicking a haunting monster
+            let monster = &mut monsters[i];
+            if monster.kind.def().movement != MonsterMovement::Mimic {
+                monster.kind = absorbed.def().absorbed_into.unwrap_or(monster.kind);
+            }
+            monster.strength *= 2;
+            monster.max_health *= 2;
+            monster.health = monster.max_health;
+        }
+
+        if self.is_monster(x, y) {
+            return None;
+        }
+
+        Some((dir, x, y))
+    }
+
+    // One step along the shortest walk from `from` to `to`.
+    // Falls back to `approach` when there's no path or another monster is in the way.
+    fn path_step(
+        &self,
+        paths: &Pathfinder,
+        from: (i32, i32),
+        to: (i32, i32),
+    ) -> Option<(Direction, i32, i32)> {
+        let next = paths
+            .find_path(from, to)
+            .and_then(|path| path.first().copied());
+        match next {
+            Some((x, y)) if !self.is_position_occupied(x, y) => {
+                Some((Direction::between(from, (x, y))?, x, y))
+            }
+            _ => self.approach(from.0, from.1, to.0 - from.0, to.1 - from.1),
+        }
+    }
+
+    // Tiles monsters can never step on this turn: walls, hazards, treasures, the exit key, and the exit
+    fn pathfinder(&self) -> Pathfinder {
+        let mut paths = Pathfinder::new(self.width(), self.height());
+        for obstacle in self.obstacles() {
+            paths.block(obstacle.x, obstacle.y);
+        }
+        for treasure in self.treasures() {
+            paths.block(treasure.x, treasure.y);
+        }
+        for (x, y) in [self.exit_key(), self.exit()].into_iter().flatten() {
+            paths.block(x, y);
+        }
+        paths
+    }
+
+    // First open tile one step from (x, y) towards (x + dx, y + dy), trying the longer axis first
+    fn approach(&self, x: i32, y: i32, dx: i32, dy: i32) -> Option<(Direction, i32, i32)> {
+        let move_x = if dx < 0 {
+            (Direction::Left, x - 1, y)
+        } else {
+            (Direction::Right, x + 1, y)
+        };
+        let move_y = if dy < 0 {
+            (Direction::Up, x, y - 1)
+        } else {
+            (Direction::Down, x, y + 1)
+        };
+        let moves = if dx.abs() > dy.abs() {
+            [move_x, move_y]
+        } else {
+            [move_y, move_x]
+        };
+        moves
+            .into_iter()
+            .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
+    }
+
+    // Random position inside the floor bounds (excluding the last row and column)
+    fn random_position(&mut self) -> (i32, i32) {
+        let (max_x, max_y) = self.bounds();
+        let x = self.rng().range(max_x as u32) as i32;
+        let y = self.rng().range(max_y as u32) as i32;
+        (x, y)
+    }
+
+    // Places `count` monsters picked by weight on empty tiles
+    fn spawn_monsters(&mut self, count: usize, monster_weights: &[(u32, MonsterKind)]) {
+        let total_weight: u32 = monster_weights.iter().map(|(weight, _)| *weight).sum();
+        while self.monsters().len() < count {
+            let (x, y) = self.random_position();
+            if !self.is_position_occupied(x, y) {
+                // Generate a random number within the total weight
+                let n = self.rng().range(total_weight);
+                let mut selected_monster = MonsterKind::GreenGoblin;
+                // Select the monster based on weighted probability
+                let mut cumulative_weight = 0;
+                for (weight, monster_kind) in monster_weights {
+                    cumulative_weight += *weight;
+                    if n < cumulative_weight {
+                        selected_monster = *monster_kind;
+                        break;
+                    }
+                }
+                // Define monster stats based on the selected kind
+                let (health, strength) = selected_monster.stats();
+                self.monsters_mut().push(Monster {
+                    x,
+                    y,
+                    health,
+                    max_health: health,
+                    strength,
+                    direction: Direction::Down,
+                    kind: selected_monster,
+                    stun_dur: 0,
+                    effects: StatusEffects::new(),
+                });
+            }
+        }
+    }
+
+    // Places a boss on an open patch away from the players, scaled to the floor.
+    // If no open patch is far enough, the boss goes on the one farthest from the players,
+    // and if there's none at all the floor gets an exit key instead.
+    fn spawn_boss(&mut self, kind: MonsterKind) {
+        let min_distance = (self.width().min(self.height()) / 2) as i32;
+        let (health, strength) = kind.stats();
+        let floor = self.floor();
+        let boss_at = |x: i32, y: i32| Monster {
+            x,
+            y,
+            health: health + floor,
+            max_health: health + floor,
+            strength: strength + floor / 20,
+            direction: Direction::Down,
+            kind,
+            stun_dur: 0,
+            effects: StatusEffects::new(),
+        };
+        // Every clear patch, with how close the nearest player is to it
+        let clear: Vec<((i32, i32), i32)> = (0..self.height() as i32)
+            .flat_map(|y| (0..self.width() as i32).map(move |x| (x, y)))
+            .filter_map(|(x, y)| {
+                let boss = boss_at(x, y);
+                let is_clear = boss
+                    .tiles()
+                    .into_iter()
+                    .all(|(x, y)| !self.is_out_of_bounds(x, y) && !self.is_position_occupied(x, y));
+                let nearest = (0..self.player_count())
+                    .map(|p| boss.distance_to(self.player(p).x, self.player(p).y))
+                    .min()
+                    .unwrap_or(i32::MAX);
+                is_clear.then_some(((x, y), nearest))
+            })
+            .collect();
+        let far: Vec<(i32, i32)> = clear
+            .iter()
+            .filter(|(_, nearest)| *nearest >= min_distance)
+            .map(|(tile, _)| *tile)
+            .collect();
+        let (x, y) = if !far.is_empty() {
+            far[self.rng().index(far.len())]
+        } else if let Some((tile, _)) = clear.iter().max_by_key(|(_, nearest)| *nearest) {
+            *tile
+        } else {
+            // Boss floors are cleared into an arena, so this only happens on tiny floors.
+            // Without a boss to drop it, the exit key is placed up front.
+            self.spawn_exit_key();
+            return;
+        };
+        self.monsters_mut().push(boss_at(x, y));
+    }
+
+    // Defeated bosses drop the exit key and a treasure chest where they stood
+    fn spawn_boss_reward(&mut self, boss: &Monster, hit_x: i32, hit_y: i32) {
+        let mut tiles = boss
+            .tiles()
+            .into_iter()
+            .filter(|tile| *tile != (hit_x, hit_y));
+        if let Some(tile) = tiles.next() {
+            self.set_exit_key(Some(tile));
+        }
+        if let Some((x, y)) = tiles.next() {
+            let value = 10 + 2 * (self.floor() + 1);
+            self.treasures_mut().push(Treasure {
+                x,
+                y,
+                value,
+                kind: TreasureKind::Chest,
+            });
+        }
+    }
+
+    // Places `count` treasures on empty tiles, the last one being a healing item
+    fn spawn_treasures(&mut self, count: usize) {
+        while self.treasures().len() < count {
+            let (x, y) = self.random_position();
+            if !self.is_position_occupied(x, y) {
+                let treasure = if self.treasures().len() == count - 1 {
+                    Treasure {
+                        x,
+                        y,
+                        value: 2,
+                        kind: TreasureKind::Heal,
+                    }
+                } else if self.spawns_items() && self.rng().one_in(20) {
+                    // 5% chance for an item
+                    let item = ItemKind::DROPS[self.rng().index(ItemKind::DROPS.len())];
+                    Treasure {
+                        x,
+                        y,
+                        value: 1,
+                        kind: TreasureKind::Item(item),
+                    }
+                } else if self.rng().range(10) < 9 {
+                    // 90% chance for $1 gold treasure
+                    Treasure {
+                        x,
+                        y,
+                        value: 1,
+                        kind: TreasureKind::Gold,
+                    }
+                } else {
+                    // 10% chance for $10 gold treasure
+                    Treasure {
+                        x,
+                        y,
+                        value: 10,
+                        kind: TreasureKind::Gold,
+                    }
+                };
+                self.treasures_mut().push(treasure);
+            }
+        }
+    }
+
+    // Places the exit key at least half the floor size away from the first player
+    fn spawn_exit_key(&mut self) {
+        let min_distance = (self.width().min(self.height()) / 2) as i32;
+        loop {
+            let (x, y) = self.random_position();
+            let player = self.player(0);
+            let dx = (x - player.x).abs();
+            let dy = (y - player.y).abs();
+            if dx + dy >= min_distance && !self.is_position_occupied(x, y) {
+                self.set_exit_key(Some((x, y)));
+                break;
+            }
+        }
+    }
+
+    // Now and then stamps a prefab room, picked by weight from the ones that fit this floor
+    fn spawn_prefab_room(&mut self) {
+        let odds = self.prefab_room_odds();
+        if !self.rng().one_in(odds) {
+            return;
+        }
+        let floor = self.floor();
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        let rooms: Vec<&PrefabRoom> = PrefabRoom::all()
+            .iter()
+            .filter(|room| room.min_floor <= floor && room.width <= width && room.height <= height)
+            .collect();
+        let total_weight: u32 = rooms.iter().map(|room| room.weight).sum();
+        let mut n = self.rng().range(total_weight);
+        for room in rooms {
+            if n < room.weight {
+                self.stamp_prefab_room(room);
+                return;
+            }
+            n -= room.weight;
+        }
+    }
+
+    // Replaces everything under a random spot that fits `room` with the room's tiles, then reconnects the floor.
+    // The room never covers a player, the exit key, or the exit.
+    fn stamp_prefab_room(&mut self, room: &PrefabRoom) -> bool {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        if room.width > width || room.height > height {
+            return false;
+        }
+        for _ in 0..16 {
+            let left = self.rng().index(width - room.width + 1) as i32;
+            let top = self.rng().index(height - room.height + 1) as i32;
+            let (right, bottom) = (left + room.width as i32, top + room.height as i32);
+            let inside = |x: i32, y: i32| x >= left && y >= top && x < right && y < bottom;
+            let covers_fixed_tile = (top..bottom)
+                .flat_map(|y| (left..right).map(move |x| (x, y)))
+                .any(|(x, y)| self.is_player(x, y) || self.is_exit_key(x, y) || self.is_exit(x, y));
+            if covers_fixed_tile {
+                continue;
+            }
+
+            self.obstacles_mut().retain(|obs| !inside(obs.x, obs.y));
+            self.monsters_mut().retain(|mon| !inside(mon.x, mon.y));
+            self.treasures_mut().retain(|t| !inside(t.x, t.y));
+            for (dy, row) in room.tiles.chunks(room.width).enumerate() {
+                for (dx, tile) in row.iter().enumerate() {
+                    let (x, y) = (left + dx as i32, top + dy as i32);
+                    match tile {
+                        PrefabTile::Floor => {}
+                        PrefabTile::Obstacle(kind) => {
+                            let kind = *kind;
+                            self.obstacles_mut().push(Obstacle { x, y, kind });
+                        }
+                        PrefabTile::Monster(kind) => {
+                            let (health, strength) = kind.stats();
+                            self.monsters_mut().push(Monster {
+                                x,
+                                y,
+                                health,
+                                max_health: health,
+                                strength,
+                                direction: Direction::Down,
+                                kind: *kind,
+                                stun_dur: 0,
+                                effects: StatusEffects::new(),
+                            });
+                        }
+                        PrefabTile::Treasure(kind, value) => {
+                            let (kind, value) = (kind.clone(), *value);
+                            self.treasures_mut().push(Treasure { x, y, value, kind });
+                        }
+                    }
+                }
+            }
+
+            // The room's walls may have cut part of the floor off. Only plain walls get
+            // tunnelled through, so doors and crates stay where the room put them.
+            let is_wall =
+                |kind: &ObstacleKind| matches!(kind, ObstacleKind::WallA | ObstacleKind::WallB);
+            let mut walls: Vec<(i32, i32)> = self
+                .obstacles()
+                .iter()
+                .filter(|obs| is_wall(&obs.kind))
+                .map(|obs| (obs.x, obs.y))
+                .collect();
+            let player = self.player(0);
+            connect_regions(width, height, &mut walls, (player.x, player.y));
+            let walls: HashSet<(i32, i32)> = walls.into_iter().collect();
+            self.obstacles_mut()
+                .retain(|obs| !is_wall(&obs.kind) || walls.contains(&(obs.x, obs.y)));
+            return true;
+        }
+        false
+    }
+
+    // Scatters traps, crates, doors, and (on frozen floors) ice patches over open tiles
+    fn spawn_hazards(&mut self) {
+        let floor = self.floor();
+        if floor == 0 {
+            return;
+        }
+        let (max_x, max_y) = self.bounds();
+        let magic_ratio = ((max_x * max_y) / 32) as usize;
+
+        // Ice patches run in a line so players have room to slide
+        if matches!(
+            self.theme(),
+            DungeonThemeKind::IceCave | DungeonThemeKind::Arctic
+        ) {
+            for _ in 0..1 + magic_ratio {
+                let (mut x, mut y) = self.random_position();
+                let dir = Direction::ALL[self.rng().index(4)];
+                for _ in 0..4 {
+                    if self.is_out_of_bounds(x, y) || self.is_position_occupied(x, y) {
+                        break;
+                    }
+                    let kind = ObstacleKind::IceFloor;
+                    self.obstacles_mut().push(Obstacle { x, y, kind });
+                    (x, y) = dir.step(x, y);
+                }
+            }
+        }
+
+        if floor >= 2 {
+            for _ in 0..1 + (floor / 5).min(magic_ratio as u32 + 2) {
+                self.spawn_hazard(ObstacleKind::SpikeTrap);
+            }
+        }
+        for _ in 0..self.rng().range(2 + magic_ratio as u32) {
+            self.spawn_hazard(ObstacleKind::Crate);
+        }
+
+        // A door with a pressure plate that opens it
+        if floor >= 3 && self.rng().one_in(3) && self.spawn_hazard(ObstacleKind::Door) {
+            self.spawn_hazard(ObstacleKind::PressurePlate);
+        }
+        if floor >= 4 && self.rng().one_in(3) {
+            let dir = Direction::ALL[self.rng().index(4)];
+            self.spawn_hazard(ObstacleKind::OneWayDoor(dir));
+        }
+    }
+
+    // Places an obstacle on an open tile, making sure solid ones don't cut the first player off from the exit key
+    fn spawn_hazard(&mut self, kind: ObstacleKind) -> bool {
+        for _ in 0..16 {
+            let (x, y) = self.random_position();
+            if self.is_position_occupied(x, y) {
+                continue;
+            }
+            self.obstacles_mut().push(Obstacle { x, y, kind });
+            if !kind.is_solid() || self.can_reach_exit_key() {
+                return true;
+            }
+            self.obstacles_mut().pop();
+        }
+        false
+    }
+
+    // Whether the first player can walk to the exit key without going through solid obstacles
+    fn can_reach_exit_key(&self) -> bool {
+        let Some(exit_key) = self.exit_key() else {
+            return true;
+        };
+        let mut paths = Pathfinder::new(self.width(), self.height());
+        for obstacle in self.obstacles() {
+            if obstacle.kind.is_solid() {
+                paths.block(obstacle.x, obstacle.y);
+            }
+        }
+        let player = self.player(0);
+        paths.find_path((player.x, player.y), exit_key).is_some()
+    }
+
+    // Fills the floor with walls from the theme's generator.
+    // Occupied tiles stay open, and walls are knocked down until every open tile is reachable.
+    fn spawn_obstacles(&mut self) {
+        let generator = self.theme().generator();
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        let mut walls = vec![];
+        for (x, y) in generator.generate(self.rng(), width, height) {
+            // 1/3 chance to skip a maze wall
+            if generator == GeneratorKind::RecursiveDivision && self.rng().one_in(3) {
+                continue;
+            }
+            // Make sure spot is empty
+            if self.is_position_occupied(x, y) {
+                continue;
+            }
+            walls.push((x, y));
+        }
+        let player = self.player(0);
+        connect_regions(width, height, &mut walls, (player.x, player.y));
+        for (x, y) in walls {
+            let kind = if self.rng().range(10) == 9 {
+                // 10% chance for firepit
+                ObstacleKind::WallB
+            } else {
+                // 90% chance for stone block
+                ObstacleKind::WallA
+            };
+            self.obstacles_mut().push(Obstacle { x, y, kind });
+        }
+    }
+}
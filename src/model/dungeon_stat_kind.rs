@@ -13,6 +13,10 @@ pub enum DungeonStatKind {
     DamageTaken,
     Defeated(MonsterKind),
     DefeatedBy(MonsterKind),
+    ItemsUsed,
+    GoldSpent,
+    ItemsPurchased,
+    Afflicted(StatusEffectKind),
 }
 impl DungeonStatKind {
     pub const ALL: &'static [Self] = &[
@@ -23,6 +27,9 @@ impl DungeonStatKind {
         Self::DamageDealt,
         Self::DamageTaken,
         Self::StepsMoved,
+        Self::ItemsUsed,
+        Self::GoldSpent,
+        Self::ItemsPurchased,
         Self::Defeated(MonsterKind::BlueBlob),
         Self::Defeated(MonsterKind::Ghost),
         Self::Defeated(MonsterKind::GreenGoblin),
@@ -43,6 +50,10 @@ impl DungeonStatKind {
         Self::DefeatedBy(MonsterKind::Spider),
         Self::DefeatedBy(MonsterKind::YellowBlob),
         Self::DefeatedBy(MonsterKind::Zombie),
+        Self::Afflicted(StatusEffectKind::Poison),
+        Self::Afflicted(StatusEffectKind::Freeze),
+        Self::Afflicted(StatusEffectKind::Slow),
+        Self::Afflicted(StatusEffectKind::Burn),
     ];
     pub const DEFEATED: &'static [Self] = &[
         Self::Defeated(MonsterKind::BlueBlob),
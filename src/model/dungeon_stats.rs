@@ -2,7 +2,7 @@ use super::*;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub struct DungeonStats {
     pub entries: BTreeMap<String, u32>,
 }
@@ -37,3 +37,7 @@ impl DungeonStats {
             .or_insert(amount);
     }
 }
+
+impl Schema for DungeonStats {
+    const VERSION: u16 = 1;
+}
@@ -115,6 +115,16 @@ impl DungeonThemeKind {
             block_b_sprite: "big_stump",
         },
     ];
+    pub fn generator(&self) -> GeneratorKind {
+        match self {
+            Self::Castle => GeneratorKind::RoomsAndCorridors,
+            Self::Crypt => GeneratorKind::Blocks,
+            Self::Pirate => GeneratorKind::RecursiveDivision,
+            Self::Forest => GeneratorKind::Caves,
+            Self::IceCave => GeneratorKind::Caves,
+            Self::Arctic => GeneratorKind::RecursiveDivision,
+        }
+    }
     pub fn theme(&self) -> DungeonTheme {
         match self {
             Self::Castle => Self::THEMES[0],
@@ -0,0 +1,113 @@
+use super::*;
+
+// Dungeon layout shipped before files were versioned (schema v1, files without an envelope)
+#[derive(BorshDeserialize)]
+pub(crate) struct DungeonV1 {
+    crawl_id: u32,
+    theme: DungeonThemeKind,
+    floor: u32,
+    turn: u32,
+    width: u32,
+    height: u32,
+    player: PlayerV1,
+    monsters: Vec<MonsterV1>,
+    treasures: Vec<Treasure>,
+    obstacles: Vec<Obstacle>,
+    exit_key: Option<(i32, i32)>,
+    exit: Option<(i32, i32)>,
+    stats: DungeonStats,
+    total_stats: DungeonStats,
+    unlocked: PlayerAchievements,
+    all_unlocked: PlayerAchievements,
+}
+
+// Player layout before players had an inventory or status effects
+#[derive(BorshDeserialize)]
+pub(crate) struct PlayerV1 {
+    x: i32,
+    y: i32,
+    health: u32,
+    max_health: u32,
+    strength: u32,
+    gold: u32,
+    direction: Direction,
+}
+
+// Monster layout before monsters had status effects
+#[derive(BorshDeserialize)]
+pub(crate) struct MonsterV1 {
+    x: i32,
+    y: i32,
+    health: u32,
+    max_health: u32,
+    strength: u32,
+    direction: Direction,
+    kind: MonsterKind,
+    stun_dur: u32,
+}
+
+impl From<PlayerV1> for Player {
+    fn from(v1: PlayerV1) -> Self {
+        Self {
+            x: v1.x,
+            y: v1.y,
+            health: v1.health,
+            max_health: v1.max_health,
+            strength: v1.strength,
+            gold: v1.gold,
+            direction: v1.direction,
+            inventory: vec![],
+            effects: StatusEffects::new(),
+        }
+    }
+}
+
+impl From<MonsterV1> for Monster {
+    fn from(v1: MonsterV1) -> Self {
+        Self {
+            x: v1.x,
+            y: v1.y,
+            health: v1.health,
+            max_health: v1.max_health,
+            strength: v1.strength,
+            direction: v1.direction,
+            kind: v1.kind,
+            stun_dur: v1.stun_dur,
+            effects: StatusEffects::new(),
+        }
+    }
+}
+
+impl From<DungeonV1> for Dungeon {
+    fn from(v1: DungeonV1) -> Self {
+        // The crawl never had a seed, so it gets a fresh one for the floors still to come.
+        // What came before can't be re-simulated, so the crawl carries on unrecorded.
+        let seed = Rng::new(v1.crawl_id).next_u32();
+        Self {
+            crawl_id: v1.crawl_id,
+            theme: v1.theme,
+            floor: v1.floor,
+            turn: v1.turn,
+            width: v1.width,
+            height: v1.height,
+            player: v1.player.into(),
+            monsters: v1.monsters.into_iter().map(Monster::from).collect(),
+            treasures: v1.treasures,
+            obstacles: v1.obstacles,
+            exit_key: v1.exit_key,
+            exit: v1.exit,
+            stats: v1.stats,
+            total_stats: v1.total_stats,
+            unlocked: v1.unlocked,
+            all_unlocked: v1.all_unlocked,
+            mode: DungeonMode::Classic,
+            seed,
+            rng: Rng::for_floor(seed, v1.floor),
+            danger_tiles: vec![],
+            explored: BTreeSet::new(),
+            actions: 0,
+            events: vec![],
+            replayable: false,
+        }
+    }
+}
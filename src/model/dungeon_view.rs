@@ -0,0 +1,136 @@
+use super::*;
+
+// What a player knows about their solo crawl. Clients watch this instead of the dungeon itself.
+// The seed and RNG would give away every floor and roll still to come, so they stay on the server
+// along with lifetime stats and anything the player hasn't seen.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct DungeonView {
+    pub crawl_id: u32,
+    pub theme: DungeonThemeKind,
+    pub floor: u32,
+    pub turn: u32,
+    pub width: u32,
+    pub height: u32,
+    pub player: Player,
+    pub monsters: Vec<Monster>,
+    pub treasures: Vec<Treasure>,
+    pub obstacles: Vec<Obstacle>,
+    pub exit_key: Option<(i32, i32)>,
+    pub exit: Option<(i32, i32)>,
+    pub stats: DungeonStats,
+    pub unlocked: PlayerAchievements,
+    pub all_unlocked: PlayerAchievements,
+    pub mode: DungeonMode,
+    pub danger_tiles: Vec<DangerTile>,
+    pub explored: BTreeSet<(i32, i32)>,
+    pub actions: u32,
+    pub events: Vec<TurnEvent>,
+}
+impl DungeonView {
+    // Monsters out of sight and anything on unexplored tiles are left out.
+    // Danger tiles stay so players are always warned before a ranged attack lands.
+    // Events are kept if they happened in sight, or are about the stairs, which are never hidden.
+    // Events about monsters point at the view's monsters, so ones about hidden monsters are dropped.
+    pub fn new(dungeon: &Dungeon) -> Self {
+        let fov = dungeon.field_of_view();
+        let is_explored = |x: i32, y: i32| dungeon.explored.contains(&(x, y));
+        let is_visible =
+            |monster: &Monster| monster.tiles().iter().any(|&(x, y)| fov.is_visible(x, y));
+        let mut view_index = vec![None; dungeon.monsters.len()];
+        for (i, idx) in (0..dungeon.monsters.len())
+            .filter(|&idx| is_visible(&dungeon.monsters[idx]))
+            .enumerate()
+        {
+            view_index[idx] = Some(i);
+        }
+        Self {
+            crawl_id: dungeon.crawl_id,
+            theme: dungeon.theme,
+            floor: dungeon.floor,
+            turn: dungeon.turn,
+            width: dungeon.width,
+            height: dungeon.height,
+            player: dungeon.player.clone(),
+            monsters: dungeon
+                .monsters
+                .iter()
+                .filter(|monster| is_visible(monster))
+                .cloned()
+                .collect(),
+            treasures: dungeon
+                .treasures
+                .iter()
+                .filter(|treasure| is_explored(treasure.x, treasure.y))
+                .cloned()
+                .collect(),
+            obstacles: dungeon
+                .obstacles
+                .iter()
+                .filter(|obstacle| is_explored(obstacle.x, obstacle.y))
+                .cloned()
+                .collect(),
+            exit_key: dungeon.exit_key.filter(|&(x, y)| is_explored(x, y)),
+            exit: dungeon.exit,
+            stats: dungeon.stats.clone(),
+            unlocked: dungeon.unlocked.clone(),
+            all_unlocked: dungeon.all_unlocked.clone(),
+            mode: dungeon.mode,
+            danger_tiles: dungeon.danger_tiles.clone(),
+            explored: dungeon.explored.clone(),
+            actions: dungeon.actions,
+            events: dungeon
+                .events
+                .iter()
+                .filter(|event| {
+                    matches!(event, TurnEvent::ExitAppeared { .. })
+                        || event.position().is_none_or(|(x, y)| fov.is_visible(x, y))
+                })
+                .filter_map(|event| {
+                    let mut event = event.clone();
+                    if let Some(monster) = event.monster_mut() {
+                        *monster = view_index[*monster]?;
+                    }
+                    Some(event)
+                })
+                .collect(),
+        }
+    }
+}
+
+// Screens draw with the same helpers as the server, so the fields a view leaves out are filled with blanks
+impl From<DungeonView> for Dungeon {
+    fn from(view: DungeonView) -> Self {
+        Self {
+            crawl_id: view.crawl_id,
+            theme: view.theme,
+            floor: view.floor,
+            turn: view.turn,
+            width: view.width,
+            height: view.height,
+            player: view.player,
+            monsters: view.monsters,
+            treasures: view.treasures,
+            obstacles: view.obstacles,
+            exit_key: view.exit_key,
+            exit: view.exit,
+            stats: view.stats,
+            total_stats: DungeonStats::new(),
+            unlocked: view.unlocked,
+            all_unlocked: view.all_unlocked,
+            mode: view.mode,
+            seed: 0,
+            rng: Rng::new(0),
+            danger_tiles: view.danger_tiles,
+            explored: view.explored,
+            actions: view.actions,
+            events: view.events,
+            // Without the seed the client's copy couldn't be replayed anyway
+            replayable: false,
+        }
+    }
+}
+
+impl Schema for DungeonView {
+    // Views are rewritten on every save, so when the layout changes old ones aren't migrated
+    const VERSION: u16 = 1;
+}
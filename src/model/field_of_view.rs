@@ -0,0 +1,132 @@
+// Tiles visible from one spot, found with recursive shadowcasting.
+// Opaque tiles can be seen themselves but hide whatever is behind them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldOfView {
+    width: i32,
+    height: i32,
+    visible: Vec<bool>,
+}
+impl FieldOfView {
+    // How each of the 8 octants maps a scan's (column, row) onto the grid, as (xx, xy, yx, yy)
+    const OCTANTS: [(i32, i32, i32, i32); 8] = [
+        (1, 0, 0, 1),
+        (0, 1, 1, 0),
+        (0, -1, 1, 0),
+        (-1, 0, 0, 1),
+        (-1, 0, 0, -1),
+        (0, -1, -1, 0),
+        (0, 1, -1, 0),
+        (1, 0, 0, -1),
+    ];
+
+    pub fn new(
+        width: u32,
+        height: u32,
+        origin: (i32, i32),
+        radius: i32,
+        is_opaque: impl Fn(i32, i32) -> bool,
+    ) -> Self {
+        let mut fov = Self::empty(width, height);
+        fov.reveal(origin.0, origin.1);
+        for octant in Self::OCTANTS {
+            fov.cast(origin, radius, 1, (1.0, 0.0), octant, &is_opaque);
+        }
+        fov
+    }
+    // Nothing in sight yet
+    pub fn empty(width: u32, height: u32) -> Self {
+        Self {
+            width: width as i32,
+            height: height as i32,
+            visible: vec![false; (width * height) as usize],
+        }
+    }
+    // Adds everything visible in `other`, so a party sees whatever any of them can
+    pub fn merge(&mut self, other: &Self) {
+        for (x, y) in other.tiles() {
+            self.reveal(x, y);
+        }
+    }
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+    fn reveal(&mut self, x: i32, y: i32) {
+        if let Some(i) = self.index(x, y) {
+            self.visible[i] = true;
+        }
+    }
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| self.visible[i])
+    }
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_visible(x, y))
+    }
+    // Lights one octant row by row from `row` outwards, within the wedge between the two slopes.
+    // Every opaque run splits the wedge: the part before it is scanned recursively, the rest continues here.
+    fn cast(
+        &mut self,
+        origin: (i32, i32),
+        radius: i32,
+        row: i32,
+        (mut start, end): (f32, f32),
+        octant: (i32, i32, i32, i32),
+        is_opaque: &impl Fn(i32, i32) -> bool,
+    ) {
+        if start < end {
+            return;
+        }
+        let (xx, xy, yx, yy) = octant;
+        let mut next_start = start;
+        for distance in row..=radius {
+            let dy = -distance;
+            let mut is_blocked = false;
+            for dx in -distance..=0 {
+                let left = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right {
+                    continue;
+                }
+                if end > left {
+                    break;
+                }
+                let x = origin.0 + dx * xx + dy * xy;
+                let y = origin.1 + dx * yx + dy * yy;
+                if self.index(x, y).is_none() {
+                    continue;
+                }
+                // Rounded corners look more natural than a square view
+                if dx * dx + dy * dy <= radius * radius + radius {
+                    self.reveal(x, y);
+                }
+                let is_wall = is_opaque(x, y);
+                if is_blocked {
+                    if is_wall {
+                        next_start = right;
+                        continue;
+                    }
+                    is_blocked = false;
+                    start = next_start;
+                } else if is_wall {
+                    is_blocked = true;
+                    next_start = right;
+                    self.cast(
+                        origin,
+                        radius,
+                        distance + 1,
+                        (start, left),
+                        octant,
+                        is_opaque,
+                    );
+                }
+            }
+            if is_blocked {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+// Text that floats up from a tile when something happens there, like damage numbers.
+// Sparks burst out of the tile as it appears.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct FloatingText {
+    pub x: i32,
+    pub y: i32,
+    pub text: String,
+    pub color: u32,
+    pub shown_at: usize,
+}
+impl FloatingText {
+    pub const DURATION: usize = 40;
+    pub fn from_event(event: &TurnEvent, shown_at: usize) -> Option<Self> {
+        let (x, y) = event.position()?;
+        let (text, color) = match event {
+            TurnEvent::PlayerDamaged { damage, .. } => (format!("-{damage}"), 0xff4444ff),
+            TurnEvent::MonsterDamaged { damage, .. } => (format!("-{damage}"), 0xffffffff),
+            TurnEvent::TreasureCollected {
+                kind: TreasureKind::Gold | TreasureKind::Chest,
+                value,
+                ..
+            } => (format!("+${value}"), 0xfbf236ff),
+            TurnEvent::ExitKeyFound { .. } => ("KEY!".to_string(), 0xfbf236ff),
+            _ => return None,
+        };
+        Some(Self {
+            x,
+            y,
+            text,
+            color,
+            shown_at,
+        })
+    }
+    pub fn is_expired(&self, now: usize) -> bool {
+        now.saturating_sub(self.shown_at) >= Self::DURATION
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+// The family of layouts a floor's walls are drawn from. Each theme picks one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorKind {
+    // Recursive division maze with a third of its walls knocked out
+    RecursiveDivision,
+    // Open rooms cut out of solid rock and joined by corridors
+    RoomsAndCorridors,
+    // Cellular automata caves
+    Caves,
+    // Small wall blocks tiled across the floor
+    Blocks,
+}
+impl GeneratorKind {
+    pub const ALL: &'static [Self] = &[
+        Self::RecursiveDivision,
+        Self::RoomsAndCorridors,
+        Self::Caves,
+        Self::Blocks,
+    ];
+    // Wall positions for a width x height floor. Open tiles aren't guaranteed to connect.
+    pub fn generate(&self, rng: &mut Rng, width: usize, height: usize) -> Vec<(i32, i32)> {
+        match self {
+            Self::RecursiveDivision => generate_maze(rng, width, height),
+            Self::RoomsAndCorridors => generate_rooms(rng, width, height),
+            Self::Caves => generate_caves(rng, width, height),
+            Self::Blocks => generate_blocks(rng, width, height),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq,
+)]
+pub enum ItemKind {
+    Potion,
+    Bomb,
+    TeleportScroll,
+    RerollScroll,
+}
+impl ItemKind {
+    pub const ALL: &'static [Self] = &[
+        Self::Potion,
+        Self::Bomb,
+        Self::TeleportScroll,
+        Self::RerollScroll,
+    ];
+    // Items that can be found on the floor (reroll scrolls only come from upgrades)
+    pub const DROPS: &'static [Self] = &[Self::Potion, Self::Bomb, Self::TeleportScroll];
+    pub const POTION_HEAL: u32 = 5;
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Potion => "POTION",
+            Self::Bomb => "BOMB",
+            Self::TeleportScroll => "SCROLL",
+            Self::RerollScroll => "REROLL",
+        }
+    }
+}
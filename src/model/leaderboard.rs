@@ -54,53 +54,20 @@ impl Leaderboard {
             .or_insert(vec![entry.clone()]);
         self.find(kind, |entry| entry.crawl_id == crawl_id)
     }
-    pub fn render_entries(
-        &self,
-        crawl_id: u32,
-        mut i: i32,
-        kind: LeaderboardKind,
-        name: &str,
-        x: i32,
-        y: i32, // 9
-    ) {
+    pub fn remove(&mut self, kind: LeaderboardKind, crawl_id: u32) -> Option<LeaderboardEntry> {
         let key = format!("{kind:?}");
-        let mut rank = 0;
-        let mut prev_value = if kind.is_most() { u32::MAX } else { 0 };
-        for entry in self.entries.get(&key).unwrap_or(&vec![]) {
-            // Only increment rank if the value changes
-            if kind.is_most() && entry.score < prev_value {
-                rank += 1;
-                prev_value = entry.score;
-            } else if !kind.is_most() && entry.score > prev_value {
-                rank += 1;
-                prev_value = entry.score;
-            } else if rank == 0 {
-                rank += 1;
-                prev_value = entry.score;
-            }
-            let color: u32 = if crawl_id == entry.crawl_id && tick() % 16 < 8 {
-                0x1e6f50ff
-            } else if entry.name == name {
-                0x6ecb62ff
-            } else {
-                0xacaabdff
-            };
-            text!(
-                "{} {}{:.8} {:>11} ",
-                rank,
-                if rank > 9 { "" } else { " " },
-                entry.name,
-                &format!("{}", entry.score);
-                absolute = true,
-                x = x + 8,
-                y = y + i * 10,
-                color = color
-            );
-
-            i += 1;
-            if i - 3 > 10 {
-                break;
-            }
-        }
+        let leaderboard = self.entries.get_mut(&key)?;
+        let i = leaderboard.iter().position(|e| e.crawl_id == crawl_id)?;
+        Some(leaderboard.remove(i))
     }
+    pub fn entries(&self, kind: LeaderboardKind) -> &[LeaderboardEntry] {
+        let key = format!("{kind:?}");
+        self.entries
+            .get(&key)
+            .map_or(&[], |entries| entries.as_slice())
+    }
+}
+
+impl Schema for Leaderboard {
+    const VERSION: u16 = 1;
 }
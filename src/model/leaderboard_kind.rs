@@ -22,6 +22,15 @@ impl LeaderboardKind {
             _ => true,
         }
     }
+    // Score a crawl's stats earn on this leaderboard
+    pub fn score(&self, stats: &DungeonStats) -> u32 {
+        match self {
+            Self::HighestFloor => stats.get(DungeonStatKind::FloorsCleared) + 1,
+            Self::MostGold => stats.get(DungeonStatKind::GoldCollected),
+            Self::MostKills => stats.total_monsters_defeated(),
+            Self::LeastSteps => stats.get(DungeonStatKind::StepsMoved),
+        }
+    }
     pub fn next(&self) -> Self {
         let i = Self::ALL.binary_search(&self).unwrap() + 1;
         let len = Self::ALL.len();
@@ -0,0 +1,293 @@
+use super::*;
+
+use std::collections::VecDeque;
+
+// Recursive division maze. Returns the wall positions for a width x height grid.
+pub fn generate_maze(rng: &mut Rng, width: usize, height: usize) -> Vec<(i32, i32)> {
+    let mut grid = vec![vec![false; width]; height];
+    let mut walls = vec![];
+
+    fn divide(
+        rng: &mut Rng,
+        grid: &mut [Vec<bool>],
+        walls: &mut Vec<(i32, i32)>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        if width <= 3 || height <= 3 {
+            return;
+        }
+
+        let horizontal = rng.one_in(2);
+
+        if horizontal {
+            let max_wall_y = y + height - 2;
+            let min_wall_y = y + 1;
+            if max_wall_y < min_wall_y {
+                return;
+            }
+            let wall_y = min_wall_y + rng.index((max_wall_y - min_wall_y) / 2 + 1) * 2;
+
+            for (i, cell) in grid[wall_y].iter_mut().enumerate().skip(x).take(width) {
+                *cell = true;
+                walls.push((i as i32, wall_y as i32));
+            }
+
+            let passage_x = x + rng.index(width);
+            grid[wall_y][passage_x] = false;
+            walls.retain(|&(wx, wy)| !(wx == passage_x as i32 && wy == wall_y as i32));
+
+            // Ensure at least one passage in the adjacent walls
+            if wall_y > 0
+                && wall_y + 1 < grid.len()
+                && !grid[wall_y - 1][passage_x]
+                && !grid[wall_y + 1][passage_x]
+            {
+                grid[wall_y][passage_x] = false;
+                walls.retain(|&(wx, wy)| !(wx == passage_x as i32 && wy == wall_y as i32));
+            }
+
+            divide(rng, grid, walls, x, y, width, wall_y - y);
+            divide(
+                rng,
+                grid,
+                walls,
+                x,
+                wall_y + 1,
+                width,
+                y + height - wall_y - 1,
+            );
+        } else {
+            let max_wall_x = x + width - 2;
+            let min_wall_x = x + 1;
+            if max_wall_x < min_wall_x {
+                return;
+            }
+            let wall_x = min_wall_x + rng.index((max_wall_x - min_wall_x) / 2 + 1) * 2;
+
+            for (i, row) in grid.iter_mut().enumerate().skip(y).take(height) {
+                row[wall_x] = true;
+                walls.push((wall_x as i32, i as i32));
+            }
+
+            let passage_y = y + rng.index(height);
+            grid[passage_y][wall_x] = false;
+            walls.retain(|&(wx, wy)| !(wx == wall_x as i32 && wy == passage_y as i32));
+
+            // Ensure at least one passage in the adjacent walls
+            if wall_x > 0
+                && wall_x + 1 < grid[0].len()
+                && !grid[passage_y][wall_x - 1]
+                && !grid[passage_y][wall_x + 1]
+            {
+                grid[passage_y][wall_x] = false;
+                walls.retain(|&(wx, wy)| !(wx == wall_x as i32 && wy == passage_y as i32));
+            }
+
+            divide(rng, grid, walls, x, y, wall_x - x, height);
+            divide(
+                rng,
+                grid,
+                walls,
+                wall_x + 1,
+                y,
+                x + width - wall_x - 1,
+                height,
+            );
+        }
+    }
+
+    divide(rng, &mut grid, &mut walls, 0, 0, width, height);
+    walls
+}
+
+// Rooms carved out of solid rock, each joined to the last by an L-shaped corridor. Returns the remaining rock.
+pub fn generate_rooms(rng: &mut Rng, width: usize, height: usize) -> Vec<(i32, i32)> {
+    let mut grid = vec![vec![true; width]; height];
+    let mut rooms: Vec<(usize, usize, usize, usize)> = vec![];
+    for _ in 0..(width * height) / 8 {
+        let (w, h) = (2 + rng.index(3), 2 + rng.index(3));
+        if w >= width || h >= height {
+            continue;
+        }
+        let x = rng.index(width - w + 1);
+        let y = rng.index(height - h + 1);
+        // Keep at least one tile of rock between rooms
+        let overlaps = rooms
+            .iter()
+            .any(|&(rx, ry, rw, rh)| x <= rx + rw && rx <= x + w && y <= ry + rh && ry <= y + h);
+        if overlaps {
+            continue;
+        }
+        for row in grid.iter_mut().skip(y).take(h) {
+            for cell in row.iter_mut().skip(x).take(w) {
+                *cell = false;
+            }
+        }
+        let center = (x + w / 2, y + h / 2);
+        if let Some(&(rx, ry, rw, rh)) = rooms.last() {
+            let prev = (rx + rw / 2, ry + rh / 2);
+            // Go across then down, or down then across
+            let corner = if rng.one_in(2) {
+                (center.0, prev.1)
+            } else {
+                (prev.0, center.1)
+            };
+            for (from, to) in [(prev, corner), (corner, center)] {
+                for row in grid
+                    .iter_mut()
+                    .take(from.1.max(to.1) + 1)
+                    .skip(from.1.min(to.1))
+                {
+                    for cell in row
+                        .iter_mut()
+                        .take(from.0.max(to.0) + 1)
+                        .skip(from.0.min(to.0))
+                    {
+                        *cell = false;
+                    }
+                }
+            }
+        }
+        rooms.push((x, y, w, h));
+    }
+    walls_in(&grid)
+}
+
+// Cellular automata caves: random noise smoothed until it clumps into rock and open caverns
+pub fn generate_caves(rng: &mut Rng, width: usize, height: usize) -> Vec<(i32, i32)> {
+    let mut grid: Vec<Vec<bool>> = (0..height)
+        .map(|_| (0..width).map(|_| rng.range(100) < 40).collect())
+        .collect();
+    for _ in 0..3 {
+        let prev = grid.clone();
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                // Rock around the edge of the floor doesn't count, so caves open onto the border
+                let neighbors = (y.saturating_sub(1)..(y + 2).min(height))
+                    .flat_map(|ny| {
+                        (x.saturating_sub(1)..(x + 2).min(width)).map(move |nx| (nx, ny))
+                    })
+                    .filter(|&(nx, ny)| (nx, ny) != (x, y) && prev[ny][nx])
+                    .count();
+                if neighbors >= 5 {
+                    *cell = true;
+                } else if neighbors <= 2 {
+                    *cell = false;
+                }
+            }
+        }
+    }
+    walls_in(&grid)
+}
+
+// 3x3 wall blocks laid out in a grid, with a one tile corridor around each. '#' is a wall.
+const BLOCKS: &[[&str; 3]] = &[
+    ["#.#", "...", "#.#"],
+    ["##.", "#..", "..."],
+    [".#.", ".#.", "..."],
+    ["...", ".#.", "..."],
+    ["#..", "...", "..#"],
+    ["###", "...", "#.#"],
+];
+
+// Tiles the floor with randomly picked (and sometimes mirrored) blocks
+pub fn generate_blocks(rng: &mut Rng, width: usize, height: usize) -> Vec<(i32, i32)> {
+    let mut walls = vec![];
+    for block_y in (0..height).step_by(4) {
+        for block_x in (0..width).step_by(4) {
+            let block = BLOCKS[rng.index(BLOCKS.len())];
+            let mirrored = rng.one_in(2);
+            for (dy, line) in block.iter().enumerate() {
+                for (dx, tile) in line.chars().enumerate() {
+                    let dx = if mirrored { 2 - dx } else { dx };
+                    let (x, y) = (block_x + dx, block_y + dy);
+                    if tile == '#' && x < width && y < height {
+                        walls.push((x as i32, y as i32));
+                    }
+                }
+            }
+        }
+    }
+    walls
+}
+
+// Removes walls until every open tile can be walked to from `start`.
+// Each cut-off region gets a tunnel through as few walls as possible.
+pub fn connect_regions(
+    width: usize,
+    height: usize,
+    walls: &mut Vec<(i32, i32)>,
+    start: (i32, i32),
+) {
+    loop {
+        let mut paths = Pathfinder::new(width as u32, height as u32);
+        for &(x, y) in walls.iter() {
+            paths.block(x, y);
+        }
+        let distances = paths.distances(start);
+        let is_reached = |x: i32, y: i32| paths.distance(&distances, x, y).is_some();
+        let cut_off = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| (x, y)))
+            .find(|&(x, y)| paths.is_walkable(x, y) && !is_reached(x, y));
+        let Some(from) = cut_off else {
+            return;
+        };
+
+        // 0-1 BFS where stepping onto a wall costs 1, stopping at the first reached tile
+        let index = |(x, y): (i32, i32)| y as usize * width + x as usize;
+        let mut cost = vec![u32::MAX; width * height];
+        let mut prev = vec![None; width * height];
+        let mut queue = VecDeque::from([from]);
+        cost[index(from)] = 0;
+        let mut end = None;
+        while let Some(tile) = queue.pop_front() {
+            if is_reached(tile.0, tile.1) {
+                end = Some(tile);
+                break;
+            }
+            for dir in Direction::ALL {
+                let next = dir.step(tile.0, tile.1);
+                if next.0 < 0 || next.1 < 0 || next.0 >= width as i32 || next.1 >= height as i32 {
+                    continue;
+                }
+                let step = u32::from(!paths.is_walkable(next.0, next.1));
+                let next_cost = cost[index(tile)] + step;
+                if next_cost < cost[index(next)] {
+                    cost[index(next)] = next_cost;
+                    prev[index(next)] = Some(tile);
+                    if step == 0 {
+                        queue.push_front(next);
+                    } else {
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        // Knock down the walls along the tunnel
+        let mut tile = end;
+        while let Some(t) = tile {
+            walls.retain(|&wall| wall != t);
+            tile = prev[index(t)];
+        }
+        // Nothing could be dug out, so give up on the rest
+        if end.is_none() {
+            return;
+        }
+    }
+}
+
+fn walls_in(grid: &[Vec<bool>]) -> Vec<(i32, i32)> {
+    let mut walls = vec![];
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &is_wall) in row.iter().enumerate() {
+            if is_wall {
+                walls.push((x as i32, y as i32));
+            }
+        }
+    }
+    walls
+}
@@ -6,21 +6,63 @@ pub use achievement_kind::*;
 mod achievements_modal;
 pub use achievements_modal::*;
 
+mod balance_report;
+pub use balance_report::*;
+
+mod boss_attack;
+pub use boss_attack::*;
+
+mod boss_phase;
+pub use boss_phase::*;
+
 mod cloud;
 pub use cloud::*;
 
+mod combat_log;
+pub use combat_log::*;
+
 mod confetti;
 pub use confetti::*;
 
+mod crawl_bot;
+pub use crawl_bot::*;
+
+mod crawl_replay;
+pub use crawl_replay::*;
+
 mod multiplayer_dungeon;
 pub use multiplayer_dungeon::*;
 
+mod multiplayer_dungeon_v1;
+use multiplayer_dungeon_v1::*;
+
+mod multiplayer_dungeon_view;
+pub use multiplayer_dungeon_view::*;
+
+mod danger_tile;
+pub use danger_tile::*;
+
+mod deep_pace;
+pub use deep_pace::*;
+
 mod direction;
 pub use direction::*;
 
 mod dungeon;
 pub use dungeon::*;
 
+mod dungeon_mode;
+pub use dungeon_mode::*;
+
+mod dungeon_rules;
+pub use dungeon_rules::*;
+
+mod dungeon_v1;
+use dungeon_v1::*;
+
+mod dungeon_view;
+pub use dungeon_view::*;
+
 mod dungeon_stats;
 pub use dungeon_stats::*;
 
@@ -39,6 +81,18 @@ pub use emote::*;
 mod entity;
 pub use entity::*;
 
+mod field_of_view;
+pub use field_of_view::*;
+
+mod floating_text;
+pub use floating_text::*;
+
+mod generator_kind;
+pub use generator_kind::*;
+
+mod item_kind;
+pub use item_kind::*;
+
 mod leaderboard;
 pub use leaderboard::*;
 
@@ -48,12 +102,27 @@ pub use leaderboard_kind::*;
 mod leaderboard_entry;
 pub use leaderboard_entry::*;
 
+mod maze;
+pub use maze::*;
+
 mod monster;
 pub use monster::*;
 
+mod monster_def;
+pub use monster_def::*;
+
 mod monster_kind;
 pub use monster_kind::*;
 
+mod monster_movement;
+pub use monster_movement::*;
+
+mod monster_pace;
+pub use monster_pace::*;
+
+mod monster_spawn;
+pub use monster_spawn::*;
+
 mod obstacle;
 pub use obstacle::*;
 
@@ -63,20 +132,89 @@ pub use obstacle_kind::*;
 mod particle;
 pub use particle::*;
 
+mod pathfinder;
+pub use pathfinder::*;
+
+mod pathing_bot;
+pub use pathing_bot::*;
+
+mod prefab_room;
+pub use prefab_room::*;
+
+mod prefab_room_error;
+pub use prefab_room_error::*;
+
+mod prefab_tile;
+pub use prefab_tile::*;
+
 mod player;
 pub use player::*;
 
 mod player_achievements;
 pub use player_achievements::*;
 
+mod player_upgrades;
+pub use player_upgrades::*;
+
+mod random_bot;
+pub use random_bot::*;
+
+mod ranged_attack;
+pub use ranged_attack::*;
+
 mod raindrop;
 pub use raindrop::*;
 
+mod rng;
+pub use rng::*;
+
+mod schema;
+pub use schema::*;
+
+mod score_mismatch;
+pub use score_mismatch::*;
+
+mod stale_command;
+pub use stale_command::*;
+
+mod shop_item;
+pub use shop_item::*;
+
 mod snowflake;
 pub use snowflake::*;
 
+mod spawn_table;
+pub use spawn_table::*;
+
+mod status_effect;
+pub use status_effect::*;
+
+mod status_effect_kind;
+pub use status_effect_kind::*;
+
+mod status_effects;
+pub use status_effects::*;
+
+mod toast;
+pub use toast::*;
+
+mod travel_target;
+pub use travel_target::*;
+
 mod treasure;
 pub use treasure::*;
 
 mod treasure_kind;
 pub use treasure_kind::*;
+
+mod turn_event;
+pub use turn_event::*;
+
+mod upgrade_kind;
+pub use upgrade_kind::*;
+
+mod versioned;
+pub use versioned::*;
+
+#[cfg(test)]
+mod tests;
@@ -2,7 +2,7 @@ use super::*;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub struct Monster {
     pub x: i32,
     pub y: i32,
@@ -12,4 +12,44 @@ pub struct Monster {
     pub direction: Direction,
     pub kind: MonsterKind,
     pub stun_dur: u32,
+    pub effects: StatusEffects,
+}
+impl Monster {
+    // Number of monster turns skipped after being hit by a player
+    pub const STUN_DUR: u32 = 2;
+    // Bosses attack every few turns, winding up the turn before
+    pub const BOSS_ATTACK_EVERY: u32 = 4;
+
+    pub fn size(&self) -> i32 {
+        self.kind.size()
+    }
+    // Whether (x, y) is covered by the monster's footprint
+    pub fn occupies(&self, x: i32, y: i32) -> bool {
+        let size = self.size();
+        x >= self.x && x < self.x + size && y >= self.y && y < self.y + size
+    }
+    pub fn tiles(&self) -> Vec<(i32, i32)> {
+        let size = self.size();
+        (0..size)
+            .flat_map(|dy| (0..size).map(move |dx| (self.x + dx, self.y + dy)))
+            .collect()
+    }
+    // Steps from the nearest tile of the footprint to (x, y), ignoring walls
+    pub fn distance_to(&self, x: i32, y: i32) -> i32 {
+        let far = self.size() - 1;
+        let dx = (self.x - x).max(x - (self.x + far)).max(0);
+        let dy = (self.y - y).max(y - (self.y + far)).max(0);
+        dx + dy
+    }
+    // Bosses get angrier below half health
+    pub fn phase(&self) -> u32 {
+        if self.health * 2 > self.max_health {
+            1
+        } else {
+            2
+        }
+    }
+    pub fn is_boss_attack_turn(clock: u32) -> bool {
+        clock % Self::BOSS_ATTACK_EVERY == Self::BOSS_ATTACK_EVERY - 1
+    }
 }
@@ -0,0 +1,117 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// A monster's stats, behavior, and spawn rules, loaded from monsters.json
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MonsterDef {
+    pub kind: MonsterKind,
+    // Short name shown in the log
+    pub name: String,
+    pub health: u32,
+    pub strength: u32,
+    #[serde(default)]
+    pub movement: MonsterMovement,
+    // Replaces `movement` once the monster is hurt
+    #[serde(default)]
+    pub wounded_movement: Option<MonsterMovement>,
+    // Turns the monster moves on
+    #[serde(default)]
+    pub pace: MonsterPace,
+    // Replaces `pace` on deeper floors
+    #[serde(default)]
+    pub deep_pace: Option<DeepPace>,
+    // What a monster that absorbs this one turns into (see `MonsterMovement::Haunt`)
+    #[serde(default)]
+    pub absorbed_into: Option<MonsterKind>,
+    #[serde(default)]
+    pub ranged_attack: Option<RangedAttack>,
+    // Status effect inflicted by the monster's bite
+    #[serde(default)]
+    pub melee_effect: Option<StatusEffectKind>,
+    // Status effect inflicted by the monster's ranged attack
+    #[serde(default)]
+    pub ranged_effect: Option<StatusEffectKind>,
+    // Bosses guard the exit key on boss floors
+    #[serde(default)]
+    pub is_boss: bool,
+    // A boss's attacks and pace before and after it's enraged at half health
+    #[serde(default)]
+    pub boss_phases: Vec<BossPhase>,
+    #[serde(default)]
+    pub spawns: Vec<MonsterSpawn>,
+}
+impl MonsterDef {
+    // Every monster's definition, in `MonsterKind::ALL` order
+    pub fn all() -> &'static [MonsterDef] {
+        static DEFS: OnceLock<Vec<MonsterDef>> = OnceLock::new();
+        DEFS.get_or_init(|| {
+            Self::parse(include_str!("monsters.json"))
+                .unwrap_or_else(|err| panic!("monsters.json: {err}"))
+        })
+    }
+    // Turns the monster moves on, on `floor` (counting from 0)
+    pub fn pace(&self, floor: u32) -> MonsterPace {
+        match self.deep_pace {
+            Some(deep) if floor >= deep.from_floor => deep.pace,
+            _ => self.pace,
+        }
+    }
+    // How a boss fights in `phase` (see `Monster::phase`), sticking with its last phase
+    pub fn boss_phase(&self, phase: u32) -> Option<BossPhase> {
+        let i = (phase.max(1) as usize - 1).min(self.boss_phases.len().checked_sub(1)?);
+        Some(self.boss_phases[i])
+    }
+    // Parses a monster table, making sure every kind is defined exactly once,
+    // every ranged attack gets a turn to aim, and every boss knows how to attack
+    pub fn parse(json: &str) -> Result<Vec<Self>, String> {
+        let defs: Vec<Self> = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        if let Some(def) = defs
+            .iter()
+            .find(|def| def.ranged_attack.is_some_and(|attack| attack.every < 2))
+        {
+            return Err(format!(
+                "{:?} must take aim every 2 turns or more",
+                def.kind
+            ));
+        }
+        if let Some(def) = defs
+            .iter()
+            .find(|def| def.is_boss && def.boss_phases.is_empty())
+        {
+            return Err(format!("{:?} is a boss without any phases", def.kind));
+        }
+        MonsterKind::ALL
+            .iter()
+            .map(|kind| {
+                let mut matches = defs.iter().filter(|def| def.kind == *kind);
+                match (matches.next(), matches.next()) {
+                    (Some(def), None) => Ok(def.clone()),
+                    (None, _) => Err(format!("{kind:?} is missing")),
+                    (Some(_), Some(_)) => Err(format!("{kind:?} is defined more than once")),
+                }
+            })
+            .collect()
+    }
+    // Spawn weights for every monster that can show up on a floor
+    pub fn spawn_weights(
+        table: SpawnTable,
+        theme: DungeonThemeKind,
+        floor: u32,
+        is_winter: bool,
+    ) -> Vec<(u32, MonsterKind)> {
+        Self::all()
+            .iter()
+            .filter_map(|def| {
+                let weight: u32 = def
+                    .spawns
+                    .iter()
+                    .filter(|spawn| spawn.applies(table, theme, floor, is_winter))
+                    .map(|spawn| spawn.weight)
+                    .sum();
+                (weight > 0).then_some((weight, def.kind))
+            })
+            .collect()
+    }
+}
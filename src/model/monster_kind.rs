@@ -30,6 +30,7 @@ pub enum MonsterKind {
     EvilTurbi,
     IceYeti,
     Snowman,
+    YetiKing,
 }
 impl MonsterKind {
     pub const ALL: &'static [Self] = &[
@@ -46,57 +47,54 @@ impl MonsterKind {
         Self::EvilTurbi,
         Self::IceYeti,
         Self::Snowman,
+        Self::YetiKing,
     ];
     pub fn by_index(n: usize) -> Self {
-        match n % 10 {
-            0 => Self::GreenGoblin,
-            1 => Self::OrangeGoblin,
-            2 => Self::YellowBlob,
-            3 => Self::BlueBlob,
-            4 => Self::RedBlob,
-            5 => Self::Shade,
-            6 => Self::Spider,
-            7 => Self::Ghost,
-            8 => Self::SpectralGhost,
-            9 => Self::Zombie,
-            10 => Self::EvilTurbi,
-            11 => Self::IceYeti,
-            12 => Self::Snowman,
-            _ => unreachable!(),
-        }
+        Self::ALL[n % Self::ALL.len()]
+    }
+    // Stats and behavior from monsters.json
+    pub fn def(&self) -> &'static MonsterDef {
+        let i = Self::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        &MonsterDef::all()[i]
     }
     pub fn abbrev<'a>(&self) -> &'a str {
-        match self {
-            Self::BlueBlob => "B. Blob",
-            Self::RedBlob => "R. Blob",
-            Self::YellowBlob => "Y. Blob",
-            Self::GreenGoblin => "G. Goblin",
-            Self::OrangeGoblin => "O. Goblin",
-            Self::Shade => "Shade",
-            Self::Spider => "Spider",
-            Self::Ghost => "Ghost",
-            Self::SpectralGhost => "S. Ghost",
-            Self::Zombie => "Zombie",
-            Self::EvilTurbi => "E. Turbi",
-            Self::IceYeti => "Ice Yeti",
-            Self::Snowman => "Snowman",
+        &self.def().name
+    }
+    // Bosses guard the exit key on boss floors
+    pub fn is_boss(&self) -> bool {
+        self.def().is_boss
+    }
+    // Width and height in tiles
+    pub fn size(&self) -> i32 {
+        if self.is_boss() {
+            2
+        } else {
+            1
+        }
+    }
+    pub fn movement(&self, is_wounded: bool) -> MonsterMovement {
+        let def = self.def();
+        match def.wounded_movement {
+            Some(movement) if is_wounded => movement,
+            _ => def.movement,
         }
     }
+    // (range, every), see `RangedAttack`
+    pub fn ranged_attack(&self) -> Option<(i32, u32)> {
+        let attack = self.def().ranged_attack?;
+        Some((attack.range, attack.every))
+    }
+    // Status effect inflicted by the monster's bite
+    pub fn melee_effect(&self) -> Option<StatusEffectKind> {
+        self.def().melee_effect
+    }
+    // Status effect inflicted by the monster's ranged attack (snowballs freeze, webs slow)
+    pub fn ranged_effect(&self) -> Option<StatusEffectKind> {
+        self.def().ranged_effect
+    }
     pub fn stats(&self) -> (u32, u32) {
         // (hp, strength)
-        match self {
-            Self::OrangeGoblin => (5, 1),
-            Self::GreenGoblin => (2, 1),
-            Self::RedBlob => (3, 2),
-            Self::YellowBlob => (2, 1),
-            Self::Shade => (3, 2),
-            Self::Spider => (4, 2),
-            Self::Ghost => (2, 2),
-            Self::Zombie => (3, 3),
-            Self::EvilTurbi => (3, 3),
-            Self::IceYeti => (6, 1),
-            Self::Snowman => (3, 3),
-            _ => (1, 1),
-        }
+        let def = self.def();
+        (def.health, def.strength)
     }
 }
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+// How a monster gets around the floor. Pathing policies walk around walls using the `Pathfinder`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum MonsterMovement {
+    // Follows the shortest path to the player
+    #[default]
+    Chase,
+    // Backs away from the player while they are within `range` steps
+    Flee { range: u32 },
+    // Wanders until the player is within `range` steps, then chases.
+    // Only wanders on turns that aren't a multiple of `rest_every` (1 = never rests).
+    Patrol { range: u32, rest_every: u32 },
+    // Lies in wait between lunges of up to `leap` tiles along the path to the player
+    Ambush { leap: u32 },
+    // Drifts straight at the player through walls
+    Phase,
+    // Drifts through walls towards nearby monsters it can absorb (see `MonsterDef::absorbed_into`).
+    // Otherwise it closes in once the player is within `range` tiles on either axis,
+    // resting on turns that are a multiple of `rest_every`.
+    Haunt { range: i32, rest_every: u32 },
+    // Heads for the exit or the exit key when it can't get to the player
+    Guard,
+    // Moves like a random monster each turn (and just chases when it picks itself)
+    Mimic,
+}
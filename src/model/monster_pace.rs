@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+// Which turns a monster gets to move on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum MonsterPace {
+    #[default]
+    EveryTurn,
+    // Only moves on turns that are a multiple of n
+    Every(u32),
+    // Moves on every turn except multiples of n
+    RestEvery(u32),
+}
+impl MonsterPace {
+    pub fn moves_on(&self, clock: u32) -> bool {
+        match self {
+            Self::EveryTurn => true,
+            Self::Every(n) => clock.is_multiple_of(*n),
+            Self::RestEvery(n) => !clock.is_multiple_of(*n),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// Where and how often a monster shows up
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MonsterSpawn {
+    pub tables: Vec<SpawnTable>,
+    // Every theme when empty
+    #[serde(default)]
+    pub themes: Vec<DungeonThemeKind>,
+    pub weight: u32,
+    // First floor (counting from 0) the entry applies to
+    #[serde(default)]
+    pub min_floor: u32,
+    #[serde(default)]
+    pub winter_only: bool,
+}
+impl MonsterSpawn {
+    pub fn applies(
+        &self,
+        table: SpawnTable,
+        theme: DungeonThemeKind,
+        floor: u32,
+        is_winter: bool,
+    ) -> bool {
+        self.tables.contains(&table)
+            && (self.themes.is_empty() || self.themes.contains(&theme))
+            && floor >= self.min_floor
+            && (is_winter || !self.winter_only)
+    }
+}
@@ -51,7 +51,7 @@ impl PlayerState {
         self.players.keys().position(|user_id| user_id == player_id)
     }
     // Modify a player’s context without holding a mutable borrow on the whole map
-    pub fn modify_player<F>(&mut self, player_id: &str, modify_fn: F) -> Result<(), &str>
+    pub fn modify_player<F>(&mut self, player_id: &str, modify_fn: F) -> Result<(), &'static str>
     where
         F: FnOnce(&mut PlayerContext),
     {
@@ -82,744 +82,192 @@ pub struct MultiplayerDungeon {
     pub exit_key: Option<(i32, i32)>,
     pub exit: Option<(i32, i32)>,
     pub player: PlayerState,
+    pub seed: u32,
+    pub rng: Rng,
+    pub danger_tiles: Vec<DangerTile>,
+    // Tiles any player has seen on this floor
+    pub explored: BTreeSet<(i32, i32)>,
+    // What happened since the last player moved, including the monsters' turn if the round ended
+    pub events: Vec<TurnEvent>,
 }
 impl MultiplayerDungeon {
-    pub fn move_player(&mut self, user_id: &str, direction: Direction) -> Result<(), &str> {
-        unsafe {
-            // get mut ptr
-            let self_ptr = self as *mut Self;
-
-            // Get player
-            let Some(ctx) = self.player.players.get(user_id) else {
-                os::server::log!("Player is not in this dungeon");
-                return Err("Player is not in this dungeon");
-            };
+    pub fn move_player(
+        &mut self,
+        user_id: &str,
+        direction: Direction,
+        log: fn(&str),
+    ) -> Result<(), &'static str> {
+        // Get player
+        let (Some(i), Some(ctx)) = (self.player.get_index(user_id), self.player.get(user_id))
+        else {
+            log("Player is not in this dungeon");
+            return Err("Player is not in this dungeon");
+        };
+
+        // Ensure player can move this round
+        if ctx.next_round != self.round {
+            log(&format!(
+                "Player cannot move until round {}",
+                ctx.next_round
+            ));
+            return Err("Player cannot move this turn");
+        }
 
-            // Ensure player is still alive
+        // Move, attack, or swap places with a stunned monster
+        self.events.clear();
+        self.player_turn(i, direction, log)?;
+        self.player.modify_player(user_id, |ctx| {
+            ctx.next_round += 1;
+        })?;
+        self.explore();
+        Ok(())
+    }
+    // Players move once per round, so a move is identified by its floor and round.
+    // Turns away moves sent for a round that's over, or for one the player already moved in.
+    pub fn check_command(&self, user_id: &str, floor: u32, round: u32) -> Result<(), StaleCommand> {
+        if floor != self.floor {
+            return Err(StaleCommand::WrongFloor {
+                expected: self.floor,
+                received: floor,
+            });
+        }
+        if round > self.round {
+            return Err(StaleCommand::OutOfOrder {
+                expected: self.round,
+                received: round,
+            });
+        }
+        let has_moved = self
+            .player
+            .get(user_id)
+            .is_some_and(|ctx| ctx.next_round > round);
+        if round < self.round || has_moved {
+            return Err(StaleCommand::Duplicate { received: round });
+        }
+        Ok(())
+    }
+    pub fn move_monsters(&mut self, log: fn(&str)) {
+        self.monsters_turn(log)
+    }
+    // Clears the current floor, moves down to the next one, and scatters the players
+    pub fn next_floor(&mut self) -> Result<(), &'static str> {
+        // Remove exit
+        self.exit = None;
+
+        // Clear monsters, treasures, and obstacles
+        self.monsters.clear();
+        self.treasures.clear();
+        self.obstacles.clear();
+        self.danger_tiles.clear();
+        self.events.clear();
+        self.explored.clear();
+
+        // Increase floor
+        self.floor += 1;
+
+        // Increment floor stats and reset next round
+        for ctx in self.player.players.values_mut() {
+            ctx.increment_stats(DungeonStatKind::FloorsCleared, 1);
+            ctx.next_round = 0;
             if ctx.player.health == 0 {
-                os::server::log!("Player is dead");
-                return Err("Player is dead");
-            }
-
-            // Ensure player can move this round
-            if ctx.next_round != self.round {
-                os::server::log!("Player cannot move until round {}", ctx.next_round);
-                return Err("Player cannot move this turn");
-            }
-
-            // Get next position based on movement direction
-            let Player { x, y, .. } = ctx.player;
-            let (new_x, new_y) = match direction {
-                Direction::Up => (x, y - 1),
-                Direction::Down => (x, y + 1),
-                Direction::Left => (x - 1, y),
-                Direction::Right => (x + 1, y),
-            };
-
-            // Ensure player is in bounds
-            if self.is_out_of_bounds(new_x, new_y) {
-                os::server::log!("Player cannot move out-of-bounds");
-                return Err("Player cannot move out-of-bounds");
-            }
-
-            // Ensure player is not blocked by an obstacle
-            if self.is_obstacle(new_x, new_y) {
-                os::server::log!("Player is blocked by an obstacle");
-                return Err("Player is blocked by an obstacle");
+                ctx.player.health = 1;
             }
+        }
 
-            // Ensure player is not blocked by another player
-            if self.is_player(new_x, new_y) {
-                os::server::log!("Player is blocked by another player");
-                return Err("Player is blocked by another player");
-            }
+        // Update dungeon theme
+        let is_winter = true;
+        self.theme = if is_winter {
+            DungeonThemeKind::WINTER[self.rng.index(DungeonThemeKind::WINTER.len())]
+        } else {
+            DungeonThemeKind::ALL[self.rng.index(DungeonThemeKind::ALL.len())]
+        };
 
-            // If player moves towards an adjacent monster...
-            if self.is_monster(new_x, new_y) {
-                // Find the monster
-                if let Some(monster) = self
-                    .monsters
-                    .iter_mut()
-                    .find(|m| m.x == new_x && m.y == new_y && m.health > 0)
-                {
-                    // Swap positions with the stunned monsters
-                    if monster.stun_dur > 0 {
-                        os::server::log!("Player swapped positions with {:?}", monster.kind);
-                        return self.player.modify_player(user_id, |ctx| {
-                            std::mem::swap(&mut ctx.player.x, &mut monster.x);
-                            std::mem::swap(&mut ctx.player.y, &mut monster.y);
-                            ctx.next_round += 1;
-                        });
-                    }
+        // Embiggen every 3 floors
+        if self.floor.is_multiple_of(3) {
+            self.width += 2;
+            self.height += 2;
+        }
 
-                    // Calculate and apply damage to monster
-                    os::server::log!("Player attacks {:?}!", monster.kind);
-                    let amount = ctx.player.strength;
-                    let prev_monster_health = monster.health;
-                    monster.health = monster.health.saturating_sub(amount);
-                    let damage = prev_monster_health.abs_diff(monster.health);
-                    os::server::log!("Player did {damage} damage");
-                    self.player.modify_player(user_id, |ctx| {
-                        ctx.increment_stats(DungeonStatKind::DamageDealt, damage);
+        // Reset turn
+        self.round = 0;
+
+        // Randomize positions
+        let user_ids: Vec<_> = self.player.players.keys().cloned().collect();
+        for user_id in user_ids {
+            loop {
+                let x = self.rng.range(self.width) as i32;
+                let y = self.rng.range(self.height) as i32;
+                if !self.is_position_occupied(x, y) {
+                    self.player.modify_player(&user_id, |ctx| {
+                        ctx.player.x = x;
+                        ctx.player.y = y;
                     })?;
-
-                    // Apply stun to monster
-                    monster.stun_dur = 1;
-
-                    // If monster was defeated...
-                    if monster.health == 0 {
-                        os::server::log!("Player defeated {:?}", monster.kind);
-                        (*self_ptr).player.modify_player(user_id, |ctx| {
-                            ctx.increment_stats(DungeonStatKind::Defeated(monster.kind), 1);
-                        })?;
-                    }
-                }
-
-                // If all monsters are defeated, spawn a treasure
-                if self.monsters.iter().all(|m| m.health == 0) {
-                    match os::server::random_number::<u8>() % 4 {
-                        3 | 2 => {
-                            self.treasures.push(Treasure {
-                                x: new_x,
-                                y: new_y,
-                                value: 2,
-                                kind: TreasureKind::HealthUp,
-                            });
-                        }
-                        1 => {
-                            self.treasures.push(Treasure {
-                                x: new_x,
-                                y: new_y,
-                                value: 50,
-                                kind: TreasureKind::Gold,
-                            });
-                        }
-                        _ => {
-                            self.treasures.push(Treasure {
-                                x: new_x,
-                                y: new_y,
-                                value: 1,
-                                kind: TreasureKind::Heal,
-                            });
-                        }
-                    }
+                    break;
                 }
-                return (*self_ptr).player.modify_player(user_id, |ctx| {
-                    ctx.next_round += 1;
-                });
             }
-
-            // Player moved
-            os::server::log!("Player moved {direction:?}.");
-            (*self_ptr).player.modify_player(user_id, |ctx| {
-                ctx.player.x = new_x;
-                ctx.player.y = new_y;
-                ctx.player.direction = direction;
-                ctx.increment_stats(DungeonStatKind::StepsMoved, 1);
-            })?;
-
-            // Player collected treasure
-            if self.is_treasure(new_x, new_y) {
-                if let Some(treasure) = self.treasures.iter().find(|m| m.x == new_x && m.y == new_y)
-                {
-                    let amount = treasure.value;
-                    match treasure.kind {
-                        TreasureKind::Gold => {
-                            os::server::log!("Got treasure! +${amount}");
-                            (*self_ptr).player.modify_player(user_id, |ctx| {
-                                ctx.player.gold += amount;
-                                ctx.increment_stats(DungeonStatKind::GoldCollected, amount);
-                            })?;
-                        }
-                        TreasureKind::Heal => {
-                            (*self_ptr).player.modify_player(user_id, |ctx| {
-                                let prev_player_health = ctx.player.health;
-                                ctx.player.health =
-                                    (ctx.player.health + amount).min(ctx.player.max_health);
-                                let recovered_health =
-                                    prev_player_health.abs_diff(ctx.player.health);
-                                os::server::log!("Recovered {} HP!", recovered_health);
-                                ctx.increment_stats(
-                                    DungeonStatKind::HealthRecovered,
-                                    recovered_health,
-                                );
-                            })?;
-                        }
-                        TreasureKind::HealthUp => {
-                            (*self_ptr).player.modify_player(user_id, |ctx| {
-                                ctx.player.max_health += 1;
-                                let prev_player_health = ctx.player.health;
-                                ctx.player.health =
-                                    (ctx.player.health + amount).min(ctx.player.max_health);
-                                let recovered_health =
-                                    prev_player_health.abs_diff(ctx.player.health);
-                                ctx.increment_stats(
-                                    DungeonStatKind::HealthRecovered,
-                                    recovered_health,
-                                );
-                                os::server::log!("Health Up! Recovered {} HP!", recovered_health);
-                            })?;
-                        }
-                    }
-                }
-                self.treasures.retain_mut(|t| t.x != new_x || t.y != new_y);
-            }
-
-            // Check if player on an exit
-            if self.is_exit_key(new_x, new_y) {
-                os::server::log!("Found exit key.");
-                self.exit_key = None;
-                let (max_x, max_y) = self.bounds();
-                // Initialize exit position at least 8 tiles away from player
-                let min_distance = (self.width.min(self.height) / 2) as i32;
-                loop {
-                    let x = os::server::random_number::<i32>().abs() % max_x;
-                    let y = os::server::random_number::<i32>().abs() % max_y;
-                    let dx = (x - new_x).abs();
-                    let dy = (y - new_y).abs();
-                    if dx + dy >= min_distance && !self.is_position_occupied(x, y) {
-                        self.exit = Some((x, y));
-                        break;
-                    }
-                }
-                os::server::log!("Hidden stairs appeared!");
-            }
-
-            os::server::log!("Player moved {direction:?}.");
-            return (*self_ptr).player.modify_player(user_id, |ctx| {
-                ctx.next_round += 1;
-            });
         }
+        Ok(())
     }
-    pub fn move_all_monsters(&mut self) -> Result<(), &str> {
-        unsafe {
-            // get mut ptr
-            let self_ptr = self as *mut Self;
-            let player_ctx = self.player.clone();
-            let mut monsters = self.monsters.clone();
-
-            // Move each monster
-            for (i, monster) in monsters.iter_mut().enumerate() {
-                // Get monster position
-                let (mx, my) = (monster.x, monster.y);
-
-                // Skip dead monsters (but leave them in the dungeon)
-                if monster.health == 0 {
-                    continue;
-                }
-
-                // Killed mid-loop during another monster action
-                if self.monsters[i].health == 0 {
-                    continue;
-                }
-
-                // Skip stunned monsters
-                if monster.stun_dur > 0 {
-                    os::server::log!("{:?} is stunned", monster.kind);
-                    monster.stun_dur = monster.stun_dur.saturating_sub(1);
-                    self.monsters[i] = monster.clone();
-                    continue;
-                }
-
-                // Check for adjacent players
-                os::server::log!("{:?} is checking for players to attack...", monster.kind);
-                let mut did_attack_player = false;
-                for (user_id, ctx) in player_ctx.players.iter() {
-                    if ctx.player.health == 0 {
-                        continue;
-                    }
-                    // If the monster is adjacent to the player, it attacks
-                    if (mx - ctx.player.x).abs() + (my - ctx.player.y).abs() == 1 {
-                        os::server::log!("{:?} attacks!", monster.kind);
-                        if self.is_player(mx, my - 1) {
-                            monster.direction = Direction::Up;
-                        }
-                        if self.is_player(mx, my + 1) {
-                            monster.direction = Direction::Down;
-                        }
-                        if self.is_player(mx - 1, my) {
-                            monster.direction = Direction::Left;
-                        }
-                        if self.is_player(mx + 1, my) {
-                            monster.direction = Direction::Right;
-                        }
-                        // Apply damage
-                        (*self_ptr).player.modify_player(&user_id, |ctx| {
-                            let prev_player_health = ctx.player.health;
-                            ctx.player.health = ctx.player.health.saturating_sub(monster.strength);
-                            let damage = prev_player_health.abs_diff(ctx.player.health);
-                            ctx.increment_stats(DungeonStatKind::DamageTaken, damage);
-                            os::server::log!("{:?} did {} damage.", monster.kind, damage);
-
-                            if ctx.player.health == 0 {
-                                os::server::log!("{user_id} died");
-                                ctx.increment_stats(DungeonStatKind::DefeatedBy(monster.kind), 1);
-                            }
-                        })?;
-                        did_attack_player = true;
-                        break;
-                    }
-                }
-                if did_attack_player {
-                    continue;
-                }
-
-                // Find the closest player
-                os::server::log!("{:?} is trying to move", monster.kind);
-                let Some((user_id, ctx)) = player_ctx.players.iter().min_by_key(|(_, ctx)| {
-                    if ctx.player.health == 0 {
-                        return i32::MAX;
-                    }
-                    let dx = ctx.player.x - mx;
-                    let dy = ctx.player.y - my;
-                    dx * dx + dy * dy // Squared distance for comparison
-                }) else {
-                    os::server::log!("Could not find closest player");
-                    continue;
-                };
-                os::server::log!("The closest player to {:?} is {}", monster.kind, user_id);
-
-                // Movement based on monster kind
-                let k: MonsterKind = match monster.kind {
-                    MonsterKind::EvilTurbi => {
-                        let kind = MonsterKind::by_index(os::server::random_number());
-                        os::server::log!("{:?} is feeling like a {:?}", monster.kind, kind);
-                        kind
-                    }
-                    _ => monster.kind,
-                };
-                let (dir, mx, my) = match k {
-                    MonsterKind::BlueBlob | MonsterKind::YellowBlob | MonsterKind::RedBlob => {
-                        // Get distance from player
-                        let dx = ctx.player.x - mx;
-                        let dy = ctx.player.y - my;
-
-                        // When player is 2 or fewer spaces away, chase them
-                        if dx.abs() <= 2 && dy.abs() <= 2 {
-                            let (dir, mx, my) = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                                (false, _, false) => (Direction::Up, mx, my - 1),
-                                (false, _, true) => (Direction::Down, mx, my + 1),
-                                (true, false, _) => (Direction::Left, mx - 1, my),
-                                (true, true, _) => (Direction::Right, mx + 1, my),
-                            };
-                            if self.is_position_occupied(mx, my) {
-                                os::server::log!("{:?} is blocked", monster.kind);
-                                continue;
-                            }
-                            (dir, mx, my)
-                        }
-                        // Otherwise, move in a random direction
-                        else {
-                            let (dir, mx, my) = match os::server::random_number::<usize>() % 4 {
-                                0 => (Direction::Up, mx, my - 1),
-                                1 => (Direction::Down, mx, my + 1),
-                                2 => (Direction::Left, mx - 1, my),
-                                _ => (Direction::Right, mx + 1, my),
-                            };
-                            if self.is_position_occupied(mx, my) {
-                                os::server::log!("{:?} is blocked", monster.kind);
-                                continue;
-                            }
-                            (dir, mx, my)
-                        }
-                    }
-                    MonsterKind::Spider => {
-                        // Move every 3 rounds
-                        if self.round % 3 != 0 {
-                            os::server::log!("{:?} is resting", monster.kind);
-                            continue;
-                        }
-
-                        // Get distance from player
-                        let dx = ctx.player.x - mx;
-                        let dy = ctx.player.y - my;
-
-                        // Attempt to move up to 3 spaces towards player
-                        let steps = 3.min(dx.abs().max(dy.abs()));
-
-                        let mut new_mx = mx;
-                        let mut new_my = my;
-                        let mut dir = monster.direction;
-
-                        for s in (1..=steps).rev() {
-                            let (dir_next, mx_next, my_next) =
-                                match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                                    (false, _, false) => (Direction::Up, mx, my - s),
-                                    (false, _, true) => (Direction::Down, mx, my + s),
-                                    (true, false, _) => (Direction::Left, mx - s, my),
-                                    (true, true, _) => (Direction::Right, mx + s, my),
-                                };
-
-                            if !self.is_position_occupied(mx_next, my_next) {
-                                new_mx = mx_next;
-                                new_my = my_next;
-                                dir = dir_next;
-                                break;
-                            }
-                        }
-
-                        (dir, new_mx, new_my)
-                    }
-                    MonsterKind::Shade => {
-                        // Move every other round
-                        if self.round % 2 != 0 {
-                            os::server::log!("{:?} is resting", monster.kind);
-                            continue;
-                        }
-
-                        // Get distance from player
-                        let dx = ctx.player.x - mx;
-                        let dy = ctx.player.y - my;
-
-                        // Move one space
-                        let steps = 1;
-
-                        // Calculate next position
-                        let (dir, mx, my) = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                            (false, _, false) => (Direction::Up, mx, my - steps),
-                            (false, _, true) => (Direction::Down, mx, my + steps),
-                            (true, false, _) => (Direction::Left, mx - steps, my),
-                            (true, true, _) => (Direction::Right, mx + steps, my),
-                        };
-
-                        // Check for blocks
-                        if self.is_monster(mx, my) {
-                            os::server::log!("{:?} is blocked by another monster", monster.kind);
-                            continue;
-                        }
-
-                        (dir, mx, my)
-                    }
-                    MonsterKind::Ghost | MonsterKind::SpectralGhost => {
-                        let mut dir = Direction::Down;
-                        let mut mx = monster.x;
-                        let mut my = monster.y;
-
-                        // First, check for nearby ghosts to absorb
-                        let mut did_find_nearby_ghost = false;
-                        for dx in -3..=3_i32 {
-                            for dy in -3..=3_i32 {
-                                if dx == 0 && dy == 0 {
-                                    continue;
-                                }
-                                let dir_ = match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                                    (false, _, false) => Direction::Up,
-                                    (false, _, true) => Direction::Down,
-                                    (true, false, _) => Direction::Left,
-                                    (true, true, _) => Direction::Right,
-                                };
-                                let mx_ = monster.x + dx;
-                                let my_ = monster.y + dy;
-                                if self.monsters.iter().any(|m| {
-                                    m.health > 0
-                                        && m.kind == MonsterKind::Ghost
-                                        && m.x == mx_
-                                        && m.y == my_
-                                }) {
-                                    did_find_nearby_ghost = true;
-                                    // Move one space towards nearby ghost
-                                    dir = dir_;
-                                    let dx = dx.checked_div(dx.abs()).unwrap_or(0);
-                                    if dx != 0 {
-                                        mx = monster.x + dx;
-                                    } else {
-                                        let dy = dy.checked_div(dy.abs()).unwrap_or(0);
-                                        my = monster.y + dy;
-                                    }
-                                    break;
-                                }
-                            }
-                        }
-
-                        // Either run from or move towards the player
-                        if !did_find_nearby_ghost {
-                            let (steps, dist, range) = if monster.kind == MonsterKind::Ghost {
-                                // Move every 4 rounds
-                                if self.round % 4 == 0 {
-                                    os::server::log!("{:?} is resting", monster.kind);
-                                    continue;
-                                }
-                                (1, 1, 1)
-                            } else {
-                                // Move every 4 rounds
-                                if self.round % 4 == 0 {
-                                    os::server::log!("{:?} is resting", monster.kind);
-                                    continue;
-                                }
-                                (1, 1, 4)
-                            };
-                            for _ in 0..steps {
-                                let dx = ctx.player.x - mx;
-                                let dy = ctx.player.y - my;
-                                let dx_abs = dx.abs();
-                                let dy_abs = dy.abs();
-                                if dx_abs > range && dy_abs > range {
-                                    continue;
-                                }
-                                let x_or_y = if dx_abs == dy_abs {
-                                    os::server::random_number::<i32>().abs() % 2 == 0
-                                } else {
-                                    dx_abs > dy_abs
-                                };
-                                let (dir_, mx_, my_) = match (x_or_y, dx > 0, dy > 0) {
-                                    (false, _, false) => (Direction::Up, mx, my - dist),
-                                    (false, _, true) => (Direction::Down, mx, my + dist),
-                                    (true, false, _) => (Direction::Left, mx - dist, my),
-                                    (true, true, _) => (Direction::Right, mx + dist, my),
-                                };
-
-                                dir = dir_;
-                                mx = mx_;
-                                my = my_;
-                            }
-                        }
-
-                        // Didn't move
-                        if monster.x == mx && monster.y == my {
-                            os::server::log!("{:?} has nowhere to move", monster.kind);
-                            continue;
-                        }
-
-                        // Check if the monster is blocked by a player
-                        if self.is_player(mx, my) {
-                            os::server::log!("{:?} is blocked by a player", monster.kind);
-                            continue;
-                        }
-
-                        // Check if the monster is blocked by an exit
-                        if self.is_exit(mx, my) {
-                            os::server::log!("{:?} is blocked by an exit", monster.kind);
-                            continue;
-                        }
-
-                        // "Absorb" any non-dead ghost in the same position
-                        if let Some(idx) = self.monsters.iter().position(|m| {
-                            m.health > 0 && m.x == mx && m.y == my && m.kind == MonsterKind::Ghost
-                        }) {
-                            os::server::log!("{:?} is absorbing a ghost!", monster.kind);
-                            // Remove ghost
-                            self.monsters[idx].health = 0;
-                            // Increase stats and upgrade to Spectral Ghost
-                            if monster.kind != MonsterKind::EvilTurbi {
-                                monster.kind = MonsterKind::SpectralGhost;
-                            }
-                            monster.strength *= 2;
-                            monster.max_health *= 2;
-                            monster.health = monster.max_health;
-                        }
-
-                        // Returns false for dead monsters
-                        if self.is_monster(mx, my) {
-                            os::server::log!("{:?} is blocked by a monster", monster.kind);
-                            continue;
-                        }
-
-                        (dir, mx, my)
-                    }
-                    MonsterKind::Zombie => {
-                        let range = 4;
-                        // Moves towards the player each turn
-                        let dx = ctx.player.x - mx;
-                        let dy = ctx.player.y - my;
-                        let dx_abs = dx.abs();
-                        let dy_abs = dy.abs();
-                        if dx_abs + dy_abs > range {
-                            os::server::log!("{:?} is moving randomly", monster.kind);
-                            if self.round % 2 == 0 {
-                                os::server::log!("{:?} is resting", monster.kind);
-                                continue;
-                            }
-                            let (dir, mx, my) = match os::server::random_number::<usize>() % 4 {
-                                0 => (Direction::Up, mx, my - 1),
-                                1 => (Direction::Down, mx, my + 1),
-                                2 => (Direction::Left, mx - 1, my),
-                                _ => (Direction::Right, mx + 1, my),
-                            };
-                            if self.is_position_occupied(mx, my) {
-                                os::server::log!("{:?} is blocked", monster.kind);
-                                continue;
-                            }
-                            (dir, mx, my)
-                        } else {
-                            os::server::log!("{:?} is chasing a player", monster.kind);
-                            let move_y = || {
-                                if dy < 0 {
-                                    (Direction::Up, mx, my - 1)
-                                } else {
-                                    (Direction::Down, mx, my + 1)
-                                }
-                            };
-                            let move_x = || {
-                                if dx < 0 {
-                                    (Direction::Left, mx - 1, my)
-                                } else {
-                                    (Direction::Right, mx + 1, my)
-                                }
-                            };
-                            // Determine whether to try moving L/R or U/D first
-                            let all = if dx.abs() > dy.abs() {
-                                [move_x(), move_y()]
-                            } else {
-                                [move_y(), move_x()]
-                            };
-                            // Find the first unoccupied position
-                            let Some((dir, mx, my)) = all
-                                .into_iter()
-                                .find(|a| !self.is_position_occupied(a.1, a.2))
-                            else {
-                                os::server::log!("{:?} is blocked", monster.kind);
-                                continue;
-                            };
-
-                            (dir, mx, my)
-                        }
-                    }
-                    MonsterKind::IceYeti => {
-                        // Move every other turn
-                        if self.turn % 2 != 0 {
-                            continue;
-                        }
-
-                        let dx = ctx.player.x - mx;
-                        let dy = ctx.player.y - my;
-
-                        // Attempt to move up to 2 spaces towards player
-                        let steps = 2.min(dx.abs().max(dy.abs()));
-
-                        let mut new_mx = mx;
-                        let mut new_my = my;
-                        let mut dir = monster.direction;
-
-                        for s in (1..=steps).rev() {
-                            let (try_dir, try_x, try_y) =
-                                match (dx.abs() > dy.abs(), dx > 0, dy > 0) {
-                                    (false, _, false) => (Direction::Up, mx, my - s),
-                                    (false, _, true) => (Direction::Down, mx, my + s),
-                                    (true, false, _) => (Direction::Left, mx - s, my),
-                                    (true, true, _) => (Direction::Right, mx + s, my),
-                                };
-
-                            if !self.is_position_occupied(try_x, try_y) {
-                                new_mx = try_x;
-                                new_my = try_y;
-                                dir = try_dir;
-                                break;
-                            }
-                        }
-
-                        // If no change occurred, fallback to previous single-step logic
-                        if new_mx == mx && new_my == my {
-                            let x_move = if dx < 0 {
-                                (Direction::Left, mx - 1, my)
-                            } else {
-                                (Direction::Right, mx + 1, my)
-                            };
-                            let y_move = if dy < 0 {
-                                (Direction::Up, mx, my - 1)
-                            } else {
-                                (Direction::Down, mx, my + 1)
-                            };
-
-                            let moves = if dx.abs() > dy.abs() {
-                                [x_move, y_move]
-                            } else {
-                                [y_move, x_move]
-                            };
-
-                            if let Some(next) = moves
-                                .iter()
-                                .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
-                                .copied()
-                            {
-                                next
-                            } else {
-                                continue;
-                            }
-                        } else {
-                            (dir, new_mx, new_my)
-                        }
-                    }
-                    MonsterKind::Snowman => {
-                        // Move every other turn
-                        if self.turn % 2 != 0 {
-                            continue;
-                        }
-
-                        // Moves towards the exit, the stairs, or the player
-                        let mut next = (monster.direction, mx, my);
-                        for (tx, ty) in [
-                            self.exit.unwrap_or((ctx.player.x, ctx.player.y)),
-                            self.exit_key.unwrap_or((ctx.player.x, ctx.player.y)),
-                            (ctx.player.x, ctx.player.y),
-                        ] {
-                            let dx = tx - mx;
-                            let dy = ty - my;
-
-                            let x = if dx < 0 {
-                                (Direction::Left, mx - 1, my)
-                            } else {
-                                (Direction::Right, mx + 1, my)
-                            };
-                            let y = if dy < 0 {
-                                (Direction::Up, mx, my - 1)
-                            } else {
-                                (Direction::Down, mx, my + 1)
-                            };
-
-                            let moves = if dx.abs() > dy.abs() { [x, y] } else { [y, x] };
+    // Adds monsters, treasures, the exit key, and obstacles to the current floor
+    pub fn populate_floor(&mut self, log: fn(&str)) {
+        // Get the dungeon bounds
+        let (max_x, max_y) = self.bounds();
 
-                            let Some((d, x, y)) = moves
-                                .iter()
-                                .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
-                                .copied()
-                            else {
-                                continue;
-                            };
-                            next = (d, x, y);
-                        }
-                        next
-                    }
-                    _ => {
-                        // Moves towards the player each turn
-                        let dx = ctx.player.x - mx;
-                        let dy = ctx.player.y - my;
+        // Get the magic ratio
+        let magic_ratio = ((max_x * max_y) / 32) as usize;
+
+        // After first floor, add monsters and treasures
+        if self.floor > 0 {
+            log("Randomizing monsters...");
+            let num_monsters = 2 + magic_ratio;
+            let is_winter = true;
+            let monster_weights =
+                MonsterDef::spawn_weights(SpawnTable::Coop, self.theme, self.floor, is_winter);
+            self.spawn_monsters(num_monsters, &monster_weights);
+
+            log("Randomizing treasures...");
+            let num_treasures = magic_ratio + (self.floor as f32 * 0.75) as usize;
+            self.spawn_treasures(num_treasures);
+        }
 
-                        let x = if dx < 0 {
-                            (Direction::Left, mx - 1, my)
-                        } else {
-                            (Direction::Right, mx + 1, my)
-                        };
-                        let y = if dy < 0 {
-                            (Direction::Up, mx, my - 1)
-                        } else {
-                            (Direction::Down, mx, my + 1)
-                        };
+        // Initialize exit_key position away from player 0
+        log("Initializing exit key position...");
+        self.spawn_exit_key();
 
-                        let moves = if dx.abs() > dy.abs() { [x, y] } else { [y, x] };
+        log("Randomizing obstacles...");
+        self.spawn_obstacles();
 
-                        let Some(next) = moves
-                            .iter()
-                            .find(|(_, x, y)| !self.is_position_occupied(*x, *y))
-                            .copied()
-                        else {
-                            continue;
-                        };
-                        next
-                    }
-                };
+        log("Stamping prefab rooms...");
+        self.spawn_prefab_room();
 
-                if self.is_out_of_bounds(mx, my) {
-                    os::server::log!("{:?} cannot move out-of-bounds", monster.kind);
-                    continue;
-                }
+        log("Placing hazards...");
+        self.spawn_hazards();
 
-                monster.x = mx;
-                monster.y = my;
-                monster.direction = dir;
-                self.monsters[i] = monster.clone();
-            }
-            Ok(())
+        self.explore();
+    }
+    // What the party can see between them
+    pub fn field_of_view(&self) -> FieldOfView {
+        let is_opaque = |x, y| {
+            self.obstacle_at(x, y)
+                .is_some_and(|obs| obs.kind.is_solid())
+        };
+        let mut fov = FieldOfView::empty(self.width, self.height);
+        for ctx in self.player.players.values() {
+            let origin = (ctx.player.x, ctx.player.y);
+            fov.merge(&FieldOfView::new(
+                self.width,
+                self.height,
+                origin,
+                Dungeon::SIGHT_RADIUS,
+                is_opaque,
+            ));
         }
+        fov
+    }
+    // Remembers every tile in sight
+    pub fn explore(&mut self) {
+        let visible: Vec<(i32, i32)> = self.field_of_view().tiles().collect();
+        self.explored.extend(visible);
     }
     pub fn did_all_players_move(&self) -> bool {
         self.player
@@ -833,46 +281,104 @@ impl MultiplayerDungeon {
             .values()
             .all(|ctx| ctx.player.health == 0)
     }
-    pub fn is_player(&self, x: i32, y: i32) -> bool {
-        self.player
-            .players
-            .values()
-            .any(|ctx| ctx.player.x == x && ctx.player.y == y)
+}
+impl DungeonRules for MultiplayerDungeon {
+    fn clock(&self) -> u32 {
+        self.round
     }
-    pub fn bounds(&self) -> (i32, i32) {
-        let max_x = self.width as i32 - 1;
-        let max_y = self.height as i32 - 1;
-        (max_x, max_y)
+    fn floor(&self) -> u32 {
+        self.floor
     }
-    pub fn is_out_of_bounds(&self, x: i32, y: i32) -> bool {
-        let (min_x, min_y) = (0, 0);
-        let (max_x, max_y) = self.bounds();
-        x < min_x || y < min_y || x > max_x || y > max_y
+    fn theme(&self) -> DungeonThemeKind {
+        self.theme
+    }
+    fn width(&self) -> u32 {
+        self.width
+    }
+    fn height(&self) -> u32 {
+        self.height
+    }
+    fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+    fn obstacles_mut(&mut self) -> &mut Vec<Obstacle> {
+        &mut self.obstacles
+    }
+    fn monsters(&self) -> &[Monster] {
+        &self.monsters
     }
-    pub fn is_obstacle(&self, x: i32, y: i32) -> bool {
-        self.obstacles.iter().any(|obs| obs.x == x && obs.y == y)
+    fn monsters_mut(&mut self) -> &mut Vec<Monster> {
+        &mut self.monsters
     }
-    pub fn is_monster(&self, x: i32, y: i32) -> bool {
-        self.monsters
-            .iter()
-            .any(|mon| mon.x == x && mon.y == y && mon.health > 0)
+    fn treasures(&self) -> &[Treasure] {
+        &self.treasures
     }
-    pub fn is_treasure(&self, x: i32, y: i32) -> bool {
-        self.treasures.iter().any(|t| t.x == x && t.y == y)
+    fn treasures_mut(&mut self) -> &mut Vec<Treasure> {
+        &mut self.treasures
     }
-    pub fn is_exit_key(&self, x: i32, y: i32) -> bool {
-        self.exit_key.map_or(false, |a| a.0 == x && a.1 == y)
+    fn exit_key(&self) -> Option<(i32, i32)> {
+        self.exit_key
     }
-    pub fn is_exit(&self, x: i32, y: i32) -> bool {
-        self.exit.map_or(false, |a| a.0 == x && a.1 == y)
+    fn set_exit_key(&mut self, exit_key: Option<(i32, i32)>) {
+        self.exit_key = exit_key;
     }
-    pub fn is_position_blocked(&self, x: i32, y: i32) -> bool {
-        self.is_obstacle(x, y) || self.is_monster(x, y) || self.is_player(x, y)
+    fn exit(&self) -> Option<(i32, i32)> {
+        self.exit
     }
-    pub fn is_position_occupied(&self, x: i32, y: i32) -> bool {
-        self.is_position_blocked(x, y)
-            || self.is_treasure(x, y)
-            || self.is_exit_key(x, y)
-            || self.is_exit(x, y)
+    fn set_exit(&mut self, exit: Option<(i32, i32)>) {
+        self.exit = exit;
+    }
+    fn player_count(&self) -> usize {
+        self.player.players.len()
+    }
+    fn player(&self, i: usize) -> &Player {
+        let ctx = self.player.players.values().nth(i);
+        &ctx.expect("Player index out of range").player
+    }
+    fn player_mut(&mut self, i: usize) -> &mut Player {
+        let ctx = self.player.players.values_mut().nth(i);
+        &mut ctx.expect("Player index out of range").player
+    }
+    fn increment_player_stats(&mut self, i: usize, kind: DungeonStatKind, amount: u32) {
+        if let Some(ctx) = self.player.players.values_mut().nth(i) {
+            ctx.increment_stats(kind, amount);
+        }
+    }
+    // Co-op has no use_item command yet
+    fn spawns_items(&self) -> bool {
+        false
+    }
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+    fn danger_tiles(&self) -> &[DangerTile] {
+        &self.danger_tiles
+    }
+    fn danger_tiles_mut(&mut self) -> &mut Vec<DangerTile> {
+        &mut self.danger_tiles
+    }
+    fn events_mut(&mut self) -> &mut Vec<TurnEvent> {
+        &mut self.events
+    }
+}
+
+impl Schema for MultiplayerDungeon {
+    // v2 added seeds, inventories, status effects and everything else a crawl tracks since.
+    // v1 crawls get a fresh seed.
+    const VERSION: u16 = 2;
+    fn migrate(version: u16, data: &[u8]) -> std::io::Result<Self> {
+        let migrated = match version {
+            1 => MultiplayerDungeonV1::try_from_slice(data).map(Self::from),
+            2 => return Self::try_from_slice(data),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported multiplayer dungeon version {version}"),
+            )),
+        };
+        // Older crawls start out knowing only what's in sight
+        migrated.map(|mut dungeon| {
+            dungeon.explore();
+            dungeon
+        })
     }
 }
@@ -0,0 +1,82 @@
+use super::*;
+
+// Multiplayer dungeon layout shipped before files were versioned (schema v1, files without an envelope)
+#[derive(BorshDeserialize)]
+pub(crate) struct MultiplayerDungeonV1 {
+    owner: String,
+    crawl_id: u32,
+    theme: DungeonThemeKind,
+    floor: u32,
+    round: u32,
+    turn: u32,
+    width: u32,
+    height: u32,
+    monsters: Vec<MonsterV1>,
+    treasures: Vec<Treasure>,
+    obstacles: Vec<Obstacle>,
+    exit_key: Option<(i32, i32)>,
+    exit: Option<(i32, i32)>,
+    player: PlayerStateV1,
+}
+
+#[derive(BorshDeserialize)]
+struct PlayerStateV1 {
+    players: BTreeMap<String, PlayerContextV1>,
+}
+
+#[derive(BorshDeserialize)]
+struct PlayerContextV1 {
+    player: PlayerV1,
+    stats: DungeonStats,
+    total_stats: DungeonStats,
+    unlocked: PlayerAchievements,
+    all_unlocked: PlayerAchievements,
+    next_round: u32,
+}
+
+impl From<PlayerStateV1> for PlayerState {
+    fn from(v1: PlayerStateV1) -> Self {
+        let players = v1.players.into_iter().map(|(user_id, ctx)| {
+            let ctx = PlayerContext {
+                player: ctx.player.into(),
+                stats: ctx.stats,
+                total_stats: ctx.total_stats,
+                unlocked: ctx.unlocked,
+                all_unlocked: ctx.all_unlocked,
+                next_round: ctx.next_round,
+            };
+            (user_id, ctx)
+        });
+        Self {
+            players: players.collect(),
+        }
+    }
+}
+
+impl From<MultiplayerDungeonV1> for MultiplayerDungeon {
+    fn from(v1: MultiplayerDungeonV1) -> Self {
+        // The crawl never had a seed, so it gets a fresh one for the rounds still to come
+        let seed = Rng::new(v1.crawl_id).next_u32();
+        Self {
+            owner: v1.owner,
+            crawl_id: v1.crawl_id,
+            theme: v1.theme,
+            floor: v1.floor,
+            round: v1.round,
+            turn: v1.turn,
+            width: v1.width,
+            height: v1.height,
+            monsters: v1.monsters.into_iter().map(Monster::from).collect(),
+            treasures: v1.treasures,
+            obstacles: v1.obstacles,
+            exit_key: v1.exit_key,
+            exit: v1.exit,
+            player: v1.player.into(),
+            seed,
+            rng: Rng::new(seed),
+            danger_tiles: vec![],
+            explored: BTreeSet::new(),
+            events: vec![],
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use super::*;
+
+// What one player knows about a multiplayer crawl. Each player's client watches their own view.
+// The seed and RNG stay on the server along with anything the party hasn't seen,
+// and other players' lifetime stats and achievements are left blank.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct MultiplayerDungeonView {
+    pub owner: String,
+    pub crawl_id: u32,
+    pub theme: DungeonThemeKind,
+    pub floor: u32,
+    pub round: u32,
+    pub turn: u32,
+    pub width: u32,
+    pub height: u32,
+    pub monsters: Vec<Monster>,
+    pub treasures: Vec<Treasure>,
+    pub obstacles: Vec<Obstacle>,
+    pub exit_key: Option<(i32, i32)>,
+    pub exit: Option<(i32, i32)>,
+    pub player: PlayerState,
+    pub danger_tiles: Vec<DangerTile>,
+    pub explored: BTreeSet<(i32, i32)>,
+    pub events: Vec<TurnEvent>,
+}
+impl MultiplayerDungeonView {
+    // The party shares what they see, so the floor is redacted like a `DungeonView`
+    // using every player's sight and every tile any of them has explored.
+    pub fn new(dungeon: &MultiplayerDungeon, user_id: &str) -> Self {
+        let mut player = dungeon.player.clone();
+        for (id, ctx) in player.players.iter_mut() {
+            if id != user_id {
+                ctx.total_stats = DungeonStats::new();
+                ctx.all_unlocked = PlayerAchievements::empty();
+            }
+        }
+        let fov = dungeon.field_of_view();
+        let is_explored = |x: i32, y: i32| dungeon.explored.contains(&(x, y));
+        let is_visible =
+            |monster: &Monster| monster.tiles().iter().any(|&(x, y)| fov.is_visible(x, y));
+        let mut view_index = vec![None; dungeon.monsters.len()];
+        for (i, idx) in (0..dungeon.monsters.len())
+            .filter(|&idx| is_visible(&dungeon.monsters[idx]))
+            .enumerate()
+        {
+            view_index[idx] = Some(i);
+        }
+        Self {
+            owner: dungeon.owner.clone(),
+            crawl_id: dungeon.crawl_id,
+            theme: dungeon.theme,
+            floor: dungeon.floor,
+            round: dungeon.round,
+            turn: dungeon.turn,
+            width: dungeon.width,
+            height: dungeon.height,
+            monsters: dungeon
+                .monsters
+                .iter()
+                .filter(|monster| is_visible(monster))
+                .cloned()
+                .collect(),
+            treasures: dungeon
+                .treasures
+                .iter()
+                .filter(|treasure| is_explored(treasure.x, treasure.y))
+                .cloned()
+                .collect(),
+            obstacles: dungeon
+                .obstacles
+                .iter()
+                .filter(|obstacle| is_explored(obstacle.x, obstacle.y))
+                .cloned()
+                .collect(),
+            exit_key: dungeon.exit_key.filter(|&(x, y)| is_explored(x, y)),
+            exit: dungeon.exit,
+            player,
+            danger_tiles: dungeon.danger_tiles.clone(),
+            explored: dungeon.explored.clone(),
+            events: dungeon
+                .events
+                .iter()
+                .filter(|event| {
+                    matches!(event, TurnEvent::ExitAppeared { .. })
+                        || event.position().is_none_or(|(x, y)| fov.is_visible(x, y))
+                })
+                .filter_map(|event| {
+                    let mut event = event.clone();
+                    if let Some(monster) = event.monster_mut() {
+                        *monster = view_index[*monster]?;
+                    }
+                    Some(event)
+                })
+                .collect(),
+        }
+    }
+}
+
+// Screens draw with the same helpers as the server, so the fields a view leaves out are filled with blanks
+impl From<MultiplayerDungeonView> for MultiplayerDungeon {
+    fn from(view: MultiplayerDungeonView) -> Self {
+        Self {
+            owner: view.owner,
+            crawl_id: view.crawl_id,
+            theme: view.theme,
+            floor: view.floor,
+            round: view.round,
+            turn: view.turn,
+            width: view.width,
+            height: view.height,
+            monsters: view.monsters,
+            treasures: view.treasures,
+            obstacles: view.obstacles,
+            exit_key: view.exit_key,
+            exit: view.exit,
+            player: view.player,
+            seed: 0,
+            rng: Rng::new(0),
+            danger_tiles: view.danger_tiles,
+            explored: view.explored,
+            events: view.events,
+        }
+    }
+}
+
+impl Schema for MultiplayerDungeonView {
+    // Views are rewritten on every move, so when the layout changes old ones aren't migrated
+    const VERSION: u16 = 1;
+}
@@ -2,8 +2,31 @@ use super::*;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq,
+)]
 pub enum ObstacleKind {
     WallA,
     WallB,
+    // Hurts players who step on it
+    SpikeTrap,
+    // Opens every door on the floor when stepped on
+    PressurePlate,
+    Door,
+    // Players keep sliding across ice until something is in the way
+    IceFloor,
+    // Pushed one tile when a player walks into it
+    Crate,
+    // Only lets players through while moving in its direction
+    OneWayDoor(Direction),
+}
+impl ObstacleKind {
+    pub const SPIKE_DAMAGE: u32 = 2;
+    // Whether the obstacle blocks movement (walkable tiles can still have effects)
+    pub fn is_solid(&self) -> bool {
+        match self {
+            Self::WallA | Self::WallB | Self::Door | Self::Crate | Self::OneWayDoor(_) => true,
+            Self::SpikeTrap | Self::PressurePlate | Self::IceFloor => false,
+        }
+    }
 }
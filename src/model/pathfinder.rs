@@ -0,0 +1,108 @@
+use super::*;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+// Shortest walking paths over a floor's walls.
+// Built once per monster turn so each monster only pays for its own search.
+#[derive(Debug, Clone)]
+pub struct Pathfinder {
+    width: i32,
+    height: i32,
+    blocked: Vec<bool>,
+}
+impl Pathfinder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width as i32,
+            height: height as i32,
+            blocked: vec![false; (width * height) as usize],
+        }
+    }
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+    fn position(&self, i: usize) -> (i32, i32) {
+        (i as i32 % self.width, i as i32 / self.width)
+    }
+    pub fn block(&mut self, x: i32, y: i32) {
+        if let Some(i) = self.index(x, y) {
+            self.blocked[i] = true;
+        }
+    }
+    pub fn is_walkable(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| !self.blocked[i])
+    }
+    // Walkable tiles next to tile `i`. The goal is always walkable so blocked targets can be reached.
+    fn neighbors(&self, i: usize, goal: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = self.position(i);
+        Direction::ALL.iter().filter_map(move |dir| {
+            let (nx, ny) = dir.step(x, y);
+            let j = self.index(nx, ny)?;
+            (!self.blocked[j] || Some(j) == goal).then_some(j)
+        })
+    }
+    // A* search from `from` to `to`. Returns every tile after `from`, ending with `to`.
+    pub fn find_path(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        let start = self.index(from.0, from.1)?;
+        let goal = self.index(to.0, to.1)?;
+        let heuristic = |i: usize| {
+            let (x, y) = self.position(i);
+            (x - to.0).unsigned_abs() + (y - to.1).unsigned_abs()
+        };
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut prev = vec![usize::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[start] = 0;
+        // Ties go to the tile closest to the goal, then the lowest index, so searches are deterministic
+        open.push(Reverse((heuristic(start), heuristic(start), start)));
+        while let Some(Reverse((_, _, i))) = open.pop() {
+            if i == goal {
+                let mut path = vec![];
+                let mut i = goal;
+                while i != start {
+                    path.push(self.position(i));
+                    i = prev[i];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let next_cost = cost[i] + 1;
+            for j in self.neighbors(i, Some(goal)) {
+                if next_cost < cost[j] {
+                    cost[j] = next_cost;
+                    prev[j] = i;
+                    let h = heuristic(j);
+                    open.push(Reverse((next_cost + h, h, j)));
+                }
+            }
+        }
+        None
+    }
+    // Walking distance from `from` to every tile (None if unreachable)
+    pub fn distances(&self, from: (i32, i32)) -> Vec<Option<u32>> {
+        let mut dist = vec![None; self.blocked.len()];
+        let Some(start) = self.index(from.0, from.1) else {
+            return dist;
+        };
+        dist[start] = Some(0);
+        let mut queue = VecDeque::from([start]);
+        while let Some(i) = queue.pop_front() {
+            let d = dist[i].unwrap_or(0) + 1;
+            for j in self.neighbors(i, None) {
+                if dist[j].is_none() {
+                    dist[j] = Some(d);
+                    queue.push_back(j);
+                }
+            }
+        }
+        dist
+    }
+    // Looks up a tile in a map returned by `distances`
+    pub fn distance(&self, distances: &[Option<u32>], x: i32, y: i32) -> Option<u32> {
+        self.index(x, y).and_then(|i| distances[i])
+    }
+}
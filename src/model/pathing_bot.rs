@@ -0,0 +1,68 @@
+use super::*;
+
+// Walks the shortest path to the exit key and then the stairs, fighting whatever gets in the way.
+// On boss floors it goes after the boss. Drinks potions when low and steps randomly when it gets stuck.
+#[derive(Debug, Clone)]
+pub struct PathingBot {
+    rng: Rng,
+    // (floor, turn) when the last action was picked
+    last_turn: Option<(u32, u32)>,
+}
+impl PathingBot {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            last_turn: None,
+        }
+    }
+    // Where the bot is headed: the stairs, the exit key, or else the closest monster
+    fn goal(dungeon: &Dungeon) -> Option<(i32, i32)> {
+        let player = &dungeon.player;
+        dungeon.exit.or(dungeon.exit_key).or_else(|| {
+            dungeon
+                .monsters
+                .iter()
+                .filter(|monster| monster.health > 0)
+                .map(|monster| (monster.x, monster.y))
+                .min_by_key(|(x, y)| (x - player.x).abs() + (y - player.y).abs())
+        })
+    }
+    fn step_towards_goal(dungeon: &Dungeon) -> Option<Direction> {
+        let from = (dungeon.player.x, dungeon.player.y);
+        let mut paths = Pathfinder::new(dungeon.width, dungeon.height);
+        for obstacle in &dungeon.obstacles {
+            if obstacle.kind.is_solid() {
+                paths.block(obstacle.x, obstacle.y);
+            }
+        }
+        let path = paths.find_path(from, Self::goal(dungeon)?)?;
+        Direction::between(from, *path.first()?)
+    }
+}
+impl CrawlBot for PathingBot {
+    fn name(&self) -> &'static str {
+        "pathing"
+    }
+    fn next_action(&mut self, dungeon: &Dungeon) -> CrawlAction {
+        let player = &dungeon.player;
+        // Every action that goes through takes a turn, so the last one failed if the clock didn't move
+        let turn = (dungeon.floor, dungeon.turn);
+        let is_stuck = self.last_turn == Some(turn);
+        self.last_turn = Some(turn);
+
+        if dungeon.is_exit(player.x, player.y) {
+            return CrawlAction::NextFloor;
+        }
+        let potion = player
+            .inventory
+            .iter()
+            .position(|item| *item == ItemKind::Potion);
+        if let Some(slot) = potion.filter(|_| player.health * 3 <= player.max_health) {
+            return CrawlAction::UseItem(slot);
+        }
+        match Self::step_towards_goal(dungeon) {
+            Some(direction) if !is_stuck => CrawlAction::Move(direction),
+            _ => CrawlAction::Move(Direction::ALL[self.rng.index(4)]),
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use super::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub struct Player {
     pub x: i32,
     pub y: i32,
@@ -10,4 +10,10 @@ pub struct Player {
     pub strength: u32,
     pub gold: u32,
     pub direction: Direction,
+    pub inventory: Vec<ItemKind>,
+    pub effects: StatusEffects,
+}
+impl Player {
+    // Number of hotbar slots
+    pub const MAX_ITEMS: usize = 4;
 }
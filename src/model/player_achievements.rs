@@ -2,7 +2,7 @@ use super::*;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub struct PlayerAchievements {
     completed: BTreeSet<u32>, // stores index of completed achievements
 }
@@ -77,3 +77,7 @@ impl PlayerAchievements {
         Self { completed }
     }
 }
+
+impl Schema for PlayerAchievements {
+    const VERSION: u16 = 1;
+}
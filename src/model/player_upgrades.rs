@@ -0,0 +1,64 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// Permanent upgrades bought with essence earned across every classic crawl
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Default,
+)]
+pub struct PlayerUpgrades {
+    pub levels: BTreeMap<String, u32>,
+    pub spent: u32,
+}
+impl PlayerUpgrades {
+    pub fn new() -> Self {
+        Self {
+            levels: BTreeMap::new(),
+            spent: 0,
+        }
+    }
+    pub fn level(&self, kind: UpgradeKind) -> u32 {
+        let key = format!("{kind:?}");
+        *self.levels.get(&key).unwrap_or(&0)
+    }
+    // Every floor cleared and monster defeated is worth 1 essence
+    pub fn essence_earned(total_stats: &DungeonStats) -> u32 {
+        total_stats.get(DungeonStatKind::FloorsCleared) + total_stats.total_monsters_defeated()
+    }
+    pub fn essence(&self, total_stats: &DungeonStats) -> u32 {
+        Self::essence_earned(total_stats).saturating_sub(self.spent)
+    }
+    // Raises an upgrade by one level and returns its cost
+    pub fn unlock(
+        &mut self,
+        kind: UpgradeKind,
+        total_stats: &DungeonStats,
+    ) -> Result<u32, &'static str> {
+        let level = self.level(kind);
+        if level >= kind.max_level() {
+            return Err("Upgrade is already maxed out");
+        }
+        let cost = kind.cost(level);
+        if self.essence(total_stats) < cost {
+            return Err("Not enough essence");
+        }
+        self.levels.insert(format!("{kind:?}"), level + 1);
+        self.spent += cost;
+        Ok(cost)
+    }
+    // Applies the upgrades to a freshly created crawl
+    pub fn apply(&self, dungeon: &mut Dungeon) {
+        let player = &mut dungeon.player;
+        let bonus_health = self.level(UpgradeKind::StartingHealth) * UpgradeKind::HEALTH_PER_LEVEL;
+        player.max_health += bonus_health;
+        player.health += bonus_health;
+        player.gold += self.level(UpgradeKind::StartingGold) * UpgradeKind::GOLD_PER_LEVEL;
+        for _ in 0..self.level(UpgradeKind::FloorReroll) {
+            player.inventory.push(ItemKind::RerollScroll);
+        }
+    }
+}
+
+impl Schema for PlayerUpgrades {
+    const VERSION: u16 = 1;
+}
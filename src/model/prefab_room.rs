@@ -0,0 +1,243 @@
+use super::*;
+
+use std::fmt::Debug;
+use std::sync::OnceLock;
+
+// A hand-authored room stamped onto floors. See prefab_rooms.txt for the file format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabRoom {
+    pub name: String,
+    // First floor the room can show up on
+    pub min_floor: u32,
+    // How often the room is picked compared to the others
+    pub weight: u32,
+    pub width: usize,
+    pub height: usize,
+    // Row by row, `width` tiles to a row
+    pub tiles: Vec<PrefabTile>,
+}
+impl PrefabRoom {
+    // Glyphs every room can use without adding them to its legend
+    pub const DEFAULT_LEGEND: &'static [(char, PrefabTile)] = &[
+        ('.', PrefabTile::Floor),
+        ('#', PrefabTile::Obstacle(ObstacleKind::WallA)),
+        ('%', PrefabTile::Obstacle(ObstacleKind::WallB)),
+        ('^', PrefabTile::Obstacle(ObstacleKind::SpikeTrap)),
+        ('_', PrefabTile::Obstacle(ObstacleKind::PressurePlate)),
+        ('+', PrefabTile::Obstacle(ObstacleKind::Door)),
+        ('~', PrefabTile::Obstacle(ObstacleKind::IceFloor)),
+        ('=', PrefabTile::Obstacle(ObstacleKind::Crate)),
+        (
+            '<',
+            PrefabTile::Obstacle(ObstacleKind::OneWayDoor(Direction::Left)),
+        ),
+        (
+            '>',
+            PrefabTile::Obstacle(ObstacleKind::OneWayDoor(Direction::Right)),
+        ),
+    ];
+    // The rooms that ship with the game, parsed on first use
+    pub fn all() -> &'static [PrefabRoom] {
+        static ROOMS: OnceLock<Vec<PrefabRoom>> = OnceLock::new();
+        ROOMS.get_or_init(|| {
+            Self::parse_all(include_str!("prefab_rooms.txt"))
+                .unwrap_or_else(|err| panic!("prefab_rooms.txt {err}"))
+        })
+    }
+    pub fn tile(&self, x: usize, y: usize) -> &PrefabTile {
+        &self.tiles[y * self.width + x]
+    }
+    // Parses every room in a prefab room file
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, PrefabRoomError> {
+        let mut rooms = vec![];
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.starts_with("//"));
+        while let Some((n, line)) = lines.next() {
+            if line.is_empty() {
+                continue;
+            }
+            let Some(name) = line.strip_prefix("room ") else {
+                let message = format!("expected `room <name>`, found `{line}`");
+                return Err(PrefabRoomError::new(n, message));
+            };
+            rooms.push(Self::parse_room(name.trim(), n, &mut lines)?);
+        }
+        Ok(rooms)
+    }
+    // Parses the settings, legend and map following a `room <name>` line
+    fn parse_room<'a>(
+        name: &str,
+        start: usize,
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<Self, PrefabRoomError> {
+        let mut room = Self {
+            name: name.to_string(),
+            min_floor: 1,
+            weight: 1,
+            width: 0,
+            height: 0,
+            tiles: vec![],
+        };
+        let mut legend: BTreeMap<char, PrefabTile> = Self::DEFAULT_LEGEND.iter().cloned().collect();
+
+        // Settings and legend entries until `map`
+        loop {
+            let Some((n, line)) = lines.next() else {
+                let message = format!("room `{name}` has no `map`");
+                return Err(PrefabRoomError::new(start, message));
+            };
+            if line.is_empty() {
+                continue;
+            }
+            if line == "map" {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("min_floor ") {
+                room.min_floor = parse_number(n, value)?;
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("weight ") {
+                room.weight = parse_number(n, value)?;
+                continue;
+            }
+            let Some((glyph, tile)) = line.split_once('=') else {
+                let message = format!(
+                    "expected `min_floor <n>`, `weight <n>`, `<glyph> = <tile>` or `map`, found `{line}`"
+                );
+                return Err(PrefabRoomError::new(n, message));
+            };
+            let glyph = glyph.trim();
+            let mut chars = glyph.chars();
+            let (Some(glyph), None) = (chars.next(), chars.next()) else {
+                let message = format!("legend glyphs are a single character, found `{glyph}`");
+                return Err(PrefabRoomError::new(n, message));
+            };
+            legend.insert(glyph, parse_tile(n, tile.trim())?);
+        }
+
+        // Rows of glyphs until `end`
+        loop {
+            let Some((n, row)) = lines.next() else {
+                let message = format!("room `{name}` is missing `end`");
+                return Err(PrefabRoomError::new(start, message));
+            };
+            if row == "end" {
+                break;
+            }
+            if row.is_empty() {
+                let message = format!("blank line in the map of `{name}`");
+                return Err(PrefabRoomError::new(n, message));
+            }
+            let width = row.chars().count();
+            if room.height == 0 {
+                room.width = width;
+            } else if width != room.width {
+                let message = format!(
+                    "map row is {width} tiles wide but the first row is {}",
+                    room.width
+                );
+                return Err(PrefabRoomError::new(n, message));
+            }
+            for glyph in row.chars() {
+                let Some(tile) = legend.get(&glyph) else {
+                    let message = format!(
+                        "unknown glyph `{glyph}`, add it to the legend (e.g. `{glyph} = monster GreenGoblin`)"
+                    );
+                    return Err(PrefabRoomError::new(n, message));
+                };
+                room.tiles.push(tile.clone());
+            }
+            room.height += 1;
+        }
+        if room.height == 0 {
+            let message = format!("room `{name}` has an empty map");
+            return Err(PrefabRoomError::new(start, message));
+        }
+        Ok(room)
+    }
+}
+
+fn parse_number(n: usize, value: &str) -> Result<u32, PrefabRoomError> {
+    let value = value.trim();
+    value
+        .parse()
+        .map_err(|_| PrefabRoomError::new(n, format!("expected a number, found `{value}`")))
+}
+
+// Looks up a variant by its name, listing the valid names when there's no match
+fn by_name<T: Debug + Clone>(
+    n: usize,
+    what: &str,
+    options: &[T],
+    name: &str,
+) -> Result<T, PrefabRoomError> {
+    let names: Vec<String> = options.iter().map(|option| format!("{option:?}")).collect();
+    match names.iter().position(|option| option == name) {
+        Some(i) => Ok(options[i].clone()),
+        None => {
+            let message = format!(
+                "unknown {what} `{name}`, expected one of {}",
+                names.join(", ")
+            );
+            Err(PrefabRoomError::new(n, message))
+        }
+    }
+}
+
+// The right-hand side of a legend entry
+fn parse_tile(n: usize, text: &str) -> Result<PrefabTile, PrefabRoomError> {
+    const OBSTACLES: &[ObstacleKind] = &[
+        ObstacleKind::WallA,
+        ObstacleKind::WallB,
+        ObstacleKind::SpikeTrap,
+        ObstacleKind::PressurePlate,
+        ObstacleKind::Door,
+        ObstacleKind::IceFloor,
+        ObstacleKind::Crate,
+    ];
+    const TREASURES: &[TreasureKind] = &[
+        TreasureKind::Gold,
+        TreasureKind::Heal,
+        TreasureKind::HealthUp,
+        TreasureKind::Chest,
+    ];
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        ["floor"] => Ok(PrefabTile::Floor),
+        ["obstacle", "OneWayDoor", direction] => {
+            let direction = by_name(n, "direction", Direction::ALL, direction)?;
+            Ok(PrefabTile::Obstacle(ObstacleKind::OneWayDoor(direction)))
+        }
+        ["obstacle", kind] => Ok(PrefabTile::Obstacle(by_name(
+            n, "obstacle", OBSTACLES, kind,
+        )?)),
+        ["monster", kind] => {
+            let kind = by_name(n, "monster", MonsterKind::ALL, kind)?;
+            if kind.is_boss() {
+                let message = format!("bosses like `{kind:?}` can't be placed in rooms");
+                return Err(PrefabRoomError::new(n, message));
+            }
+            Ok(PrefabTile::Monster(kind))
+        }
+        ["treasure", "Item", item, value] => {
+            let item = by_name(n, "item", ItemKind::ALL, item)?;
+            Ok(PrefabTile::Treasure(
+                TreasureKind::Item(item),
+                parse_number(n, value)?,
+            ))
+        }
+        ["treasure", kind, value] => {
+            let kind = by_name(n, "treasure", TREASURES, kind)?;
+            Ok(PrefabTile::Treasure(kind, parse_number(n, value)?))
+        }
+        _ => {
+            let message = format!(
+                "expected `floor`, `obstacle <kind>`, `monster <kind>` or `treasure <kind> <value>`, found `{text}`"
+            );
+            Err(PrefabRoomError::new(n, message))
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::fmt;
+
+// A problem in a prefab room file and the line it was found on
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabRoomError {
+    pub line: usize,
+    pub message: String,
+}
+impl PrefabRoomError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+impl fmt::Display for PrefabRoomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
@@ -0,0 +1,11 @@
+use super::*;
+
+// What a prefab room puts on one of its tiles
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefabTile {
+    // Cleared floor
+    Floor,
+    Obstacle(ObstacleKind),
+    Monster(MonsterKind),
+    Treasure(TreasureKind, u32),
+}
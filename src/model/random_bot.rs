@@ -0,0 +1,29 @@
+use super::*;
+
+// Wanders at random, taking the stairs when it stumbles onto them and using items now and then
+#[derive(Debug, Clone)]
+pub struct RandomBot {
+    rng: Rng,
+}
+impl RandomBot {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+impl CrawlBot for RandomBot {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+    fn next_action(&mut self, dungeon: &Dungeon) -> CrawlAction {
+        let player = &dungeon.player;
+        if dungeon.is_exit(player.x, player.y) {
+            CrawlAction::NextFloor
+        } else if !player.inventory.is_empty() && self.rng.one_in(8) {
+            CrawlAction::UseItem(0)
+        } else {
+            CrawlAction::Move(Direction::ALL[self.rng.index(4)])
+        }
+    }
+}
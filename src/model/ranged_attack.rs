@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+// Ranged monsters attack players in a straight line up to `range` tiles away.
+// They take aim on turns where `clock % every == 1` (so `every` is at least 2), and the attack lands a turn later.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RangedAttack {
+    pub range: i32,
+    pub every: u32,
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// Deterministic random number generator (SplitMix64).
+// The state is saved with the dungeon so the same seed and inputs replay identically.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Rng {
+    pub state: u64,
+}
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Self { state: seed as u64 }
+    }
+    // Generator for a dungeon floor, so each floor only depends on the seed and floor number
+    pub fn for_floor(seed: u32, floor: u32) -> Self {
+        Self {
+            state: ((floor as u64) << 32) | seed as u64,
+        }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    // Random number in 0..n (returns 0 when n is 0)
+    pub fn range(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        self.next_u32() % n
+    }
+    // Random index into a collection of length `len`
+    pub fn index(&mut self, len: usize) -> usize {
+        self.range(len as u32) as usize
+    }
+    // True with a 1 in `n` chance
+    pub fn one_in(&mut self, n: u32) -> bool {
+        self.range(n) == 0
+    }
+}
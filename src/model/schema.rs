@@ -0,0 +1,31 @@
+use super::*;
+
+use std::io::{Error, ErrorKind, Result};
+
+// A type persisted to its own file.
+// When its layout changes, bump VERSION and teach `migrate` to read the old layout.
+pub trait Schema: BorshSerialize + BorshDeserialize {
+    // Layout currently written to disk. Files saved before envelopes existed are v1.
+    const VERSION: u16;
+
+    // Reads data saved with an older (or the current) layout
+    fn migrate(version: u16, data: &[u8]) -> Result<Self> {
+        if version == Self::VERSION {
+            return Self::try_from_slice(data);
+        }
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unsupported schema version {version}"),
+        ))
+    }
+
+    // Decodes a file in any supported layout
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        Versioned::<Self>::try_from_slice(bytes).map(|file| file.0)
+    }
+
+    // Encodes a file in the current layout
+    fn encode(&self) -> Result<Vec<u8>> {
+        Versioned(self).try_to_vec()
+    }
+}
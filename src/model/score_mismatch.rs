@@ -0,0 +1,10 @@
+use super::*;
+
+// A leaderboard score that does not match the re-simulated crawl
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ScoreMismatch {
+    pub kind: LeaderboardKind,
+    pub submitted: u32,
+    // None when the replayed crawl never ended
+    pub replayed: Option<u32>,
+}
@@ -0,0 +1,41 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq,
+)]
+pub enum ShopItem {
+    MaxHealth,
+    Heal,
+    Strength,
+    Item(ItemKind),
+}
+impl ShopItem {
+    pub const ALL: &'static [Self] = &[
+        Self::Heal,
+        Self::MaxHealth,
+        Self::Strength,
+        Self::Item(ItemKind::Potion),
+        Self::Item(ItemKind::Bomb),
+        Self::Item(ItemKind::TeleportScroll),
+    ];
+    pub fn price(&self) -> u32 {
+        match self {
+            Self::Heal => 10,
+            Self::MaxHealth => 30,
+            Self::Strength => 50,
+            Self::Item(ItemKind::Potion) => 15,
+            Self::Item(ItemKind::Bomb) => 20,
+            Self::Item(ItemKind::TeleportScroll) => 25,
+            Self::Item(ItemKind::RerollScroll) => 40,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Heal => "HEAL",
+            Self::MaxHealth => "MAX HP +1",
+            Self::Strength => "STRENGTH +1",
+            Self::Item(item) => item.name(),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+// Which kind of dungeon a monster spawn entry applies to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnTable {
+    Solo,
+    Coop,
+}
@@ -0,0 +1,43 @@
+use super::*;
+use std::fmt;
+
+// Why a gameplay command was turned away before it touched the dungeon.
+// Clients retry and double-send while they wait for a move to land, so these are expected and harmless.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum StaleCommand {
+    // The crawl ended or was replaced after the command was sent
+    WrongCrawl { expected: u32, received: u32 },
+    // The crawl has moved on to another floor since the command was sent
+    WrongFloor { expected: u32, received: u32 },
+    // The command (or a later one) was already applied
+    Duplicate { received: u32 },
+    // The command was sent for a turn that hasn't happened yet
+    OutOfOrder { expected: u32, received: u32 },
+}
+impl fmt::Display for StaleCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongCrawl { expected, received } => {
+                write!(
+                    f,
+                    "command for crawl {received}, but crawl {expected} is underway"
+                )
+            }
+            Self::WrongFloor { expected, received } => {
+                write!(
+                    f,
+                    "command for floor {received}, but floor {expected} is underway"
+                )
+            }
+            Self::Duplicate { received } => {
+                write!(f, "command for turn {received} was already applied")
+            }
+            Self::OutOfOrder { expected, received } => {
+                write!(
+                    f,
+                    "command for turn {received}, but turn {expected} is next"
+                )
+            }
+        }
+    }
+}
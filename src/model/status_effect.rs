@@ -0,0 +1,9 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub turns: u32,
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub enum StatusEffectKind {
+    Poison,
+    Freeze,
+    Slow,
+    Burn,
+}
+impl StatusEffectKind {
+    pub const ALL: &'static [Self] = &[Self::Poison, Self::Freeze, Self::Slow, Self::Burn];
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Poison => "poisoned",
+            Self::Freeze => "frozen",
+            Self::Slow => "slowed",
+            Self::Burn => "burning",
+        }
+    }
+    // Number of turns the effect lasts
+    pub fn duration(&self) -> u32 {
+        match self {
+            Self::Poison => 4,
+            Self::Freeze => 2,
+            Self::Slow => 4,
+            Self::Burn => 3,
+        }
+    }
+    // Damage dealt each turn
+    pub fn damage(&self) -> u32 {
+        match self {
+            Self::Poison | Self::Burn => 1,
+            Self::Freeze | Self::Slow => 0,
+        }
+    }
+    // Whether the effect costs the afflicted their turn with `turns` remaining.
+    // Freezing skips the next turn, then thaws for a turn. Slowing skips every other turn.
+    pub fn is_holding(&self, turns: u32) -> bool {
+        match self {
+            Self::Freeze | Self::Slow => turns.is_multiple_of(2),
+            Self::Poison | Self::Burn => false,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// Status effects afflicting a player or monster
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Default,
+)]
+pub struct StatusEffects {
+    pub list: Vec<StatusEffect>,
+}
+impl StatusEffects {
+    pub fn new() -> Self {
+        Self { list: vec![] }
+    }
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.list.iter().any(|effect| effect.kind == kind)
+    }
+    // Adds an effect for its full duration. Effects don't stack, so an active effect is left as is.
+    // Returns whether the effect was added.
+    pub fn add(&mut self, kind: StatusEffectKind) -> bool {
+        if self.has(kind) {
+            return false;
+        }
+        self.list.push(StatusEffect {
+            kind,
+            turns: kind.duration(),
+        });
+        true
+    }
+    // Whether an effect costs the afflicted their next turn
+    pub fn is_holding(&self) -> bool {
+        self.list
+            .iter()
+            .any(|effect| effect.kind.is_holding(effect.turns))
+    }
+    // Counts down every effect by a turn, dropping expired ones.
+    // Returns the damage dealt this turn.
+    pub fn tick(&mut self) -> u32 {
+        let damage = self.list.iter().map(|effect| effect.kind.damage()).sum();
+        for effect in self.list.iter_mut() {
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        self.list.retain(|effect| effect.turns > 0);
+        damage
+    }
+}
@@ -0,0 +1,1849 @@
+use super::*;
+
+fn log(_: &str) {}
+
+// An empty 8x8 floor with the player in the top-left corner
+fn empty_dungeon() -> Dungeon {
+    let mut dungeon = Dungeon::new(1, 42, DungeonStats::new(), PlayerAchievements::empty());
+    dungeon.width = 8;
+    dungeon.height = 8;
+    dungeon.player.x = 1;
+    dungeon.player.y = 1;
+    dungeon
+}
+
+fn monster(kind: MonsterKind, x: i32, y: i32) -> Monster {
+    let (health, strength) = kind.stats();
+    Monster {
+        x,
+        y,
+        health,
+        max_health: health,
+        strength,
+        direction: Direction::Down,
+        kind,
+        stun_dur: 0,
+        effects: StatusEffects::new(),
+    }
+}
+
+fn treasure(kind: TreasureKind, value: u32, x: i32, y: i32) -> Treasure {
+    Treasure { x, y, value, kind }
+}
+
+#[test]
+fn player_moves_to_empty_tile() {
+    let mut dungeon = empty_dungeon();
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+    assert!(matches!(dungeon.player.direction, Direction::Right));
+    assert!(dungeon.move_player(Direction::Down, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 2));
+    assert_eq!(dungeon.stats.get(DungeonStatKind::StepsMoved), 2);
+    assert_eq!(dungeon.total_stats.get(DungeonStatKind::StepsMoved), 2);
+}
+
+#[test]
+fn player_cannot_move_out_of_bounds() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.x = 0;
+    dungeon.player.y = 0;
+    assert!(!dungeon.move_player(Direction::Up, log));
+    assert!(!dungeon.move_player(Direction::Left, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (0, 0));
+    assert_eq!(dungeon.stats.get(DungeonStatKind::StepsMoved), 0);
+}
+
+#[test]
+fn player_is_blocked_by_obstacles() {
+    let mut dungeon = empty_dungeon();
+    dungeon.obstacles.push(Obstacle {
+        x: 2,
+        y: 1,
+        kind: ObstacleKind::WallA,
+    });
+    assert!(!dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (1, 1));
+}
+
+#[test]
+fn defeated_spectral_ghost_lets_player_walk_through_walls() {
+    let mut dungeon = empty_dungeon();
+    let mut ghost = monster(MonsterKind::SpectralGhost, 5, 5);
+    ghost.health = 0;
+    dungeon.monsters.push(ghost);
+    dungeon.obstacles.push(Obstacle {
+        x: 2,
+        y: 1,
+        kind: ObstacleKind::WallA,
+    });
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+}
+
+#[test]
+fn player_attacks_and_stuns_monster() {
+    let mut dungeon = empty_dungeon();
+    dungeon
+        .monsters
+        .push(monster(MonsterKind::OrangeGoblin, 2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+
+    // Player stays put and the monster takes damage
+    assert_eq!((dungeon.player.x, dungeon.player.y), (1, 1));
+    assert_eq!(dungeon.monsters[0].health, 4);
+    assert_eq!(dungeon.monsters[0].stun_dur, Monster::STUN_DUR);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::DamageDealt), 1);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::StepsMoved), 0);
+}
+
+#[test]
+fn player_swaps_places_with_stunned_monster() {
+    let mut dungeon = empty_dungeon();
+    let mut goblin = monster(MonsterKind::OrangeGoblin, 2, 1);
+    goblin.stun_dur = 1;
+    dungeon.monsters.push(goblin);
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (1, 1));
+    assert_eq!(dungeon.monsters[0].health, 5);
+}
+
+#[test]
+fn defeating_last_monster_drops_reward() {
+    let mut dungeon = empty_dungeon();
+    dungeon
+        .monsters
+        .push(monster(MonsterKind::GreenGoblin, 2, 1));
+    dungeon.player.strength = 2;
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.monsters[0].health, 0);
+    assert_eq!(dungeon.stats.monster_kills(MonsterKind::GreenGoblin), 1);
+    assert_eq!(dungeon.treasures.len(), 1);
+    assert_eq!((dungeon.treasures[0].x, dungeon.treasures[0].y), (2, 1));
+
+    // Dead monsters no longer block the player
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+    assert!(dungeon.treasures.is_empty());
+}
+
+#[test]
+fn adjacent_monster_attacks_player() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 1, 2));
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, 7);
+    assert!(matches!(dungeon.monsters[0].direction, Direction::Up));
+    assert_eq!(dungeon.stats.get(DungeonStatKind::DamageTaken), 3);
+}
+
+#[test]
+fn turns_record_what_happened() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.strength = 2;
+    dungeon.monsters.push(monster(MonsterKind::GreenGoblin, 2, 1));
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 1, 2));
+    dungeon.take_turn(Direction::Right, log).unwrap();
+    let (goblin, zombie) = (MonsterKind::GreenGoblin, MonsterKind::Zombie);
+    assert_eq!(
+        dungeon.events,
+        vec![
+            TurnEvent::PlayerAttacked { player: 0, monster: 0, kind: goblin },
+            TurnEvent::MonsterDamaged { monster: 0, kind: goblin, x: 2, y: 1, damage: 2 },
+            TurnEvent::MonsterDefeated { monster: 0, kind: goblin, x: 2, y: 1 },
+            TurnEvent::MonsterAttacked { monster: 1, kind: zombie, player: 0 },
+            TurnEvent::PlayerDamaged { player: 0, x: 1, y: 1, damage: 3, by: Some(zombie) },
+        ]
+    );
+    assert_eq!(dungeon.events[4].to_string(), "Zombie hits P1 for 3");
+
+    // Only the last turn's events are kept
+    dungeon.monsters.clear();
+    dungeon.take_turn(Direction::Down, log).unwrap();
+    assert!(dungeon.events.is_empty());
+}
+
+#[test]
+fn stunned_monster_skips_its_turn() {
+    let mut dungeon = empty_dungeon();
+    let mut zombie = monster(MonsterKind::Zombie, 1, 2);
+    zombie.stun_dur = 1;
+    dungeon.monsters.push(zombie);
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, 10);
+    assert_eq!(dungeon.monsters[0].stun_dur, 0);
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, 7);
+}
+
+#[test]
+fn monster_moves_towards_player() {
+    let mut dungeon = empty_dungeon();
+    dungeon
+        .monsters
+        .push(monster(MonsterKind::GreenGoblin, 5, 1));
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (4, 1));
+    assert!(matches!(dungeon.monsters[0].direction, Direction::Left));
+}
+
+// A wall down column 3 with a gap at the bottom of the 8x8 floor
+fn walled_dungeon() -> Dungeon {
+    let mut dungeon = empty_dungeon();
+    for y in 0..6 {
+        dungeon.obstacles.push(Obstacle {
+            x: 3,
+            y,
+            kind: ObstacleKind::WallA,
+        });
+    }
+    dungeon
+}
+
+#[test]
+fn pathfinder_routes_around_walls() {
+    let dungeon = walled_dungeon();
+    let paths = dungeon.pathfinder();
+    let path = paths.find_path((5, 1), (1, 1)).unwrap();
+    assert_eq!(path.len(), 14);
+    assert_eq!(path.last(), Some(&(1, 1)));
+    assert!(path.iter().all(|(x, y)| paths.is_walkable(*x, *y)));
+
+    // Sealing the gap cuts the floor in two
+    let mut paths = paths.clone();
+    paths.block(3, 6);
+    paths.block(3, 7);
+    assert_eq!(paths.find_path((5, 1), (1, 1)), None);
+    let distances = paths.distances((1, 1));
+    assert_eq!(paths.distance(&distances, 2, 7), Some(7));
+    assert_eq!(paths.distance(&distances, 5, 1), None);
+}
+
+#[test]
+fn walls_hide_what_is_behind_them() {
+    let dungeon = walled_dungeon();
+    let fov = dungeon.field_of_view();
+    assert!(fov.is_visible(1, 1));
+    assert!(fov.is_visible(3, 1));
+    assert!(!fov.is_visible(4, 1));
+    assert!(fov.is_visible(1, 5));
+    // Too far away
+    assert!(!fov.is_visible(1, 6));
+    assert!(fov.tiles().all(|(x, _)| x <= 3));
+}
+
+#[test]
+fn players_only_know_what_they_have_seen() {
+    let mut dungeon = walled_dungeon();
+    dungeon.exit_key = Some((5, 1));
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 1, 2, 3));
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 1, 5, 5));
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 6, 2));
+    dungeon.obstacles.push(obstacle(ObstacleKind::WallA, 6, 5));
+    dungeon.explore();
+    let view = DungeonView::new(&dungeon);
+    assert_eq!(view.exit_key, None);
+    assert_eq!(view.treasures.len(), 1);
+    assert!(view.monsters.is_empty());
+    assert!(!view.obstacles.iter().any(|obstacle| obstacle.x == 6));
+    // Auto-walking explores until the key turns up
+    let view = Dungeon::from(view);
+    assert_eq!(TravelTarget::objective(&view), TravelTarget::Unexplored);
+    assert_eq!(TravelTarget::Unexplored.position(&view), Some((1, 6)));
+
+    // Remembered tiles stay explored, but monsters are only shown while they're in sight
+    dungeon.player.x = 5;
+    dungeon.player.y = 4;
+    dungeon.explore();
+    let view = DungeonView::new(&dungeon);
+    assert_eq!(view.exit_key, Some((5, 1)));
+    assert_eq!(view.treasures.len(), 2);
+    assert_eq!(view.monsters.len(), 1);
+    assert_eq!(view.obstacles, dungeon.obstacles);
+    dungeon.player.x = 1;
+    dungeon.player.y = 1;
+    let view = DungeonView::new(&dungeon);
+    assert_eq!(view.exit_key, Some((5, 1)));
+    assert!(view.monsters.is_empty());
+
+    // Every floor starts unexplored
+    dungeon.next_floor();
+    assert!(dungeon.explored.is_empty());
+    dungeon.populate_floor(log);
+    assert!(dungeon.explored.contains(&(dungeon.player.x, dungeon.player.y)));
+}
+
+#[test]
+fn dungeon_views_only_tell_what_happened_in_sight() {
+    let mut dungeon = walled_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 6, 2));
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 2, 1));
+    let zombie = MonsterKind::Zombie;
+    dungeon.events = vec![
+        TurnEvent::MonsterDamaged { monster: 0, kind: zombie, x: 6, y: 2, damage: 1 },
+        TurnEvent::MonsterAttacked { monster: 1, kind: zombie, player: 0 },
+        TurnEvent::ExitAppeared { x: 6, y: 6 },
+    ];
+    let view = DungeonView::new(&dungeon);
+    // Monster indices point into the view's monsters
+    assert_eq!(
+        view.events,
+        vec![
+            TurnEvent::MonsterAttacked { monster: 0, kind: zombie, player: 0 },
+            TurnEvent::ExitAppeared { x: 6, y: 6 },
+        ]
+    );
+}
+
+#[test]
+fn retried_commands_are_turned_away() {
+    let mut dungeon = empty_dungeon();
+    dungeon.populate_floor(log);
+    let (crawl_id, actions) = (dungeon.crawl_id, dungeon.actions);
+    assert_eq!(dungeon.check_command(crawl_id, actions), Ok(()));
+    assert!(dungeon.take_turn(Direction::Right, log).is_ok());
+
+    // The same move sent again can't be applied twice
+    assert_eq!(
+        dungeon.check_command(crawl_id, actions),
+        Err(StaleCommand::Duplicate { received: actions })
+    );
+    assert_eq!(
+        dungeon.check_command(crawl_id + 1, dungeon.actions),
+        Err(StaleCommand::WrongCrawl {
+            expected: crawl_id,
+            received: crawl_id + 1,
+        })
+    );
+    assert!(dungeon.check_command(crawl_id, dungeon.actions + 1).is_err());
+
+    // Actions that don't cost a turn are still counted
+    let actions = dungeon.actions;
+    dungeon.player.gold = 100;
+    dungeon.floor = Dungeon::SHOP_INTERVAL - 1;
+    dungeon.exit = Some((dungeon.player.x, dungeon.player.y));
+    let turn = dungeon.turn;
+    assert!(dungeon.purchase(ShopItem::Strength, log).is_ok());
+    assert_eq!(dungeon.turn, turn);
+    assert_eq!(dungeon.actions, actions + 1);
+    dungeon.next_floor();
+    assert_eq!(dungeon.turn, 0);
+    assert_eq!(dungeon.actions, actions + 2);
+}
+
+#[test]
+fn dungeon_views_keep_the_seed_on_the_server() {
+    let mut dungeon = empty_dungeon();
+    dungeon.populate_floor(log);
+    dungeon.total_stats.increment(DungeonStatKind::GoldCollected, 10);
+    let view = DungeonView::new(&dungeon);
+    let bytes = view.encode().unwrap();
+    assert_eq!(DungeonView::decode(&bytes).unwrap(), view);
+    // The full dungeon can't be read from a view file
+    assert!(Dungeon::decode(&bytes).is_err());
+
+    let dungeon = Dungeon::from(view);
+    assert_eq!(dungeon.seed, 0);
+    assert_eq!(dungeon.total_stats, DungeonStats::new());
+}
+
+// Whether every tile without a wall can be walked to from `start`
+fn is_connected(width: usize, height: usize, walls: &[(i32, i32)], start: (i32, i32)) -> bool {
+    let mut paths = Pathfinder::new(width as u32, height as u32);
+    for (x, y) in walls {
+        paths.block(*x, *y);
+    }
+    let distances = paths.distances(start);
+    (0..height as i32)
+        .flat_map(|y| (0..width as i32).map(move |x| (x, y)))
+        .filter(|(x, y)| paths.is_walkable(*x, *y))
+        .all(|(x, y)| paths.distance(&distances, x, y).is_some())
+}
+
+#[test]
+fn connect_regions_tunnels_into_sealed_rooms() {
+    // A wall down the middle with a walled-in pocket on the right
+    let mut walls: Vec<(i32, i32)> = (0..6).map(|y| (2, y)).collect();
+    walls.extend([(4, 1), (4, 3), (3, 2), (5, 2)]);
+    assert!(!is_connected(7, 6, &walls, (0, 0)));
+    connect_regions(7, 6, &mut walls, (0, 0));
+    assert!(is_connected(7, 6, &walls, (0, 0)));
+    // Two tunnels of one wall each: through the middle wall and into the pocket
+    assert_eq!(walls.len(), 8);
+}
+
+#[test]
+fn every_generator_makes_connected_floors() {
+    for generator in GeneratorKind::ALL {
+        for seed in 0..50 {
+            let mut rng = Rng::new(seed);
+            let size = 5 + seed as usize % 8;
+            let mut walls = generator.generate(&mut rng, size, size);
+            assert!(walls.iter().all(|(x, y)| *x < size as i32 && *y < size as i32));
+            walls.retain(|wall| *wall != (0, 0));
+            connect_regions(size, size, &mut walls, (0, 0));
+            assert!(is_connected(size, size, &walls, (0, 0)), "{generator:?}");
+        }
+    }
+}
+
+#[test]
+fn populated_floors_are_fully_connected() {
+    for seed in 0..10 {
+        let stats = DungeonStats::new();
+        let mut dungeon = Dungeon::new(1, seed, stats, PlayerAchievements::empty());
+        for _ in 0..12 {
+            dungeon.next_floor();
+            dungeon.populate_floor(log);
+            let walls: Vec<_> = dungeon
+                .obstacles
+                .iter()
+                .filter(|obs| obs.kind.is_solid())
+                .map(|obs| (obs.x, obs.y))
+                .collect();
+            let (width, height) = (dungeon.width as usize, dungeon.height as usize);
+            let player = (dungeon.player.x, dungeon.player.y);
+            // Solid hazards are only placed where they keep the exit key reachable
+            let is_hazard = |(x, y): &(i32, i32)| {
+                let kind = dungeon.obstacle_at(*x, *y).map(|obs| obs.kind);
+                !matches!(kind, Some(ObstacleKind::WallA | ObstacleKind::WallB))
+            };
+            let maze_walls: Vec<_> = walls.iter().copied().filter(|w| !is_hazard(w)).collect();
+            assert!(is_connected(width, height, &maze_walls, player));
+        }
+    }
+}
+
+#[test]
+fn chasing_monsters_walk_around_walls() {
+    let mut dungeon = walled_dungeon();
+    dungeon
+        .monsters
+        .push(monster(MonsterKind::OrangeGoblin, 5, 1));
+    for _ in 0..13 {
+        dungeon.move_monsters(log);
+        dungeon.turn += 1;
+    }
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (2, 1));
+    dungeon.move_monsters(log);
+    assert!(dungeon.player.health < dungeon.player.max_health);
+}
+
+#[test]
+fn spiders_lunge_along_the_path() {
+    let mut dungeon = walled_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Spider, 5, 1));
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (4, 3));
+    assert!(matches!(dungeon.monsters[0].direction, Direction::Down));
+
+    // Then waits for its next lunge
+    dungeon.turn = 1;
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (4, 3));
+}
+
+#[test]
+fn wounded_goblins_flee() {
+    let mut dungeon = empty_dungeon();
+    let mut goblin = monster(MonsterKind::GreenGoblin, 4, 1);
+    goblin.health = 1;
+    dungeon.monsters.push(goblin);
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (5, 1));
+
+    // Out of range, so it stops running
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (5, 1));
+}
+
+#[test]
+fn ranged_monsters_telegraph_their_attacks() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Snowman, 5, 1));
+
+    // Takes aim instead of moving
+    dungeon.turn = 1;
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (5, 1));
+    assert!(matches!(dungeon.monsters[0].direction, Direction::Left));
+    assert_eq!(dungeon.danger_tiles.len(), 1);
+    assert_eq!((dungeon.danger_tiles[0].x, dungeon.danger_tiles[0].y), (1, 1));
+    assert_eq!(dungeon.player.health, 10);
+
+    // Players who dodge aren't hit
+    let mut dodged = dungeon.clone();
+    dodged.player.y = 2;
+    dodged.turn = 2;
+    dodged.move_monsters(log);
+    assert_eq!(dodged.player.health, 10);
+    assert!(dodged.danger_tiles.is_empty());
+
+    // Players who stay put are
+    dungeon.turn = 2;
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, 7);
+    assert!(dungeon.danger_tiles.is_empty());
+}
+
+#[test]
+fn walls_block_ranged_attacks() {
+    let mut dungeon = walled_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Spider, 5, 1));
+    dungeon.turn = 1;
+    dungeon.move_monsters(log);
+    assert!(dungeon.danger_tiles.is_empty());
+
+    // Out of range
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Spider, 6, 1));
+    dungeon.turn = 1;
+    dungeon.move_monsters(log);
+    assert!(dungeon.danger_tiles.is_empty());
+    dungeon.monsters[0].x = 4;
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.danger_tiles.len(), 1);
+}
+
+#[test]
+fn status_effects_tick_down_without_stacking() {
+    let mut effects = StatusEffects::new();
+    assert!(effects.add(StatusEffectKind::Poison));
+    assert!(!effects.add(StatusEffectKind::Poison));
+    assert!(effects.add(StatusEffectKind::Slow));
+    assert_eq!(effects.list.len(), 2);
+
+    // Slowing holds every other turn while poison deals damage until it wears off
+    let mut held = vec![];
+    let mut damage = 0;
+    while !effects.list.is_empty() {
+        held.push(effects.is_holding());
+        damage += effects.tick();
+    }
+    assert_eq!(held, vec![true, false, true, false]);
+    assert_eq!(damage, StatusEffectKind::Poison.duration());
+}
+
+#[test]
+fn ice_yeti_hits_freeze_the_player() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::IceYeti, 2, 1));
+    dungeon.move_monsters(log);
+    assert!(dungeon.player.effects.has(StatusEffectKind::Freeze));
+    assert_eq!(
+        dungeon
+            .stats
+            .get(DungeonStatKind::Afflicted(StatusEffectKind::Freeze)),
+        1
+    );
+
+    // Frozen players lose their next turn
+    assert!(dungeon.take_turn(Direction::Down, log).is_ok());
+    assert_eq!((dungeon.player.x, dungeon.player.y), (1, 1));
+
+    // Then thaw for a turn before they can be frozen again
+    assert!(dungeon.take_turn(Direction::Down, log).is_ok());
+    assert_eq!((dungeon.player.x, dungeon.player.y), (1, 2));
+}
+
+#[test]
+fn spider_bites_poison_the_player() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Spider, 2, 1));
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, 8);
+    assert!(dungeon.player.effects.has(StatusEffectKind::Poison));
+
+    // Poison hurts at the end of each of the player's turns
+    dungeon.monsters.clear();
+    for _ in 0..StatusEffectKind::Poison.duration() {
+        assert!(dungeon.move_player(Direction::Down, log));
+    }
+    assert_eq!(dungeon.player.health, 4);
+    assert!(dungeon.player.effects.list.is_empty());
+    assert_eq!(dungeon.stats.get(DungeonStatKind::DamageTaken), 6);
+}
+
+#[test]
+fn firepits_burn_players_who_touch_them() {
+    let mut dungeon = empty_dungeon();
+    dungeon.obstacles.push(Obstacle {
+        x: 2,
+        y: 1,
+        kind: ObstacleKind::WallB,
+    });
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (1, 1));
+    assert!(dungeon.player.effects.has(StatusEffectKind::Burn));
+    assert_eq!(dungeon.player.health, 9);
+}
+
+fn obstacle(kind: ObstacleKind, x: i32, y: i32) -> Obstacle {
+    Obstacle { x, y, kind }
+}
+
+#[test]
+fn spike_traps_hurt_players() {
+    let mut dungeon = empty_dungeon();
+    dungeon.obstacles.push(obstacle(ObstacleKind::SpikeTrap, 2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+    assert_eq!(dungeon.player.health, 10 - ObstacleKind::SPIKE_DAMAGE);
+}
+
+#[test]
+fn pressure_plates_open_doors() {
+    let mut dungeon = empty_dungeon();
+    dungeon.obstacles.push(obstacle(ObstacleKind::Door, 1, 2));
+    dungeon.obstacles.push(obstacle(ObstacleKind::PressurePlate, 2, 1));
+    assert!(!dungeon.move_player(Direction::Down, log));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.obstacles.len(), 1);
+    assert!(!dungeon.is_obstacle(1, 2));
+}
+
+#[test]
+fn ice_floors_make_the_player_slide() {
+    let mut dungeon = empty_dungeon();
+    for x in 2..5 {
+        dungeon.obstacles.push(obstacle(ObstacleKind::IceFloor, x, 1));
+    }
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 5, 3, 1));
+    dungeon.obstacles.push(obstacle(ObstacleKind::WallA, 5, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (4, 1));
+    assert!(dungeon.treasures.is_empty());
+}
+
+#[test]
+fn crates_are_pushed_until_they_hit_something() {
+    let mut dungeon = empty_dungeon();
+    dungeon.obstacles.push(obstacle(ObstacleKind::Crate, 2, 1));
+    dungeon.obstacles.push(obstacle(ObstacleKind::WallA, 4, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+    assert!(dungeon.is_obstacle(3, 1));
+    assert!(!dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (2, 1));
+}
+
+#[test]
+fn one_way_doors_only_open_one_way() {
+    let mut dungeon = empty_dungeon();
+    let door = ObstacleKind::OneWayDoor(Direction::Right);
+    dungeon.obstacles.push(obstacle(door, 2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (3, 1));
+    assert!(!dungeon.move_player(Direction::Left, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (3, 1));
+}
+
+// Takes turns towards `target` until auto-walking stops, returning every tile walked through
+fn travel(dungeon: &mut Dungeon, target: TravelTarget) -> Vec<(i32, i32)> {
+    let mut walked = vec![];
+    while let Some(direction) = target.next_step(dungeon) {
+        assert!(dungeon.take_turn(direction, log).is_ok());
+        walked.push((dungeon.player.x, dungeon.player.y));
+    }
+    walked
+}
+
+#[test]
+fn auto_walk_goes_around_walls_and_hazards() {
+    let mut dungeon = walled_dungeon();
+    dungeon.obstacles.push(obstacle(ObstacleKind::SpikeTrap, 3, 6));
+    dungeon.exit_key = Some((5, 1));
+    assert_eq!(TravelTarget::objective(&dungeon), TravelTarget::ExitKey);
+
+    let walked = travel(&mut dungeon, TravelTarget::ExitKey);
+    assert_eq!(walked.last(), Some(&(5, 1)));
+    assert!(walked.contains(&(3, 7)));
+    assert_eq!(dungeon.player.health, 10);
+    assert_eq!(dungeon.exit_key, None);
+    assert_eq!(TravelTarget::objective(&dungeon), TravelTarget::Exit);
+
+    // Walls can't be walked to
+    assert_eq!(TravelTarget::Tile(3, 0).position(&dungeon), None);
+    assert_eq!(TravelTarget::Tile(6, 6).position(&dungeon), Some((6, 6)));
+}
+
+#[test]
+fn auto_walk_picks_the_closest_treasure_by_path() {
+    let mut dungeon = walled_dungeon();
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 1, 4, 1));
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 2, 1, 6));
+    let target = TravelTarget::NearestTreasure;
+    assert_eq!(target.position(&dungeon), Some((1, 6)));
+
+    // It keeps going until every treasure is picked up
+    let walked = travel(&mut dungeon, target);
+    assert_eq!(walked[4], (1, 6));
+    assert_eq!(walked.last(), Some(&(4, 1)));
+    assert_eq!(dungeon.player.gold, 3);
+    assert_eq!(target.position(&dungeon), None);
+}
+
+#[test]
+fn auto_walk_stops_when_a_monster_gets_close() {
+    let mut dungeon = empty_dungeon();
+    dungeon.exit_key = Some((6, 1));
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 6, 3));
+    travel(&mut dungeon, TravelTarget::ExitKey);
+    let (x, y) = (dungeon.player.x, dungeon.player.y);
+    assert!(dungeon.monsters[0].distance_to(x, y) <= TravelTarget::ALERT_DISTANCE);
+    assert!(dungeon.exit_key.is_some());
+    assert_eq!(TravelTarget::ExitKey.next_step(&dungeon), None);
+}
+
+#[test]
+fn hazards_never_cut_off_the_exit_key() {
+    for seed in 0..20 {
+        let stats = DungeonStats::new();
+        let mut dungeon = Dungeon::new(1, seed, stats, PlayerAchievements::empty());
+        for _ in 0..6 {
+            dungeon.next_floor();
+            dungeon.populate_floor(log);
+            assert!(dungeon.exit_key.is_some() || dungeon.is_boss_floor());
+            assert!(dungeon.can_reach_exit_key());
+        }
+    }
+}
+
+#[test]
+fn shipped_prefab_rooms_parse() {
+    let rooms = PrefabRoom::all();
+    assert!(!rooms.is_empty());
+    for room in rooms {
+        assert_eq!(room.tiles.len(), room.width * room.height, "{}", room.name);
+        assert!(room.width <= 5 || room.min_floor >= 4, "{}", room.name);
+    }
+}
+
+const TEST_ROOM: &str = "
+// A comment
+room Test Room
+min_floor 3
+weight 2
+g = monster GreenGoblin
+b = treasure Item Bomb 1
+v = obstacle OneWayDoor Down
+map
+#g.
+b~v
+end
+";
+
+#[test]
+fn prefab_rooms_read_their_legend_and_map() {
+    let rooms = PrefabRoom::parse_all(TEST_ROOM).unwrap();
+    assert_eq!(rooms.len(), 1);
+    let room = &rooms[0];
+    assert_eq!(room.name, "Test Room");
+    assert_eq!((room.min_floor, room.weight), (3, 2));
+    assert_eq!((room.width, room.height), (3, 2));
+    assert_eq!(room.tile(0, 0), &PrefabTile::Obstacle(ObstacleKind::WallA));
+    assert_eq!(room.tile(1, 0), &PrefabTile::Monster(MonsterKind::GreenGoblin));
+    assert_eq!(room.tile(2, 0), &PrefabTile::Floor);
+    let bomb = TreasureKind::Item(ItemKind::Bomb);
+    assert_eq!(room.tile(0, 1), &PrefabTile::Treasure(bomb, 1));
+    assert_eq!(room.tile(1, 1), &PrefabTile::Obstacle(ObstacleKind::IceFloor));
+    let door = ObstacleKind::OneWayDoor(Direction::Down);
+    assert_eq!(room.tile(2, 1), &PrefabTile::Obstacle(door));
+}
+
+#[test]
+fn prefab_room_errors_point_at_the_bad_line() {
+    let error = |text: &str| PrefabRoom::parse_all(text).unwrap_err();
+
+    let err = error("room A\nmap\n..\n.Q\nend");
+    assert_eq!(err.line, 4);
+    assert!(err.message.contains("unknown glyph `Q`"));
+
+    let err = error("room A\nmap\n..\n...\nend");
+    assert_eq!(err.line, 4);
+    assert!(err.message.contains("3 tiles wide but the first row is 2"));
+
+    let err = error("room A\ng = monster Goblin\nmap\ng\nend");
+    assert_eq!(err.line, 2);
+    assert!(err.message.contains("unknown monster `Goblin`"));
+    assert!(err.message.contains("GreenGoblin"));
+
+    let err = error("room A\nY = monster YetiKing\nmap\nY\nend");
+    assert!(err.message.contains("bosses"));
+
+    let err = error("room A\nmin_floor three\nmap\n.\nend");
+    assert_eq!(err.to_string(), "line 2: expected a number, found `three`");
+
+    let err = error("room A\n$ = treasure Gold\nmap\n$\nend");
+    assert_eq!(err.line, 2);
+
+    let err = error("\n\nroom A\nmap\n...");
+    assert_eq!(err.line, 3);
+    assert!(err.message.contains("missing `end`"));
+
+    let err = error("room A\nmap\nend");
+    assert!(err.message.contains("empty map"));
+
+    let err = error("map\n...\nend");
+    assert_eq!(err.line, 1);
+    assert!(err.message.contains("expected `room <name>`"));
+}
+
+#[test]
+fn prefab_rooms_are_stamped_around_the_player() {
+    let room = &PrefabRoom::parse_all(TEST_ROOM).unwrap()[0];
+    let mut dungeon = empty_dungeon();
+    dungeon.width = 4;
+    dungeon.height = 4;
+    dungeon.exit_key = Some((3, 3));
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 1, 0, 3));
+    for _ in 0..10 {
+        let mut dungeon = dungeon.clone();
+        assert!(dungeon.stamp_prefab_room(room));
+        assert_eq!(dungeon.monsters.len(), 1);
+        assert!(dungeon
+            .treasures
+            .iter()
+            .any(|t| t.kind == TreasureKind::Item(ItemKind::Bomb)));
+        assert!(dungeon.obstacles.len() >= 2);
+        let monster = &dungeon.monsters[0];
+        assert_ne!((monster.x, monster.y), (1, 1));
+        assert_eq!(dungeon.exit_key, Some((3, 3)));
+    }
+
+    // Rooms that don't fit are never stamped
+    dungeon.width = 2;
+    assert!(!dungeon.stamp_prefab_room(room));
+}
+
+#[test]
+fn slowed_monsters_move_every_other_turn() {
+    let mut dungeon = empty_dungeon();
+    let mut goblin = monster(MonsterKind::GreenGoblin, 5, 1);
+    goblin.effects.add(StatusEffectKind::Slow);
+    dungeon.monsters.push(goblin);
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (5, 1));
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (4, 1));
+}
+
+#[test]
+fn every_monster_has_a_definition() {
+    let defs = MonsterDef::all();
+    assert_eq!(defs.len(), MonsterKind::ALL.len());
+    for (i, kind) in MonsterKind::ALL.iter().enumerate() {
+        assert_eq!(kind.def().kind, *kind);
+        assert_eq!(MonsterKind::by_index(i), *kind);
+    }
+    assert_eq!(MonsterKind::OrangeGoblin.stats(), (5, 1));
+    assert_eq!(MonsterKind::BlueBlob.abbrev(), "B. Blob");
+    assert!(MonsterKind::YetiKing.is_boss());
+    assert_eq!(MonsterKind::Snowman.ranged_attack(), Some((4, 2)));
+    let flee = MonsterMovement::Flee { range: 3 };
+    assert_eq!(MonsterKind::GreenGoblin.movement(true), flee);
+    assert_eq!(MonsterKind::GreenGoblin.movement(false), MonsterMovement::Chase);
+}
+
+#[test]
+fn monster_tables_must_define_every_kind_once() {
+    let json = r#"[{ "kind": "GreenGoblin", "name": "G", "health": 1, "strength": 1 }]"#;
+    let err = MonsterDef::parse(json).unwrap_err();
+    assert_eq!(err, "OrangeGoblin is missing");
+
+    let defs: Vec<String> = MonsterKind::ALL
+        .iter()
+        .chain([&MonsterKind::Zombie])
+        .map(|kind| format!(r#"{{ "kind": "{kind:?}", "name": "M", "health": 1, "strength": 1 }}"#))
+        .collect();
+    let err = MonsterDef::parse(&format!("[{}]", defs.join(","))).unwrap_err();
+    assert_eq!(err, "Zombie is defined more than once");
+
+    assert!(MonsterDef::parse("[{ \"kind\": \"Goblin\" }]").is_err());
+}
+
+#[test]
+fn monster_tables_reject_ranged_attacks_that_never_aim() {
+    for every in [0, 1] {
+        let json = format!(
+            r#"[{{ "kind": "Snowman", "name": "S", "health": 1, "strength": 1,
+                "ranged_attack": {{ "range": 4, "every": {every} }} }}]"#
+        );
+        let err = MonsterDef::parse(&json).unwrap_err();
+        assert_eq!(err, "Snowman must take aim every 2 turns or more");
+    }
+}
+
+#[test]
+fn monster_tables_reject_bosses_without_phases() {
+    let json = r#"[{ "kind": "YetiKing", "name": "Y", "health": 1, "strength": 1,
+        "is_boss": true }]"#;
+    let err = MonsterDef::parse(json).unwrap_err();
+    assert_eq!(err, "YetiKing is a boss without any phases");
+}
+
+#[test]
+fn monster_paces_come_from_their_definitions() {
+    let yeti = MonsterKind::IceYeti.def();
+    assert_eq!(yeti.pace(0), MonsterPace::Every(3));
+    assert_eq!(yeti.pace(74), MonsterPace::RestEvery(3));
+    assert!(MonsterPace::Every(3).moves_on(6) && !MonsterPace::Every(3).moves_on(7));
+    assert!(!MonsterPace::RestEvery(3).moves_on(6) && MonsterPace::RestEvery(3).moves_on(7));
+
+    let king = MonsterKind::YetiKing.def();
+    assert_eq!(king.boss_phase(1).map(|phase| phase.attack), Some(BossAttack::Slam));
+    assert_eq!(king.boss_phase(3).map(|phase| phase.attack), Some(BossAttack::Shockwave));
+    assert_eq!(MonsterKind::Zombie.def().boss_phase(1), None);
+}
+
+#[test]
+fn spawn_weights_depend_on_theme_floor_and_mode() {
+    use MonsterKind::*;
+    let weights = MonsterDef::spawn_weights(SpawnTable::Solo, DungeonThemeKind::Castle, 1, true);
+    let expected = [(1, GreenGoblin), (1, OrangeGoblin), (2, BlueBlob), (4, IceYeti), (2, Snowman)];
+    assert_eq!(weights, expected);
+
+    let weights = MonsterDef::spawn_weights(SpawnTable::Solo, DungeonThemeKind::Castle, 1, false);
+    assert_eq!(weights, expected[..3]);
+
+    // Evil Turbi shows up after floor 20 and even more after floor 30
+    let weights = MonsterDef::spawn_weights(SpawnTable::Solo, DungeonThemeKind::IceCave, 49, true);
+    assert!(weights.contains(&(3, Spider)));
+    assert!(weights.contains(&(6, EvilTurbi)));
+
+    let weights = MonsterDef::spawn_weights(SpawnTable::Coop, DungeonThemeKind::Arctic, 25, true);
+    let expected = [(3, GreenGoblin), (2, Shade), (1, Spider), (3, EvilTurbi), (1, Snowman)];
+    assert_eq!(weights, expected);
+}
+
+#[test]
+fn player_dies_and_cannot_move() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.health = 2;
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 2, 1));
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, 0);
+    assert_eq!(dungeon.stats.deaths_by_monster(MonsterKind::Zombie), 1);
+    assert!(!dungeon.move_player(Direction::Down, log));
+    assert_eq!((dungeon.player.x, dungeon.player.y), (1, 1));
+
+    // Monsters leave dead players alone
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::DamageTaken), 2);
+}
+
+#[test]
+fn player_collects_gold() {
+    let mut dungeon = empty_dungeon();
+    dungeon
+        .treasures
+        .push(treasure(TreasureKind::Gold, 10, 2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.player.gold, 10);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::GoldCollected), 10);
+    assert!(dungeon.treasures.is_empty());
+}
+
+#[test]
+fn healing_is_capped_at_max_health() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.health = 9;
+    dungeon
+        .treasures
+        .push(treasure(TreasureKind::Heal, 2, 2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.player.health, 10);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::HealthRecovered), 1);
+}
+
+#[test]
+fn health_up_raises_max_health() {
+    let mut dungeon = empty_dungeon();
+    dungeon
+        .treasures
+        .push(treasure(TreasureKind::HealthUp, 1, 2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.player.max_health, 11);
+    assert_eq!(dungeon.player.health, 11);
+}
+
+#[test]
+fn items_go_into_the_inventory() {
+    let mut dungeon = empty_dungeon();
+    let scroll = TreasureKind::Item(ItemKind::TeleportScroll);
+    dungeon.treasures.push(treasure(scroll.clone(), 1, 2, 1));
+    dungeon.move_player(Direction::Right, log);
+    assert_eq!(dungeon.player.inventory, vec![ItemKind::TeleportScroll]);
+    assert!(dungeon.treasures.is_empty());
+
+    // Items stay on the floor when the hotbar is full
+    dungeon.player.inventory = vec![ItemKind::Potion; Player::MAX_ITEMS];
+    dungeon.treasures.push(treasure(scroll, 1, 3, 1));
+    dungeon.move_player(Direction::Right, log);
+    assert_eq!(dungeon.player.inventory.len(), Player::MAX_ITEMS);
+    assert_eq!(dungeon.treasures.len(), 1);
+}
+
+#[test]
+fn potion_heals_and_takes_a_turn() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.inventory = vec![ItemKind::Potion];
+    assert!(dungeon.use_item(0, log).is_err());
+    dungeon.player.health = 2;
+    assert!(dungeon.use_item(0, log).is_ok());
+    assert_eq!(dungeon.player.health, 2 + ItemKind::POTION_HEAL);
+    assert!(dungeon.player.inventory.is_empty());
+    assert_eq!(dungeon.turn, 1);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::ItemsUsed), 1);
+    assert!(dungeon.use_item(0, log).is_err());
+}
+
+#[test]
+fn bomb_destroys_adjacent_obstacles() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.inventory = vec![ItemKind::Bomb];
+    assert!(dungeon.use_item(0, log).is_err());
+    for (x, y) in [(2, 2), (0, 1), (3, 1)] {
+        dungeon.obstacles.push(Obstacle {
+            x,
+            y,
+            kind: ObstacleKind::WallA,
+        });
+    }
+    assert!(dungeon.use_item(0, log).is_ok());
+    assert_eq!(dungeon.obstacles.len(), 1);
+    assert!(dungeon.is_obstacle(3, 1));
+}
+
+#[test]
+fn teleport_scroll_moves_player_to_empty_tile() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.inventory = vec![ItemKind::TeleportScroll];
+    dungeon.monsters.push(monster(MonsterKind::BlueBlob, 6, 6));
+    assert!(dungeon.use_item(0, log).is_ok());
+    let (x, y) = (dungeon.player.x, dungeon.player.y);
+    assert!(!dungeon.is_out_of_bounds(x, y));
+    assert!(!dungeon.is_monster(x, y));
+
+    // A packed floor keeps the scroll for later
+    dungeon.player.inventory = vec![ItemKind::TeleportScroll];
+    dungeon.width = 2;
+    dungeon.height = 2;
+    dungeon.player.x = 0;
+    dungeon.player.y = 0;
+    dungeon.monsters = vec![monster(MonsterKind::BlueBlob, 1, 0)];
+    dungeon.obstacles = vec![obstacle(ObstacleKind::WallA, 0, 1)];
+    dungeon.treasures = vec![treasure(TreasureKind::Gold, 1, 1, 1)];
+    assert_eq!(dungeon.use_item(0, log), Err("Nowhere to teleport"));
+    assert_eq!(dungeon.player.inventory, vec![ItemKind::TeleportScroll]);
+}
+
+#[test]
+fn reroll_scroll_only_works_before_the_first_step() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.inventory = vec![ItemKind::RerollScroll];
+    dungeon.turn = 1;
+    assert!(dungeon.use_item(0, log).is_err());
+    assert_eq!(dungeon.player.inventory.len(), 1);
+
+    dungeon.turn = 0;
+    assert!(dungeon.use_item(0, log).is_ok());
+    assert!(dungeon.player.inventory.is_empty());
+    assert!(dungeon.exit_key.is_some());
+    // Rerolling doesn't take a turn
+    assert_eq!(dungeon.turn, 0);
+}
+
+#[test]
+fn reroll_scroll_only_works_on_the_first_floor() {
+    let mut dungeon = empty_dungeon();
+    dungeon.player.inventory = vec![ItemKind::RerollScroll, ItemKind::RerollScroll];
+    dungeon.floor = 1;
+    assert!(dungeon.use_item(0, log).is_err());
+    assert_eq!(dungeon.player.inventory.len(), 2);
+
+    // Unused scrolls are left behind on the way down
+    dungeon.floor = 0;
+    dungeon.player.inventory.push(ItemKind::Potion);
+    dungeon.next_floor();
+    assert_eq!(dungeon.player.inventory, vec![ItemKind::Potion]);
+}
+
+// An empty floor with the player standing on the exit of a shop floor
+fn shop_dungeon() -> Dungeon {
+    let mut dungeon = empty_dungeon();
+    dungeon.floor = Dungeon::SHOP_INTERVAL - 1;
+    dungeon.exit = Some((1, 1));
+    dungeon
+}
+
+#[test]
+fn shop_opens_at_the_exit_of_every_nth_floor() {
+    let mut dungeon = shop_dungeon();
+    assert!(dungeon.is_shop_open());
+    dungeon.floor += 1;
+    assert!(!dungeon.is_shop_open());
+    dungeon.floor -= 1;
+    dungeon.exit = Some((2, 1));
+    assert!(!dungeon.is_shop_open());
+    dungeon.player.gold = 100;
+    assert!(dungeon.purchase(ShopItem::Strength, log).is_err());
+}
+
+#[test]
+fn purchases_spend_gold() {
+    let mut dungeon = shop_dungeon();
+    dungeon.player.gold = 40;
+    assert!(dungeon.purchase(ShopItem::Strength, log).is_err());
+    assert!(dungeon.purchase(ShopItem::Heal, log).is_err());
+    assert!(dungeon.purchase(ShopItem::MaxHealth, log).is_ok());
+    assert_eq!((dungeon.player.health, dungeon.player.max_health), (11, 11));
+    assert_eq!(dungeon.player.gold, 10);
+
+    dungeon.player.inventory = vec![ItemKind::Bomb; Player::MAX_ITEMS];
+    dungeon.player.gold = 15;
+    assert!(dungeon.purchase(ShopItem::Item(ItemKind::Potion), log).is_err());
+    dungeon.player.inventory.pop();
+    assert!(dungeon.purchase(ShopItem::Item(ItemKind::Potion), log).is_ok());
+    assert_eq!(dungeon.player.inventory.last(), Some(&ItemKind::Potion));
+    assert_eq!(dungeon.player.gold, 0);
+
+    assert_eq!(dungeon.stats.get(DungeonStatKind::GoldSpent), 45);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::ItemsPurchased), 2);
+    assert_eq!(dungeon.turn, 0);
+}
+
+#[test]
+fn turned_down_purchases_say_why() {
+    let mut dungeon = shop_dungeon();
+    dungeon.player.gold = 10;
+    assert_eq!(dungeon.purchase(ShopItem::Strength, log), Err("Not enough gold"));
+    dungeon.player.gold = 100;
+    assert_eq!(dungeon.purchase(ShopItem::Heal, log), Err("Already at full health"));
+    dungeon.player.inventory = vec![ItemKind::Bomb; Player::MAX_ITEMS];
+    let potion = ShopItem::Item(ItemKind::Potion);
+    assert_eq!(dungeon.purchase(potion, log), Err("Inventory full"));
+    dungeon.exit = Some((2, 1));
+    assert_eq!(dungeon.purchase(potion, log), Err("The shop is closed"));
+
+    // Nothing is spent or counted when a purchase is turned down
+    assert_eq!(dungeon.player.gold, 100);
+    assert_eq!(dungeon.actions, 0);
+    assert_eq!(dungeon.stats.get(DungeonStatKind::ItemsPurchased), 0);
+}
+
+#[test]
+fn toasts_expire() {
+    let toast = Toast::new("Not enough gold", 10);
+    assert!(!toast.is_expired(10));
+    assert!(!toast.is_expired(10 + Toast::DURATION - 1));
+    assert!(toast.is_expired(10 + Toast::DURATION));
+    // Ticks from before the toast was shown don't underflow
+    assert!(!toast.is_expired(0));
+}
+
+#[test]
+fn combat_logs_keep_each_turn_once() {
+    let mut log = CombatLog::new();
+    let hit = TurnEvent::PlayerDamaged { player: 0, x: 1, y: 1, damage: 3, by: None };
+    assert!(log.record((1, 0, 1), &[hit.clone()], 10));
+    // The same turn polled again isn't logged twice
+    assert!(!log.record((1, 0, 1), &[hit.clone()], 11));
+    assert_eq!(log.lines, vec!["P1 takes 3 damage"]);
+    assert_eq!(log.updated_at, 10);
+
+    // Old lines are dropped once the log is full
+    for turn in 2..60 {
+        log.record((1, 0, turn), &[hit.clone(), hit.clone()], 12);
+    }
+    assert_eq!(log.lines.len(), CombatLog::MAX_LINES);
+
+    // Scrolling stops at either end
+    log.scroll_by(-1, 4);
+    assert_eq!(log.scroll, 0);
+    log.scroll_by(100, 4);
+    assert_eq!(log.scroll, CombatLog::MAX_LINES - 4);
+    assert_eq!(log.visible(4).len(), 4);
+
+    // A new crawl starts a new log
+    assert!(log.record((2, 0, 0), &[], 13));
+    assert!(log.lines.is_empty());
+    assert_eq!(log.visible(4).len(), 0);
+}
+
+#[test]
+fn boss_floors_have_a_boss_instead_of_an_exit_key() {
+    let mut dungeon = empty_dungeon();
+    dungeon.floor = Dungeon::BOSS_INTERVAL - 1;
+    assert!(dungeon.is_boss_floor());
+    dungeon.populate_floor(log);
+    assert_eq!(dungeon.monsters.len(), 1);
+    assert!(dungeon.monsters[0].kind.is_boss());
+    assert_eq!(dungeon.exit_key, None);
+    assert!(dungeon.obstacles.is_empty());
+    let boss = &dungeon.monsters[0];
+    assert!(boss
+        .tiles()
+        .iter()
+        .all(|(x, y)| !dungeon.is_out_of_bounds(*x, *y)));
+}
+
+#[test]
+fn bosses_settle_for_the_farthest_open_patch() {
+    let mut dungeon = empty_dungeon();
+    dungeon.width = 6;
+    dungeon.height = 6;
+    dungeon.player.x = 0;
+    dungeon.player.y = 0;
+    let open = [(1, 0), (2, 0), (1, 1), (2, 1), (1, 2), (2, 2)];
+    for (x, y) in (0..6).flat_map(|y| (0..6).map(move |x| (x, y))) {
+        if (x, y) != (0, 0) && !open.contains(&(x, y)) {
+            dungeon.obstacles.push(obstacle(ObstacleKind::WallA, x, y));
+        }
+    }
+    // Neither patch is half the floor away, so the boss takes the farther one
+    dungeon.spawn_boss(MonsterKind::YetiKing);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (1, 1));
+
+    // With nowhere to stand there's no boss, just the exit key
+    dungeon.monsters.clear();
+    dungeon.obstacles.push(obstacle(ObstacleKind::WallA, 2, 1));
+    dungeon.spawn_boss(MonsterKind::YetiKing);
+    assert!(dungeon.monsters.is_empty());
+    assert_eq!(dungeon.exit_key, Some((2, 2)));
+}
+
+#[test]
+fn boss_floors_always_have_a_way_out() {
+    for seed in 0..50 {
+        let mut dungeon = Dungeon::new(1, seed, DungeonStats::new(), PlayerAchievements::empty());
+        while !dungeon.is_boss_floor() {
+            dungeon.next_floor();
+        }
+        dungeon.populate_floor(log);
+        let has_boss = dungeon.monsters.iter().any(|m| m.kind.is_boss());
+        assert!(has_boss != dungeon.exit_key.is_some(), "seed {seed}");
+    }
+}
+
+#[test]
+fn bosses_take_up_more_than_one_tile() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::YetiKing, 4, 4));
+    assert!(dungeon.is_monster(5, 5));
+    assert!(!dungeon.is_monster(6, 5));
+
+    // Hitting any tile hurts the boss, but never stuns it
+    dungeon.player.x = 3;
+    dungeon.player.y = 5;
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.player.x, 3);
+    assert_eq!(dungeon.monsters[0].health, dungeon.monsters[0].max_health - 1);
+    assert_eq!(dungeon.monsters[0].stun_dur, 0);
+}
+
+#[test]
+fn bosses_telegraph_their_attacks() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::YetiKing, 4, 4));
+    dungeon.player.x = 3;
+    dungeon.player.y = 3;
+
+    // Winds up without moving the turn before it attacks, marking where it will hit
+    dungeon.turn = Monster::BOSS_ATTACK_EVERY - 2;
+    dungeon.move_monsters(log);
+    assert_eq!((dungeon.monsters[0].x, dungeon.monsters[0].y), (4, 4));
+    assert!(dungeon.danger_tiles.iter().any(|t| (t.x, t.y) == (3, 3)));
+
+    // Then slams every tile around it
+    dungeon.turn += 1;
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.player.health, dungeon.player.max_health - 2);
+    assert!(dungeon.danger_tiles.is_empty());
+
+    // Below half health, it sends shockwaves down its rows and columns instead
+    dungeon.monsters[0].health = 5;
+    dungeon.turn = Monster::BOSS_ATTACK_EVERY * 2 - 2;
+    dungeon.move_monsters(log);
+    assert!(!dungeon.danger_tiles.iter().any(|t| (t.x, t.y) == (3, 3)));
+    assert!(dungeon.danger_tiles.iter().any(|t| (t.x, t.y) == (4, 0)));
+}
+
+#[test]
+fn defeated_bosses_drop_the_exit_key_and_a_chest() {
+    let mut dungeon = empty_dungeon();
+    let mut boss = monster(MonsterKind::YetiKing, 4, 4);
+    boss.health = 1;
+    dungeon.monsters.push(boss);
+    dungeon.player.x = 3;
+    dungeon.player.y = 4;
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert_eq!(dungeon.monsters[0].health, 0);
+
+    let boss = &dungeon.monsters[0];
+    let exit_key = dungeon.exit_key.unwrap();
+    assert!(boss.occupies(exit_key.0, exit_key.1));
+    assert_ne!(exit_key, (4, 4));
+    let chest = dungeon
+        .treasures
+        .iter()
+        .find(|t| t.kind == TreasureKind::Chest)
+        .unwrap();
+    assert!(boss.occupies(chest.x, chest.y));
+    assert_ne!((chest.x, chest.y), exit_key);
+}
+
+#[test]
+fn bosses_burned_to_death_still_drop_the_exit_key() {
+    let mut dungeon = empty_dungeon();
+    let mut boss = monster(MonsterKind::YetiKing, 4, 4);
+    boss.health = 1;
+    boss.effects.add(StatusEffectKind::Burn);
+    dungeon.monsters.push(boss);
+    dungeon.move_monsters(log);
+    assert_eq!(dungeon.monsters[0].health, 0);
+
+    // The player gets the credit, the drops and the clear-floor reward, as if they'd hit it
+    let defeated = DungeonStatKind::Defeated(MonsterKind::YetiKing);
+    assert_eq!(dungeon.stats.get(defeated), 1);
+    let exit_key = dungeon.exit_key.unwrap();
+    assert!(dungeon.monsters[0].occupies(exit_key.0, exit_key.1));
+    assert!(dungeon.treasures.iter().any(|t| t.kind == TreasureKind::Chest));
+    assert!(dungeon.treasures.iter().any(|t| (t.x, t.y) == (4, 4)));
+}
+
+#[test]
+fn exit_key_reveals_stairs() {
+    let mut dungeon = empty_dungeon();
+    dungeon.exit_key = Some((2, 1));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert!(dungeon.exit_key.is_none());
+    let (ex, ey) = dungeon.exit.expect("Exit should be revealed");
+    let min_distance = (dungeon.width.min(dungeon.height) / 2) as i32;
+    assert!((ex - 2).abs() + (ey - 1).abs() >= min_distance);
+    assert!(!dungeon.is_out_of_bounds(ex, ey));
+}
+
+#[test]
+fn exit_key_pickups_are_recorded() {
+    let mut dungeon = empty_dungeon();
+    dungeon.exit_key = Some((2, 1));
+    dungeon.take_turn(Direction::Right, log).unwrap();
+    let (ex, ey) = dungeon.exit.unwrap();
+    assert_eq!(
+        dungeon.events,
+        vec![
+            TurnEvent::ExitKeyFound { player: 0, x: 2, y: 1 },
+            TurnEvent::ExitAppeared { x: ex, y: ey },
+        ]
+    );
+}
+
+#[test]
+fn stairs_lead_to_next_floor() {
+    let mut dungeon = empty_dungeon();
+    dungeon.exit = Some((2, 1));
+    dungeon
+        .monsters
+        .push(monster(MonsterKind::GreenGoblin, 5, 5));
+    dungeon
+        .treasures
+        .push(treasure(TreasureKind::Gold, 1, 6, 6));
+    assert!(dungeon.move_player(Direction::Right, log));
+    assert!(dungeon.is_exit(dungeon.player.x, dungeon.player.y));
+
+    dungeon.next_floor();
+    assert_eq!(dungeon.floor, 1);
+    assert_eq!(dungeon.turn, 0);
+    assert!(dungeon.exit.is_none());
+    assert!(dungeon.monsters.is_empty());
+    assert!(dungeon.treasures.is_empty());
+    assert_eq!(dungeon.stats.get(DungeonStatKind::FloorsCleared), 1);
+
+    dungeon.populate_floor(log);
+    assert!(!dungeon.monsters.is_empty());
+    assert!(dungeon.exit_key.is_some());
+}
+
+#[test]
+fn same_seed_generates_same_floor() {
+    let mut a = empty_dungeon();
+    let mut b = empty_dungeon();
+    for _ in 0..3 {
+        a.next_floor();
+        a.populate_floor(log);
+        b.next_floor();
+        b.populate_floor(log);
+    }
+    assert_eq!(a.try_to_vec().unwrap(), b.try_to_vec().unwrap());
+}
+
+#[test]
+fn same_seed_gives_same_monster_moves() {
+    let mut a = empty_dungeon();
+    for _ in 0..4 {
+        a.next_floor();
+    }
+    a.populate_floor(log);
+    let mut b = a.clone();
+    for direction in [Direction::Right, Direction::Down, Direction::Left] {
+        a.move_player(direction, log);
+        a.move_monsters(log);
+        b.move_player(direction, log);
+        b.move_monsters(log);
+    }
+    assert_eq!(a.try_to_vec().unwrap(), b.try_to_vec().unwrap());
+}
+
+// Players "a" and "b" side by side in an empty room
+fn multiplayer_dungeon() -> MultiplayerDungeon {
+    let mut dungeon = MultiplayerDungeon {
+        owner: "a".to_string(),
+        crawl_id: 1,
+        theme: DungeonThemeKind::Castle,
+        floor: 0,
+        round: 0,
+        turn: 0,
+        width: 8,
+        height: 8,
+        monsters: vec![],
+        treasures: vec![],
+        obstacles: vec![],
+        exit_key: None,
+        exit: None,
+        player: PlayerState::new(),
+        seed: 42,
+        rng: Rng::new(42),
+        danger_tiles: vec![],
+        explored: BTreeSet::new(),
+        events: vec![],
+    };
+    for (user_id, x) in [("a", 1), ("b", 2)] {
+        dungeon.player.players.insert(
+            user_id.to_string(),
+            PlayerContext {
+                player: Player {
+                    x,
+                    y: 1,
+                    health: 10,
+                    max_health: 10,
+                    strength: 1,
+                    gold: 0,
+                    direction: Direction::Down,
+                    inventory: vec![],
+                    effects: StatusEffects::new(),
+                },
+                stats: DungeonStats::new(),
+                total_stats: DungeonStats::new(),
+                unlocked: PlayerAchievements::empty(),
+                all_unlocked: PlayerAchievements::empty(),
+                next_round: 0,
+            },
+        );
+    }
+    dungeon
+}
+
+#[test]
+fn multiplayer_players_block_each_other() {
+    let mut dungeon = multiplayer_dungeon();
+    assert!(dungeon.move_player("a", Direction::Right, log).is_err());
+    assert!(dungeon.move_player("a", Direction::Down, log).is_ok());
+
+    // Each player moves once per round
+    assert!(dungeon.move_player("a", Direction::Down, log).is_err());
+    assert!(!dungeon.did_all_players_move());
+    assert!(dungeon.move_player("b", Direction::Left, log).is_ok());
+    assert!(dungeon.did_all_players_move());
+}
+
+#[test]
+fn multiplayer_moves_are_checked_against_the_round() {
+    let mut dungeon = multiplayer_dungeon();
+    assert_eq!(dungeon.check_command("a", 0, 0), Ok(()));
+    assert!(dungeon.move_player("a", Direction::Down, log).is_ok());
+
+    // A player's second move in a round is a duplicate, while the other player can still move
+    assert_eq!(
+        dungeon.check_command("a", 0, 0),
+        Err(StaleCommand::Duplicate { received: 0 })
+    );
+    assert_eq!(dungeon.check_command("b", 0, 0), Ok(()));
+    assert!(dungeon.move_player("b", Direction::Down, log).is_ok());
+    dungeon.round += 1;
+    assert_eq!(
+        dungeon.check_command("b", 0, 0),
+        Err(StaleCommand::Duplicate { received: 0 })
+    );
+    assert!(dungeon.check_command("b", 0, 2).is_err());
+    assert_eq!(
+        dungeon.check_command("b", 1, 1),
+        Err(StaleCommand::WrongFloor {
+            expected: 0,
+            received: 1,
+        })
+    );
+    assert_eq!(dungeon.check_command("b", 0, 1), Ok(()));
+}
+
+#[test]
+fn multiplayer_views_only_share_the_crawl() {
+    let mut dungeon = multiplayer_dungeon();
+    for ctx in dungeon.player.players.values_mut() {
+        ctx.total_stats.increment(DungeonStatKind::GoldCollected, 10);
+    }
+    let view = MultiplayerDungeonView::new(&dungeon, "a");
+    let bytes = view.encode().unwrap();
+    assert!(MultiplayerDungeon::decode(&bytes).is_err());
+
+    let view = MultiplayerDungeon::from(MultiplayerDungeonView::decode(&bytes).unwrap());
+    assert_eq!(view.seed, 0);
+    // Everyone still sees where the others are and how their run is going
+    let (a, b) = (view.player.get("a").unwrap(), view.player.get("b").unwrap());
+    assert_eq!(a.total_stats, dungeon.player.get("a").unwrap().total_stats);
+    assert_eq!(b.total_stats, DungeonStats::new());
+    assert_eq!((b.player.x, b.player.y), (2, 1));
+    assert_eq!(b.stats, dungeon.player.get("b").unwrap().stats);
+}
+
+#[test]
+fn multiplayer_views_only_show_what_the_party_has_seen() {
+    let mut dungeon = multiplayer_dungeon();
+    for y in 0..6 {
+        dungeon.obstacles.push(obstacle(ObstacleKind::WallA, 3, y));
+    }
+    dungeon.exit_key = Some((5, 1));
+    dungeon.treasures.push(treasure(TreasureKind::Gold, 1, 5, 5));
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 6, 2));
+    dungeon.explore();
+    let view = MultiplayerDungeonView::new(&dungeon, "a");
+    assert_eq!(view.exit_key, None);
+    assert!(view.treasures.is_empty());
+    assert!(view.monsters.is_empty());
+    assert!(view.obstacles.iter().all(|obstacle| obstacle.x == 3));
+
+    // Whatever one player sees, everyone sees
+    let set_b = |dungeon: &mut MultiplayerDungeon, x, y| {
+        let moved = dungeon.player.modify_player("b", |ctx| (ctx.player.x, ctx.player.y) = (x, y));
+        assert!(moved.is_ok());
+    };
+    set_b(&mut dungeon, 5, 4);
+    dungeon.explore();
+    let view = MultiplayerDungeonView::new(&dungeon, "a");
+    assert_eq!(view.exit_key, Some((5, 1)));
+    assert_eq!(view.treasures.len(), 1);
+    assert_eq!(view.monsters.len(), 1);
+
+    // Explored tiles are remembered, but monsters are only shown while someone can see them
+    set_b(&mut dungeon, 2, 1);
+    let view = MultiplayerDungeonView::new(&dungeon, "a");
+    assert_eq!(view.exit_key, Some((5, 1)));
+    assert!(view.monsters.is_empty());
+    assert!(dungeon.next_floor().is_ok());
+    assert!(dungeon.explored.is_empty());
+}
+#[test]
+fn daily_dash_floors_match_for_every_player() {
+    let day = DungeonMode::day(1_700_000_000);
+    let mut a = Dungeon::daily(1, day, PlayerAchievements::empty());
+    let mut b = Dungeon::daily(2, day, PlayerAchievements::empty());
+    a.populate_floor(log);
+    b.populate_floor(log);
+    assert_eq!(a.seed, b.seed);
+    assert_eq!((a.player.x, a.player.y), (b.player.x, b.player.y));
+
+    // Players wander differently but the next floor is the same
+    let _ = a.take_turn(Direction::Right, log);
+    let _ = a.take_turn(Direction::Down, log);
+    let _ = b.take_turn(Direction::Left, log);
+    for dungeon in [&mut a, &mut b] {
+        dungeon.next_floor();
+        dungeon.populate_floor(log);
+    }
+    assert_eq!((a.player.x, a.player.y), (b.player.x, b.player.y));
+    assert_eq!(a.theme, b.theme);
+    assert_eq!(a.obstacles, b.obstacles);
+    assert_eq!(a.monsters, b.monsters);
+    assert_eq!(a.treasures, b.treasures);
+    assert_eq!(a.exit_key, b.exit_key);
+
+    // Tomorrow brings a different dungeon
+    assert_ne!(
+        Dungeon::daily(1, day + 1, PlayerAchievements::empty()).seed,
+        a.seed
+    );
+}
+
+// Random-walks a crawl (using items now and then) until the player dies, recording every successful action
+fn play_crawl(seed: u32) -> (Dungeon, CrawlReplay) {
+    play_crawl_with_upgrades(seed, &PlayerUpgrades::new())
+}
+
+fn play_crawl_with_upgrades(seed: u32, upgrades: &PlayerUpgrades) -> (Dungeon, CrawlReplay) {
+    let mut dungeon = Dungeon::new(7, seed, DungeonStats::new(), PlayerAchievements::empty());
+    upgrades.apply(&mut dungeon);
+    dungeon.populate_floor(log);
+    let mut replay = CrawlReplay::new(&dungeon, upgrades);
+    let mut rng = Rng::new(seed);
+    for _ in 0..100_000 {
+        if dungeon.player.health == 0 {
+            break;
+        }
+        let action = if dungeon.is_exit(dungeon.player.x, dungeon.player.y) {
+            CrawlAction::NextFloor
+        } else if !dungeon.player.inventory.is_empty() && rng.one_in(8) {
+            CrawlAction::UseItem(0)
+        } else {
+            CrawlAction::Move(Direction::ALL[rng.index(4)])
+        };
+        if action.apply(&mut dungeon, log) {
+            replay.actions.push(action);
+        }
+    }
+    (dungeon, replay)
+}
+
+#[test]
+fn replay_recreates_crawl() {
+    let (dungeon, replay) = play_crawl(1234);
+    assert_eq!(replay.simulate(replay.actions.len(), log), dungeon);
+
+    // This crawl starts with a reroll scroll and uses it right away
+    let mut upgrades = PlayerUpgrades::new();
+    upgrades.levels.insert(format!("{:?}", UpgradeKind::FloorReroll), 1);
+    let (dungeon, replay) = play_crawl_with_upgrades(8, &upgrades);
+    assert!(replay
+        .actions
+        .iter()
+        .any(|action| matches!(action, CrawlAction::UseItem(_))));
+    assert_eq!(replay.simulate(replay.actions.len(), log), dungeon);
+}
+
+#[test]
+fn verification_accepts_honest_scores() {
+    let (dungeon, replay) = play_crawl(99);
+    assert_eq!(dungeon.player.health, 0);
+    let mut leaderboard = Leaderboard::new();
+    for kind in LeaderboardKind::ALL {
+        leaderboard.update(dungeon.crawl_id, *kind, "p1", kind.score(&dungeon.stats));
+    }
+    assert!(replay.verify(&leaderboard, log).is_empty());
+}
+
+#[test]
+fn verification_flags_tampered_scores() {
+    let (dungeon, replay) = play_crawl(99);
+    let mut leaderboard = Leaderboard::new();
+    let gold = LeaderboardKind::MostGold.score(&dungeon.stats);
+    leaderboard.update(
+        dungeon.crawl_id,
+        LeaderboardKind::MostGold,
+        "p1",
+        gold + 500,
+    );
+    let mismatches = replay.verify(&leaderboard, log);
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].kind, LeaderboardKind::MostGold);
+    assert_eq!(mismatches[0].replayed, Some(gold));
+
+    // Entries for crawls that never ended are always rejected
+    let mut unfinished = replay.clone();
+    unfinished.actions.truncate(1);
+    assert_eq!(unfinished.verify(&leaderboard, log).len(), 1);
+    assert_eq!(unfinished.verify(&leaderboard, log)[0].replayed, None);
+}
+
+#[test]
+fn legacy_files_are_read_as_v1() {
+    let mut stats = DungeonStats::new();
+    stats.increment(DungeonStatKind::GoldCollected, 42);
+    let legacy = stats.try_to_vec().unwrap();
+    assert_eq!(Versioned::<DungeonStats>::version(&legacy), None);
+    assert_eq!(DungeonStats::decode(&legacy).unwrap(), stats);
+
+    let file = stats.encode().unwrap();
+    assert_eq!(Versioned::<DungeonStats>::version(&file), Some(1));
+    assert_eq!(DungeonStats::decode(&file).unwrap(), stats);
+}
+
+#[test]
+fn older_layouts_are_migrated() {
+    #[derive(BorshSerialize, BorshDeserialize)]
+    struct StatsV1 {
+        gold: u32,
+    }
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    struct StatsV2 {
+        gold: u32,
+        kills: u32,
+    }
+    impl Schema for StatsV2 {
+        const VERSION: u16 = 2;
+        fn migrate(version: u16, data: &[u8]) -> std::io::Result<Self> {
+            match version {
+                1 => StatsV1::try_from_slice(data).map(|v1| Self {
+                    gold: v1.gold,
+                    kills: 0,
+                }),
+                _ => Self::try_from_slice(data),
+            }
+        }
+    }
+    let legacy = StatsV1 { gold: 7 }.try_to_vec().unwrap();
+    let stats = StatsV2::decode(&legacy).unwrap();
+    assert_eq!(stats, StatsV2 { gold: 7, kills: 0 });
+    assert_eq!(StatsV2::decode(&stats.encode().unwrap()).unwrap(), stats);
+}
+
+#[test]
+fn unsupported_versions_are_rejected() {
+    // Files from a newer build aren't guessed at
+    let mut file = Versioned::<DungeonStats>::MAGIC.to_vec();
+    file.extend(99u16.to_le_bytes());
+    file.extend(DungeonStats::new().try_to_vec().unwrap());
+    assert!(DungeonStats::decode(&file).is_err());
+}
+
+#[test]
+fn unseeded_dungeons_carry_on_unrecorded() {
+    let mut dungeon = empty_dungeon();
+    dungeon.monsters.push(monster(MonsterKind::Zombie, 5, 5));
+    dungeon.populate_floor(log);
+
+    // Written field by field in the shipped layout, before seeds, inventories and effects
+    let p = &dungeon.player;
+    let player = (p.x, p.y, p.health, p.max_health, p.strength, p.gold, p.direction);
+    let monsters: Vec<_> = dungeon
+        .monsters
+        .iter()
+        .map(|m| (m.x, m.y, m.health, m.max_health, m.strength, m.direction, m.kind, m.stun_dur))
+        .collect();
+    let (turn, width, height) = (dungeon.turn, dungeon.width, dungeon.height);
+    let head = (dungeon.crawl_id, dungeon.theme, dungeon.floor, turn, width, height);
+    let floor = (player, monsters, &dungeon.treasures, &dungeon.obstacles);
+    let floor = (floor, dungeon.exit_key, dungeon.exit);
+    let stats = (&dungeon.stats, &dungeon.total_stats, &dungeon.unlocked, &dungeon.all_unlocked);
+    let v1 = (head, floor, stats).try_to_vec().unwrap();
+    assert_eq!(Versioned::<Dungeon>::version(&v1), None);
+
+    let mut migrated = Dungeon::decode(&v1).unwrap();
+    assert!(!migrated.replayable);
+    assert_eq!(
+        Dungeon {
+            seed: dungeon.seed,
+            rng: dungeon.rng.clone(),
+            replayable: true,
+            ..migrated.clone()
+        },
+        dungeon
+    );
+    // The rest of the crawl is still played out
+    migrated.exit = Some((2, 1));
+    assert!(migrated.take_turn(Direction::Right, log).is_ok());
+    migrated.next_floor();
+    migrated.populate_floor(log);
+    assert_eq!(migrated.floor, 1);
+}
+
+#[test]
+fn unseeded_multiplayer_dungeons_get_a_fresh_seed() {
+    // Written field by field in the shipped layout, before seeds, inventories and effects
+    let player = (3i32, 4i32, 7u32, 10u32, 1u32, 5u32, Direction::Left);
+    let ctx = (player, DungeonStats::new(), DungeonStats::new());
+    let ctx = (ctx, PlayerAchievements::empty(), PlayerAchievements::empty(), 2u32);
+    let players = BTreeMap::from([("a".to_string(), ctx)]);
+    let monster = (5i32, 5i32, 3u32, 3u32, 3u32, Direction::Up, MonsterKind::Zombie, 0u32);
+    let head = ("a".to_string(), 9u32, DungeonThemeKind::Castle, 1u32, 2u32, 6u32, 8u32, 8u32);
+    let floor = (vec![monster], Vec::<Treasure>::new(), vec![obstacle(ObstacleKind::WallA, 0, 0)]);
+    let rest = (Some((6, 6)), None::<(i32, i32)>, players);
+    let v1 = (head, floor, rest).try_to_vec().unwrap();
+    assert_eq!(Versioned::<MultiplayerDungeon>::version(&v1), None);
+
+    let migrated = MultiplayerDungeon::decode(&v1).unwrap();
+    assert_eq!((migrated.crawl_id, migrated.round, migrated.turn), (9, 2, 6));
+    assert_eq!(migrated.seed, Rng::new(9).next_u32());
+    assert_eq!(migrated.monsters[0].kind, MonsterKind::Zombie);
+    assert_eq!(migrated.obstacles.len(), 1);
+    assert_eq!(migrated.exit_key, Some((6, 6)));
+    let ctx = migrated.player.get("a").unwrap();
+    assert_eq!((ctx.player.x, ctx.player.y, ctx.player.gold), (3, 4, 5));
+    assert!(ctx.player.inventory.is_empty());
+    assert_eq!(ctx.next_round, 2);
+}
+
+#[test]
+fn essence_buys_upgrades() {
+    let mut stats = DungeonStats::new();
+    stats.increment(DungeonStatKind::FloorsCleared, 30);
+    stats.increment(DungeonStatKind::Defeated(MonsterKind::BlueBlob), 20);
+    let mut upgrades = PlayerUpgrades::new();
+    assert_eq!(upgrades.essence(&stats), 50);
+
+    assert_eq!(upgrades.unlock(UpgradeKind::StartingHealth, &stats), Ok(20));
+    assert_eq!(upgrades.level(UpgradeKind::StartingHealth), 1);
+    assert_eq!(upgrades.essence(&stats), 30);
+    // The next level costs more
+    assert!(upgrades.unlock(UpgradeKind::StartingHealth, &stats).is_err());
+    assert!(upgrades.unlock(UpgradeKind::FloorReroll, &stats).is_err());
+    assert_eq!(upgrades.unlock(UpgradeKind::StartingGold, &stats), Ok(15));
+    assert_eq!(upgrades.essence(&stats), 15);
+
+    // Maxed out upgrades can't be raised
+    stats.increment(DungeonStatKind::FloorsCleared, 100);
+    assert_eq!(upgrades.unlock(UpgradeKind::FloorReroll, &stats), Ok(50));
+    assert!(upgrades.unlock(UpgradeKind::FloorReroll, &stats).is_err());
+}
+
+#[test]
+fn upgrades_apply_to_new_crawls_and_their_replays() {
+    let mut upgrades = PlayerUpgrades::new();
+    upgrades.levels.insert("StartingHealth".to_string(), 2);
+    upgrades.levels.insert("StartingGold".to_string(), 1);
+    upgrades.levels.insert("FloorReroll".to_string(), 1);
+
+    let mut dungeon = Dungeon::new(7, 1234, DungeonStats::new(), PlayerAchievements::empty());
+    upgrades.apply(&mut dungeon);
+    dungeon.populate_floor(log);
+    assert_eq!(dungeon.player.max_health, 14);
+    assert_eq!(dungeon.player.health, 14);
+    assert_eq!(dungeon.player.gold, 10);
+    assert_eq!(dungeon.player.inventory, vec![ItemKind::RerollScroll]);
+
+    // Reroll the first floor, then wander around
+    let mut replay = CrawlReplay::new(&dungeon, &upgrades);
+    let mut rng = Rng::new(1234);
+    let mut actions = vec![CrawlAction::UseItem(0)];
+    actions.extend((0..50).map(|_| CrawlAction::Move(Direction::ALL[rng.index(4)])));
+    for action in actions {
+        if action.apply(&mut dungeon, log) {
+            replay.actions.push(action);
+        }
+    }
+    assert!(matches!(replay.actions[0], CrawlAction::UseItem(0)));
+    assert_eq!(replay.simulate(replay.actions.len(), log), dungeon);
+}
+
+#[test]
+fn crawl_bots_play_replayable_crawls() {
+    let mut report = BalanceReport::new();
+    for name in CRAWL_BOTS {
+        for seed in 1..=20 {
+            let mut dungeon =
+                Dungeon::new(7, seed, DungeonStats::new(), PlayerAchievements::empty());
+            dungeon.populate_floor(log);
+            let mut replay = CrawlReplay::new(&dungeon, &PlayerUpgrades::new());
+            let mut bot = crawl_bot_by_name(name, seed).unwrap();
+            replay.actions = bot.play(&mut dungeon, 5_000, log);
+            assert_eq!(replay.simulate(replay.actions.len(), log), dungeon);
+            report.record(&dungeon);
+        }
+    }
+    assert!(crawl_bot_by_name("cheater", 1).is_none());
+
+    // Every crawl is counted once, and the pathing bot gets past the first floor
+    assert_eq!(report.floors.len(), 40);
+    assert_eq!(report.deaths.values().sum::<u32>(), 40);
+    assert!(report.floors[20..].iter().any(|&floor| floor > 1));
+}
@@ -0,0 +1,20 @@
+use super::*;
+
+// A short message shown over the screen, like why the player's last command failed
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub message: String,
+    pub shown_at: usize,
+}
+impl Toast {
+    pub const DURATION: usize = 120;
+    pub fn new(message: &str, shown_at: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            shown_at,
+        }
+    }
+    pub fn is_expired(&self, now: usize) -> bool {
+        now.saturating_sub(self.shown_at) >= Self::DURATION
+    }
+}
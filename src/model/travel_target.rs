@@ -0,0 +1,103 @@
+use super::*;
+
+// Where the player is auto-walking to. The client asks for one step at a time and sends it as a normal move.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TravelTarget {
+    Tile(i32, i32),
+    ExitKey,
+    Exit,
+    // Picks up treasure after treasure until none are left
+    NearestTreasure,
+    // Heads for the closest tile the player hasn't seen yet
+    Unexplored,
+}
+impl TravelTarget {
+    // Auto-walking stops once a living monster is this many steps away
+    pub const ALERT_DISTANCE: i32 = 3;
+
+    // The stairs once they're revealed, otherwise the exit key, or else more of the floor until the key turns up
+    pub fn objective(dungeon: &Dungeon) -> Self {
+        if dungeon.exit.is_some() {
+            Self::Exit
+        } else if dungeon.exit_key.is_some() {
+            Self::ExitKey
+        } else {
+            Self::Unexplored
+        }
+    }
+    // The tile being walked to, if there still is one
+    pub fn position(&self, dungeon: &Dungeon) -> Option<(i32, i32)> {
+        match *self {
+            Self::Tile(x, y) => {
+                let paths = Self::paths(dungeon);
+                (paths.is_walkable(x, y) && !dungeon.is_monster(x, y)).then_some((x, y))
+            }
+            Self::ExitKey => dungeon.exit_key,
+            Self::Exit => dungeon.exit,
+            Self::NearestTreasure => {
+                let paths = Self::paths(dungeon);
+                let distances = paths.distances((dungeon.player.x, dungeon.player.y));
+                dungeon
+                    .treasures
+                    .iter()
+                    .filter_map(|t| Some((paths.distance(&distances, t.x, t.y)?, (t.x, t.y))))
+                    .min()
+                    .map(|(_, position)| position)
+            }
+            Self::Unexplored => {
+                let paths = Self::paths(dungeon);
+                let distances = paths.distances((dungeon.player.x, dungeon.player.y));
+                (0..dungeon.height as i32)
+                    .flat_map(|y| (0..dungeon.width as i32).map(move |x| (x, y)))
+                    .filter(|position| !dungeon.explored.contains(position))
+                    .filter_map(|(x, y)| Some((paths.distance(&distances, x, y)?, (x, y))))
+                    .min()
+                    .map(|(_, position)| position)
+            }
+        }
+    }
+    // The next move towards the target. None means the walk is over: the player arrived,
+    // there's no safe way there, or a monster is close enough that the player should take over.
+    pub fn next_step(&self, dungeon: &Dungeon) -> Option<Direction> {
+        let player = &dungeon.player;
+        let from = (player.x, player.y);
+        if player.health == 0 || Self::is_monster_near(dungeon) {
+            return None;
+        }
+        let to = self.position(dungeon)?;
+        let step = *Self::paths(dungeon).find_path(from, to)?.first()?;
+        let is_danger = dungeon
+            .danger_tiles
+            .iter()
+            .any(|tile| (tile.x, tile.y) == step);
+        if is_danger {
+            return None;
+        }
+        Direction::between(from, step)
+    }
+    fn is_monster_near(dungeon: &Dungeon) -> bool {
+        let player = &dungeon.player;
+        dungeon.monsters.iter().any(|monster| {
+            monster.health > 0 && monster.distance_to(player.x, player.y) <= Self::ALERT_DISTANCE
+        })
+    }
+    // Walls, monsters and anything that hurts or slides the player are walked around
+    fn paths(dungeon: &Dungeon) -> Pathfinder {
+        let mut paths = Pathfinder::new(dungeon.width, dungeon.height);
+        for obstacle in &dungeon.obstacles {
+            let is_hazard = matches!(
+                obstacle.kind,
+                ObstacleKind::SpikeTrap | ObstacleKind::IceFloor
+            );
+            if obstacle.kind.is_solid() || is_hazard {
+                paths.block(obstacle.x, obstacle.y);
+            }
+        }
+        for monster in dungeon.monsters.iter().filter(|m| m.health > 0) {
+            for (x, y) in monster.tiles() {
+                paths.block(x, y);
+            }
+        }
+        paths
+    }
+}
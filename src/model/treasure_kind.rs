@@ -1,9 +1,11 @@
 use super::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub enum TreasureKind {
     Gold,
     Heal,
     HealthUp,
+    Item(ItemKind),
+    Chest,
 }
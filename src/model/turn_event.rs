@@ -0,0 +1,126 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Something that happened during the last turn. Dungeons keep the last turn's events
+// so clients can show a combat log and animate hits without diffing health.
+// Players and monsters are referred to by their index in the dungeon.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum TurnEvent {
+    PlayerAttacked {
+        player: usize,
+        monster: usize,
+        kind: MonsterKind,
+    },
+    // Melee attacks only. Ranged shots land a turn later as damage from `by`.
+    MonsterAttacked {
+        monster: usize,
+        kind: MonsterKind,
+        player: usize,
+    },
+    // `by` is None for hazards and status effects
+    PlayerDamaged {
+        player: usize,
+        x: i32,
+        y: i32,
+        damage: u32,
+        by: Option<MonsterKind>,
+    },
+    MonsterDamaged {
+        monster: usize,
+        kind: MonsterKind,
+        x: i32,
+        y: i32,
+        damage: u32,
+    },
+    PlayerDied {
+        player: usize,
+        x: i32,
+        y: i32,
+    },
+    MonsterDefeated {
+        monster: usize,
+        kind: MonsterKind,
+        x: i32,
+        y: i32,
+    },
+    TreasureCollected {
+        player: usize,
+        kind: TreasureKind,
+        value: u32,
+        x: i32,
+        y: i32,
+    },
+    ExitKeyFound {
+        player: usize,
+        x: i32,
+        y: i32,
+    },
+    ExitAppeared {
+        x: i32,
+        y: i32,
+    },
+}
+impl TurnEvent {
+    // The tile it happened on, for events that happen somewhere in particular
+    pub fn position(&self) -> Option<(i32, i32)> {
+        match *self {
+            Self::PlayerAttacked { .. } | Self::MonsterAttacked { .. } => None,
+            Self::PlayerDamaged { x, y, .. }
+            | Self::MonsterDamaged { x, y, .. }
+            | Self::PlayerDied { x, y, .. }
+            | Self::MonsterDefeated { x, y, .. }
+            | Self::TreasureCollected { x, y, .. }
+            | Self::ExitKeyFound { x, y, .. }
+            | Self::ExitAppeared { x, y } => Some((x, y)),
+        }
+    }
+    // The index of the monster it's about, if any
+    pub fn monster_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Self::PlayerAttacked { monster, .. }
+            | Self::MonsterAttacked { monster, .. }
+            | Self::MonsterDamaged { monster, .. }
+            | Self::MonsterDefeated { monster, .. } => Some(monster),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for TurnEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PlayerAttacked { player, kind, .. } => {
+                write!(f, "P{} attacks {}", player + 1, kind.abbrev())
+            }
+            Self::MonsterAttacked { kind, player, .. } => {
+                write!(f, "{} attacks P{}", kind.abbrev(), player + 1)
+            }
+            Self::PlayerDamaged {
+                player, damage, by, ..
+            } => match by {
+                Some(kind) => write!(f, "{} hits P{} for {damage}", kind.abbrev(), player + 1),
+                None => write!(f, "P{} takes {damage} damage", player + 1),
+            },
+            Self::MonsterDamaged { kind, damage, .. } => {
+                write!(f, "{} takes {damage} damage", kind.abbrev())
+            }
+            Self::PlayerDied { player, .. } => write!(f, "P{} died", player + 1),
+            Self::MonsterDefeated { kind, .. } => write!(f, "{} defeated", kind.abbrev()),
+            Self::TreasureCollected {
+                player,
+                kind,
+                value,
+                ..
+            } => match kind {
+                TreasureKind::Gold | TreasureKind::Chest => {
+                    write!(f, "P{} got ${value}", player + 1)
+                }
+                TreasureKind::Heal => write!(f, "P{} healed", player + 1),
+                TreasureKind::HealthUp => write!(f, "P{} got a health up", player + 1),
+                TreasureKind::Item(item) => write!(f, "P{} got a {}", player + 1, item.name()),
+            },
+            Self::ExitKeyFound { player, .. } => write!(f, "P{} found the exit key", player + 1),
+            Self::ExitAppeared { .. } => write!(f, "Stairs appeared"),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq,
+)]
+pub enum UpgradeKind {
+    StartingHealth,
+    StartingGold,
+    FloorReroll,
+}
+impl UpgradeKind {
+    pub const ALL: &'static [Self] = &[Self::StartingHealth, Self::StartingGold, Self::FloorReroll];
+    pub const HEALTH_PER_LEVEL: u32 = 2;
+    pub const GOLD_PER_LEVEL: u32 = 10;
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::StartingHealth => "STARTING HP",
+            Self::StartingGold => "STARTING GOLD",
+            Self::FloorReroll => "FLOOR REROLL",
+        }
+    }
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::StartingHealth => "+2 MAX HP PER LEVEL",
+            Self::StartingGold => "+$10 PER LEVEL",
+            Self::FloorReroll => "START WITH A REROLL",
+        }
+    }
+    pub fn max_level(&self) -> u32 {
+        match self {
+            Self::StartingHealth => 5,
+            Self::StartingGold => 5,
+            Self::FloorReroll => 1,
+        }
+    }
+    // Essence needed to go from `level` to `level + 1`
+    pub fn cost(&self, level: u32) -> u32 {
+        let base = match self {
+            Self::StartingHealth => 20,
+            Self::StartingGold => 15,
+            Self::FloorReroll => 50,
+        };
+        base * (level + 1)
+    }
+}
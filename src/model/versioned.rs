@@ -0,0 +1,39 @@
+use super::*;
+
+use std::io::{Read, Result, Write};
+
+// File envelope recording which schema version the data was written with.
+// Legacy files without the envelope are read as v1. Writes borrow the value: `Versioned(&value)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Versioned<T>(pub T);
+impl<T> Versioned<T> {
+    pub const MAGIC: [u8; 4] = *b"DDv\0";
+    // Schema version of an enveloped file (None for legacy files)
+    pub fn version(bytes: &[u8]) -> Option<u16> {
+        let mut rest = bytes.strip_prefix(&Self::MAGIC)?;
+        u16::deserialize(&mut rest).ok()
+    }
+}
+impl<T: Schema> BorshSerialize for Versioned<&T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&Versioned::<T>::MAGIC)?;
+        T::VERSION.serialize(writer)?;
+        self.0.serialize(writer)
+    }
+}
+impl<T: Schema> BorshDeserialize for Versioned<T> {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self> {
+        let (version, data) = match buf.strip_prefix(&Self::MAGIC) {
+            Some(mut rest) => (u16::deserialize(&mut rest)?, rest),
+            None => (1, *buf),
+        };
+        let value = T::migrate(version, data)?;
+        *buf = &[];
+        Ok(Self(value))
+    }
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Self::deserialize(&mut bytes.as_slice())
+    }
+}
@@ -15,11 +15,15 @@ unsafe extern "C" fn exec() -> usize {
     let user_id = os::server::get_user_id();
 
     // Save updated lobby list
-    let dungeon_filepath = paths::player_dungeon(&user_id);
-    if let Err(err) = os::server::write_file(&dungeon_filepath, &[]) {
-        os::server::log!("Could not delete player dungeon: {err:?}");
-        return os::server::CANCEL;
-    };
+    for filepath in [
+        paths::player_dungeon(&user_id),
+        paths::player_dungeon_view(&user_id),
+    ] {
+        if let Err(err) = os::server::write_file(&filepath, &[]) {
+            os::server::log!("Could not delete player dungeon: {err:?}");
+            return os::server::CANCEL;
+        };
+    }
 
     os::server::COMMIT
 }
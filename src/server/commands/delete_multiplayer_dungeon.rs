@@ -1,4 +1,5 @@
 use super::*;
+use std::fmt;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct Command {
@@ -11,6 +12,20 @@ impl Command {
     }
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    NoCrawl,
+    NotInCrawl,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCrawl => write!(f, "That crawl is over"),
+            Self::NotInCrawl => write!(f, "You aren't in that crawl"),
+        }
+    }
+}
+
 #[export_name = "turbo/delete_new_multiplayer_dungeon"]
 unsafe extern "C" fn exec() -> usize {
     // Get player id
@@ -19,22 +34,34 @@ unsafe extern "C" fn exec() -> usize {
     // Get command data
     let cmd = os::server::command!(Command);
 
+    results::commit(
+        &user_id,
+        run(&user_id, cmd).map_err(CommandError::DeleteMultiplayerDungeon),
+    )
+}
+
+fn run(user_id: &str, cmd: Command) -> Result<(), Error> {
     // Load player dungeon
     os::server::log!("Loading the multiplayer dungeon ({})...", cmd.crawl_id);
     let dungeon_filepath = paths::multiplayer_dungeon(cmd.crawl_id);
-    let mut dungeon = os::server::read!(MultiplayerDungeon, &dungeon_filepath);
+    let mut dungeon = os::server::read_else!(Versioned<MultiplayerDungeon>, &dungeon_filepath, {
+        return Err(Error::NoCrawl);
+    })
+    .0;
 
     // If user is owner or 2nd-to-last player, delete the whole dungeon
     if dungeon.owner == user_id
-        || dungeon.player.players.contains_key(&user_id) && dungeon.player.players.len() == 2
+        || dungeon.player.players.contains_key(user_id) && dungeon.player.players.len() == 2
     {
-        // Clear each player's multiplayer dungeon manifest
+        // Clear each player's multiplayer dungeon manifest and view
         for user_id in dungeon.player.players.keys() {
-            let filepath = paths::player_multiplayer_dungeon_manifest(user_id);
-            if let Err(err) = os::server::write_file(&filepath, &[]) {
-                os::server::log!("{err:?}");
-                return os::server::CANCEL;
-            };
+            let filepaths = [
+                paths::player_multiplayer_dungeon_manifest(user_id),
+                paths::multiplayer_dungeon_view(cmd.crawl_id, user_id),
+            ];
+            for filepath in filepaths {
+                os::server::write_file(&filepath, &[]).expect("Could not clear player file.");
+            }
         }
 
         // Delete the dungeon
@@ -43,11 +70,11 @@ unsafe extern "C" fn exec() -> usize {
         // TODO: clear/delete macro
         os::server::write_file(&dungeon_filepath, &[]).expect("Could not save dungeon file.");
 
-        return os::server::COMMIT;
+        return Ok(());
     }
 
     // If user is player, make them leave the dungeon
-    if dungeon.player.players.contains_key(&user_id) {
+    if dungeon.player.players.contains_key(user_id) {
         os::server::emit(
             &format!("multiplayer_dungeon_{}", cmd.crawl_id),
             format!("{:.8} left the party", user_id).as_bytes(),
@@ -55,22 +82,23 @@ unsafe extern "C" fn exec() -> usize {
 
         // Remove player from dungeon
         os::server::log!("Removing {user_id} from dungeon...");
-        dungeon.player.players.remove(&user_id);
+        dungeon.player.players.remove(user_id);
 
-        // Clear this player's multiplayer dungeon manifest
+        // Clear this player's multiplayer dungeon manifest and view
         os::server::log!("Clearing {user_id} manifest...");
-        let filepath = paths::player_multiplayer_dungeon_manifest(&user_id);
-        if let Err(err) = os::server::write_file(&filepath, &[]) {
-            os::server::log!("{err:?}");
-            return os::server::CANCEL;
-        };
+        let filepaths = [
+            paths::player_multiplayer_dungeon_manifest(user_id),
+            paths::multiplayer_dungeon_view(cmd.crawl_id, user_id),
+        ];
+        for filepath in filepaths {
+            os::server::write_file(&filepath, &[]).expect("Could not clear player file.");
+        }
 
         os::server::log!("Saving dungeon...");
-        let dungeon_filepath = paths::multiplayer_dungeon(cmd.crawl_id);
-        os::server::write!(&dungeon_filepath, &dungeon).expect("Could not save dungeon file.");
+        move_player_multiplayer_dungeon::save_dungeon(&dungeon);
 
-        return os::server::COMMIT;
+        return Ok(());
     }
 
-    return os::server::CANCEL;
+    Err(Error::NotInCrawl)
 }
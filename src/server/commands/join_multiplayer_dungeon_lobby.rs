@@ -1,4 +1,5 @@
 use super::*;
+use std::fmt;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct Command {
@@ -13,6 +14,19 @@ impl Command {
     }
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    // The lobby started or was closed before the player got in
+    LobbyNotFound,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LobbyNotFound => write!(f, "That lobby is no longer open"),
+        }
+    }
+}
+
 #[export_name = "turbo/join_multiplayer_dungeon_lobby"]
 unsafe extern "C" fn exec() -> usize {
     // Get player id
@@ -21,6 +35,13 @@ unsafe extern "C" fn exec() -> usize {
     // Get command data
     let cmd = os::server::command!(Command);
 
+    results::commit(
+        &user_id,
+        run(&user_id, cmd).map_err(CommandError::JoinMultiplayerDungeonLobby),
+    )
+}
+
+fn run(user_id: &str, cmd: Command) -> Result<(), Error> {
     // Load the lobby list
     let lobby_list_filepath = paths::multiplayer_dungeon_list();
     let mut lobby_list = os::server::read_or!(
@@ -32,17 +53,14 @@ unsafe extern "C" fn exec() -> usize {
     // Get the requested lobby
     let Some(lobby) = lobby_list.get_mut(&cmd.owner) else {
         os::server::log!("Lobby is not available");
-        return os::server::CANCEL;
+        return Err(Error::LobbyNotFound);
     };
 
     // Add new user to lobby
-    lobby.players.insert(user_id.clone());
+    lobby.players.insert(user_id.to_string());
 
     // Save updated lobby list
-    if let Err(err) = os::server::write!(&lobby_list_filepath, &lobby_list) {
-        os::server::log!("{err:?}");
-        return os::server::CANCEL;
-    };
+    os::server::write!(&lobby_list_filepath, &lobby_list).expect("Could not save lobby list.");
 
-    os::server::COMMIT
+    Ok(())
 }
@@ -0,0 +1,79 @@
+use super::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Command;
+impl Command {
+    pub const NAME: &'static str = "migrate_player_files";
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+// Rewrites the player's files (and the global leaderboard) in the current schema versions
+#[export_name = "turbo/migrate_player_files"]
+unsafe extern "C" fn exec() -> usize {
+    // Get player id
+    let user_id = os::server::get_user_id();
+
+    migrate::<DungeonStats>(&paths::player_dungeon_stats(&user_id));
+    migrate::<PlayerAchievements>(&paths::player_achievements(&user_id));
+    migrate::<Dungeon>(&paths::player_dungeon(&user_id));
+    migrate::<CrawlReplay>(&paths::player_dungeon_replay(&user_id));
+    migrate::<Leaderboard>(&paths::global_leaderboard());
+
+    // Clients only watch views, so crawls without a view in the current layout need one written
+    for daily in [false, true] {
+        let view_filepath = move_player::view_filepath(&user_id, daily);
+        if read::<DungeonView>(&view_filepath).is_none() {
+            let (dungeon_filepath, _) = move_player::crawl_filepaths(&user_id, daily);
+            if let Some(dungeon) = read::<Dungeon>(&dungeon_filepath) {
+                move_player::save_dungeon(&user_id, daily, &dungeon);
+            }
+        }
+    }
+    let manifest = os::server::read_file(&paths::player_multiplayer_dungeon_manifest(&user_id));
+    if let Some(crawl_id) = manifest
+        .ok()
+        .and_then(|bytes| u32::try_from_slice(&bytes).ok())
+    {
+        let view_filepath = paths::multiplayer_dungeon_view(crawl_id, &user_id);
+        if read::<MultiplayerDungeonView>(&view_filepath).is_none() {
+            let dungeon_filepath = paths::multiplayer_dungeon(crawl_id);
+            if let Some(dungeon) = read::<MultiplayerDungeon>(&dungeon_filepath) {
+                move_player_multiplayer_dungeon::save_dungeon(&dungeon);
+            }
+        }
+    }
+
+    os::server::COMMIT
+}
+
+fn read<T: Schema>(filepath: &str) -> Option<T> {
+    let bytes = os::server::read_file(filepath).ok()?;
+    T::decode(&bytes).ok()
+}
+
+fn migrate<T: Schema>(filepath: &str) {
+    let Ok(bytes) = os::server::read_file(filepath) else {
+        return;
+    };
+    if bytes.is_empty() {
+        return;
+    }
+    let version = Versioned::<T>::version(&bytes);
+    if version == Some(T::VERSION) {
+        return;
+    }
+    let value = match T::decode(&bytes) {
+        Ok(value) => value,
+        Err(err) => return os::server::log!("Could not migrate {filepath}: {err:?}"),
+    };
+    match os::server::write!(filepath, &Versioned(&value)) {
+        Ok(_) => os::server::log!(
+            "Migrated {filepath} from v{} to v{}",
+            version.unwrap_or(1),
+            T::VERSION
+        ),
+        Err(err) => os::server::log!("Could not write {filepath}: {err:?}"),
+    }
+}
@@ -23,7 +23,7 @@ unsafe extern "C" fn exec() -> usize {
     // Load player dungeon
     os::server::log!("Loading the dungeon for player {}...", user_id);
     let dungeon_filepath = paths::player_dungeon(&user_id);
-    let mut dungeon = os::server::read!(Dungeon, &dungeon_filepath);
+    let mut dungeon = os::server::read!(Versioned<Dungeon>, &dungeon_filepath).0;
     if cmd.crawl_id != dungeon.crawl_id {
         return os::server::CANCEL;
     }
@@ -47,8 +47,12 @@ unsafe extern "C" fn exec() -> usize {
         // Update the global leaderboard
         os::server::log!("Reading global leaderboard...");
         let leaderboard_filepath = paths::global_leaderboard();
-        let mut leaderboard =
-            os::server::read_or!(Leaderboard, &leaderboard_filepath, Leaderboard::new());
+        let mut leaderboard = os::server::read_or!(
+            Versioned<Leaderboard>,
+            &leaderboard_filepath,
+            Versioned(Leaderboard::new())
+        )
+        .0;
         os::server::log!("Updating global leaderboard...");
         if let Some(entry) = leaderboard.update(
             dungeon.crawl_id,
@@ -87,13 +91,13 @@ unsafe extern "C" fn exec() -> usize {
             os::server::alert!("Player {:.8} reached floor {:?}!", user_id, entry.score);
         }
         os::server::log!("Saving global leaderboard...");
-        os::server::write!(&leaderboard_filepath, &leaderboard)
+        os::server::write!(&leaderboard_filepath, &Versioned(&leaderboard))
             .expect("Could not write leaderboard");
 
         // Update player stats
         os::server::log!("Saving player stats...");
         let player_stats_filepath = paths::player_dungeon_stats(&user_id);
-        os::server::write!(&player_stats_filepath, &dungeon.total_stats)
+        os::server::write!(&player_stats_filepath, &Versioned(&dungeon.total_stats))
             .expect("Could not write player stats");
 
         // Unlock achievements
@@ -113,13 +117,16 @@ unsafe extern "C" fn exec() -> usize {
         );
         os::server::log!("Saving player achievements...");
         let player_achievements_filepath = paths::player_achievements(&user_id);
-        os::server::write!(&player_achievements_filepath, &dungeon.all_unlocked)
-            .expect("Could not write player achievements");
+        os::server::write!(
+            &player_achievements_filepath,
+            &Versioned(&dungeon.all_unlocked)
+        )
+        .expect("Could not write player achievements");
     }
 
     // Save the dungeon
     os::server::log!("Saving the dungeon...");
-    os::server::write!(&dungeon_filepath, &dungeon).expect("Could not write player dungeon");
+    move_player::save_dungeon(&user_id, false, &dungeon);
 
     // Commit the command result
     os::server::COMMIT
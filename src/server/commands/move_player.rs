@@ -1,14 +1,45 @@
 use super::*;
+use std::fmt;
 
 pub const COMMAND: &'static str = "move_player";
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct MovePlayerCommand {
     pub direction: Direction,
+    pub daily: bool,
+    // The crawl and action count the client last saw, so retries and double-sends can't move twice
+    pub crawl_id: u32,
+    pub actions: u32,
 }
 
-pub fn new(direction: Direction) -> MovePlayerCommand {
-    MovePlayerCommand { direction }
+pub fn new(dungeon: &Dungeon, direction: Direction) -> MovePlayerCommand {
+    MovePlayerCommand {
+        direction,
+        daily: dungeon.mode.is_daily(),
+        crawl_id: dungeon.crawl_id,
+        actions: dungeon.actions,
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    NoCrawl,
+    Stale(StaleCommand),
+    GameOver,
+    // The move broke a rule, like walking into a wall
+    Blocked(String),
+    NoReplay,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCrawl => write!(f, "No crawl in progress"),
+            Self::Stale(err) => err.fmt(f),
+            Self::GameOver => write!(f, "Game over"),
+            Self::Blocked(reason) => write!(f, "{reason}"),
+            Self::NoReplay => write!(f, "This crawl can't be recorded"),
+        }
+    }
 }
 
 #[export_name = "turbo/move_player"]
@@ -19,55 +50,150 @@ unsafe extern "C" fn exec() -> usize {
     // Get command data
     let cmd = os::server::command!(MovePlayerCommand);
 
+    results::commit(
+        &user_id,
+        run(&user_id, cmd).map_err(CommandError::MovePlayer),
+    )
+}
+
+fn run(user_id: &str, cmd: MovePlayerCommand) -> Result<(), Error> {
     // Load player dungeon
     os::server::log!("Loading the dungeon for player {}...", user_id);
-    let dungeon_filepath = paths::player_dungeon(&user_id);
-    let mut dungeon = os::server::read_else!(Dungeon, &dungeon_filepath, {
-        // Reset dungeon file
-        os::server::write_file(&dungeon_filepath, &[]).expect("Could not save dungeon file.");
-        return os::server::COMMIT;
-    });
+    let (dungeon_filepath, _) = crawl_filepaths(user_id, cmd.daily);
+    let mut dungeon = os::server::read_else!(Versioned<Dungeon>, &dungeon_filepath, {
+        return Err(Error::NoCrawl);
+    })
+    .0;
+
+    // Cancel command if it was sent for another crawl or was already applied
+    dungeon
+        .check_command(cmd.crawl_id, cmd.actions)
+        .map_err(Error::Stale)?;
 
     // Cancel command if player has already won or lost
     os::server::log!("Checking game over conditions...");
     if dungeon.player.health == 0 {
         os::server::log!("P1 has died. Game over.");
-        return os::server::CANCEL;
+        return Err(Error::GameOver);
     }
+    let replay = load_replay(user_id, cmd.daily, &dungeon).ok_or(Error::NoReplay)?;
 
-    // Move player
+    // Move player, then monsters if player has not reached the exit
     os::server::log!("Moving player...");
-    if !dungeon.move_player(cmd.direction, os::server::log) {
-        return os::server::CANCEL;
+    dungeon
+        .take_turn(cmd.direction, os::server::log)
+        .map_err(|reason| Error::Blocked(reason.to_string()))?;
+
+    finish_turn(
+        user_id,
+        cmd.daily,
+        dungeon,
+        replay,
+        CrawlAction::Move(cmd.direction),
+    );
+    Ok(())
+}
+
+// Solo crawl dungeon and replay files for the classic or Daily Dash crawl
+pub fn crawl_filepaths(user_id: &str, daily: bool) -> (String, String) {
+    if daily {
+        (
+            paths::player_daily_dungeon(user_id),
+            paths::player_daily_dungeon_replay(user_id),
+        )
+    } else {
+        (
+            paths::player_dungeon(user_id),
+            paths::player_dungeon_replay(user_id),
+        )
     }
+}
 
-    // Move monsters if player has not reached the exit
-    if !dungeon.is_exit(dungeon.player.x, dungeon.player.y) {
-        os::server::log!("Moving monsters...");
-        dungeon.move_monsters(os::server::log);
-
-        // Alternatively, you could invoke an external command to perform actions
-        // The flow to invoke the move_monsters command from within the move_players command:
-        // 1. Write our dungeon updates to the player's dungeon file
-        // 2. Execute move_monsters via invoke_command. This will update the dungeon file.
-        // 3. Read the dungeon file and update our dungeon var
-        // os::server::write!(&dungeon_filepath, &dungeon).expect("Could not write player dungeon");
-        // let res = os::server::invoke_command(
-        //     &PROGRAM_ID,
-        //     &move_monsters::COMMAND,
-        //     &move_monsters::Command::for_crawl_id(dungeon.crawl_id)
-        //         .try_to_vec()
-        //         .unwrap(),
-        // )
-        // .expect("Could not invoke move_monsters command");
-        // dungeon = os::server::read!(Dungeon, &dungeon_filepath);
+// The view of the classic or Daily Dash crawl the client watches
+pub fn view_filepath(user_id: &str, daily: bool) -> String {
+    if daily {
+        paths::player_daily_dungeon_view(user_id)
     } else {
-        os::server::log!("P1 reached exit.");
+        paths::player_dungeon_view(user_id)
+    }
+}
+
+// Saves the dungeon, which only the server reads, and the player's view of it for the client
+pub fn save_dungeon(user_id: &str, daily: bool, dungeon: &Dungeon) {
+    let (dungeon_filepath, _) = crawl_filepaths(user_id, daily);
+    os::server::write!(&dungeon_filepath, &Versioned(dungeon))
+        .expect("Could not write player dungeon");
+    let view = DungeonView::new(dungeon);
+    os::server::write!(&view_filepath(user_id, daily), &Versioned(&view))
+        .expect("Could not write player dungeon view");
+}
+
+// The replay every action of the crawl is recorded to. Commands load it before changing anything.
+// Crawls from before replays were recorded have none (Some(None)) and carry on unrecorded.
+pub fn load_replay(user_id: &str, daily: bool, dungeon: &Dungeon) -> Option<Option<CrawlReplay>> {
+    if !dungeon.replayable {
+        return Some(None);
+    }
+    let (_, replay_filepath) = crawl_filepaths(user_id, daily);
+    let replay = os::server::read_file(&replay_filepath)
+        .and_then(|bytes| CrawlReplay::decode(&bytes))
+        .ok()?;
+    if replay.crawl_id != dungeon.crawl_id {
+        os::server::log!("Replay does not match crawl {}", dungeon.crawl_id);
+        return None;
     }
+    Some(Some(replay))
+}
+
+// Records a completed action, settles the crawl if the player died, and saves the dungeon
+pub fn finish_turn(
+    user_id: &str,
+    daily: bool,
+    mut dungeon: Dungeon,
+    mut replay: Option<CrawlReplay>,
+    action: CrawlAction,
+) {
+    // Record the action for replays
+    if let Some(replay) = &mut replay {
+        let (_, replay_filepath) = crawl_filepaths(user_id, daily);
+        replay.actions.push(action);
+        os::server::write!(&replay_filepath, &Versioned(&*replay))
+            .expect("Could not write player replay");
+    }
+
+    // If player died during a Daily Dash...
+    if dungeon.player.health == 0 {
+        if let DungeonMode::Daily(day) = dungeon.mode {
+            dungeon.increment_stats(DungeonStatKind::CrawlsCompleted, 1);
+
+            // Update the day's leaderboard
+            os::server::log!("Reading daily leaderboard...");
+            let leaderboard_filepath = paths::daily_leaderboard(day);
+            let mut leaderboard = os::server::read_or!(
+                Versioned<Leaderboard>,
+                &leaderboard_filepath,
+                Versioned(Leaderboard::new())
+            )
+            .0;
+            os::server::log!("Updating daily leaderboard...");
+            for kind in LeaderboardKind::ALL {
+                let score = kind.score(&dungeon.stats);
+                leaderboard.update(dungeon.crawl_id, *kind, user_id, score);
+            }
+            os::server::log!("Saving daily leaderboard...");
+            os::server::write!(&leaderboard_filepath, &Versioned(&leaderboard))
+                .expect("Could not write daily leaderboard");
 
-    // Increment turn
-    os::server::log!("Incrementing turn number...");
-    dungeon.turn += 1;
+            // Archive and verify the replay
+            if let Some(replay) = &replay {
+                archive_replay(&dungeon, replay);
+            }
+
+            // Daily runs don't count towards lifetime stats, achievements, or rankings
+            save_dungeon(user_id, daily, &dungeon);
+            return;
+        }
+    }
 
     // If player died...
     if dungeon.player.health == 0 {
@@ -77,14 +203,18 @@ unsafe extern "C" fn exec() -> usize {
         // Update the global leaderboard
         os::server::log!("Reading global leaderboard...");
         let leaderboard_filepath = paths::global_leaderboard();
-        let mut leaderboard =
-            os::server::read_or!(Leaderboard, &leaderboard_filepath, Leaderboard::new());
+        let mut leaderboard = os::server::read_or!(
+            Versioned<Leaderboard>,
+            &leaderboard_filepath,
+            Versioned(Leaderboard::new())
+        )
+        .0;
         os::server::log!("Updating global leaderboard...");
         if let Some(entry) = leaderboard.update(
             dungeon.crawl_id,
             LeaderboardKind::LeastSteps,
-            &user_id,
-            dungeon.stats.get(DungeonStatKind::StepsMoved),
+            user_id,
+            LeaderboardKind::LeastSteps.score(&dungeon.stats),
         ) {
             os::server::alert!(
                 "Player {:.8} died after only {:?} steps! R.I.P. son",
@@ -95,35 +225,40 @@ unsafe extern "C" fn exec() -> usize {
         if let Some(entry) = leaderboard.update(
             dungeon.crawl_id,
             LeaderboardKind::MostKills,
-            &user_id,
-            dungeon.stats.total_monsters_defeated(),
+            user_id,
+            LeaderboardKind::MostKills.score(&dungeon.stats),
         ) {
             os::server::alert!("Player {:.8} slayed {:?} monsters!", user_id, entry.score);
         }
         if let Some(entry) = leaderboard.update(
             dungeon.crawl_id,
             LeaderboardKind::MostGold,
-            &user_id,
-            dungeon.stats.get(DungeonStatKind::GoldCollected),
+            user_id,
+            LeaderboardKind::MostGold.score(&dungeon.stats),
         ) {
             os::server::alert!("Player {:.8} amassed {:?} gold!", user_id, entry.score);
         }
         if let Some(entry) = leaderboard.update(
             dungeon.crawl_id,
             LeaderboardKind::HighestFloor,
-            &user_id,
-            dungeon.stats.get(DungeonStatKind::FloorsCleared) + 1,
+            user_id,
+            LeaderboardKind::HighestFloor.score(&dungeon.stats),
         ) {
             os::server::alert!("Player {:.8} reached floor {:?}!", user_id, entry.score);
         }
         os::server::log!("Saving global leaderboard...");
-        os::server::write!(&leaderboard_filepath, &leaderboard)
+        os::server::write!(&leaderboard_filepath, &Versioned(&leaderboard))
             .expect("Could not write leaderboard");
 
+        // Archive and verify the replay
+        if let Some(replay) = &replay {
+            archive_replay(&dungeon, replay);
+        }
+
         // Update player stats
         os::server::log!("Saving player stats...");
-        let player_stats_filepath = paths::player_dungeon_stats(&user_id);
-        os::server::write!(&player_stats_filepath, &dungeon.total_stats)
+        let player_stats_filepath = paths::player_dungeon_stats(user_id);
+        os::server::write!(&player_stats_filepath, &Versioned(&dungeon.total_stats))
             .expect("Could not write player stats");
 
         // Unlock achievements
@@ -142,15 +277,18 @@ unsafe extern "C" fn exec() -> usize {
             dungeon.all_unlocked.achievement_kinds()
         );
         os::server::log!("Saving player achievements...");
-        let player_achievements_filepath = paths::player_achievements(&user_id);
-        os::server::write!(&player_achievements_filepath, &dungeon.all_unlocked)
-            .expect("Could not write player achievements");
+        let player_achievements_filepath = paths::player_achievements(user_id);
+        os::server::write!(
+            &player_achievements_filepath,
+            &Versioned(&dungeon.all_unlocked)
+        )
+        .expect("Could not write player achievements");
 
         let floor_rankings_filepath = "floor_rankings";
         let mut floor_rankings =
             os::server::read_or!(BTreeMap<String, u32>, floor_rankings_filepath, BTreeMap::new());
         floor_rankings
-            .entry(user_id.clone())
+            .entry(user_id.to_string())
             .and_modify(|floor| {
                 if *floor < dungeon.floor {
                     *floor = dungeon.floor;
@@ -167,7 +305,7 @@ unsafe extern "C" fn exec() -> usize {
         let mut yeti_rankings =
             os::server::read_or!(BTreeMap<String, u32>, yeti_rankings_filepath, BTreeMap::new());
         yeti_rankings.insert(
-            user_id.clone(),
+            user_id.to_string(),
             dungeon.total_stats.monster_kills(MonsterKind::IceYeti),
         );
         if yeti_rankings.len() > 100 {
@@ -180,8 +318,24 @@ unsafe extern "C" fn exec() -> usize {
 
     // Save the dungeon
     os::server::log!("Saving the dungeon...");
-    os::server::write!(&dungeon_filepath, &dungeon).expect("Could not write player dungeon");
+    save_dungeon(user_id, daily, &dungeon);
+}
 
-    // Commit the command result
-    os::server::COMMIT
+// Archives the replay so the crawl can be watched after the next one starts,
+// then re-simulates the crawl to verify its leaderboard entries
+fn archive_replay(dungeon: &Dungeon, replay: &CrawlReplay) {
+    os::server::log!("Archiving replay...");
+    os::server::write!(&paths::crawl_replay(dungeon.crawl_id), &Versioned(replay))
+        .expect("Could not write crawl replay");
+
+    let verify_cmd = verify_crawl::Command::new(dungeon.crawl_id);
+    if let Err(err) = os::server::enqueue_command(
+        PROGRAM_ID,
+        verify_crawl::Command::NAME,
+        &verify_cmd.try_to_vec().unwrap(),
+        dungeon.crawl_id as u64,
+        None,
+    ) {
+        os::server::log!("Could not enqueue crawl verification: {err:?}");
+    }
 }
@@ -1,16 +1,39 @@
 use super::*;
+use std::fmt;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct Command {
     crawl_id: u32,
     direction: Direction,
+    // The floor and round the client last saw, so retries and double-sends can't move twice
+    floor: u32,
+    round: u32,
 }
 impl Command {
     pub const NAME: &'static str = "move_player_multiplayer_dungeon";
-    pub fn new(crawl_id: u32, direction: Direction) -> Self {
+    pub fn new(dungeon: &MultiplayerDungeon, direction: Direction) -> Self {
         Self {
-            crawl_id,
+            crawl_id: dungeon.crawl_id,
             direction,
+            floor: dungeon.floor,
+            round: dungeon.round,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    NoCrawl,
+    Stale(StaleCommand),
+    // The move broke a rule, like moving twice in a round
+    InvalidMove(String),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCrawl => write!(f, "That crawl is over"),
+            Self::Stale(err) => err.fmt(f),
+            Self::InvalidMove(reason) => write!(f, "{reason}"),
         }
     }
 }
@@ -23,25 +46,37 @@ unsafe extern "C" fn exec() -> usize {
     // Get command data
     let cmd = os::server::command!(Command);
 
+    results::commit(
+        &user_id,
+        run(&user_id, cmd).map_err(CommandError::MovePlayerMultiplayerDungeon),
+    )
+}
+
+fn run(user_id: &str, cmd: Command) -> Result<(), Error> {
     // Load multiplayer dungeon
     os::server::log!("Loading the multiplayer dungeon ({})...", cmd.crawl_id);
     let dungeon_filepath = paths::multiplayer_dungeon(cmd.crawl_id);
-    let mut dungeon = os::server::read!(MultiplayerDungeon, &dungeon_filepath);
+    let mut dungeon = os::server::read_else!(Versioned<MultiplayerDungeon>, &dungeon_filepath, {
+        return Err(Error::NoCrawl);
+    })
+    .0;
+
+    // Cancel command if it was sent for a round that's over or was already applied
+    dungeon
+        .check_command(user_id, cmd.floor, cmd.round)
+        .map_err(Error::Stale)?;
 
     // Move player
     os::server::log!("Moving player...");
-    if dungeon.move_player(&user_id, cmd.direction).is_err() {
-        return os::server::CANCEL;
-    }
+    dungeon
+        .move_player(user_id, cmd.direction, os::server::log)
+        .map_err(|err| Error::InvalidMove(err.to_string()))?;
     dungeon.turn += 1;
 
     // If no more players can move this round, move the monsters
     if dungeon.did_all_players_move() {
         os::server::log!("Moving monsters...");
-        if let Err(err) = dungeon.move_all_monsters() {
-            os::server::log!("Error moving monsters: {err}");
-            return os::server::CANCEL;
-        };
+        dungeon.move_monsters(os::server::log);
         // Increment turn
         os::server::log!("Incrementing dungeon round...");
         dungeon.round += 1;
@@ -127,8 +162,20 @@ unsafe extern "C" fn exec() -> usize {
 
     // Save the dungeon
     os::server::log!("Saving the dungeon...");
-    os::server::write!(&dungeon_filepath, &dungeon).expect("Could not write player dungeon");
+    save_dungeon(&dungeon);
 
-    // Commit the command result
-    os::server::COMMIT
+    Ok(())
+}
+
+// Saves the dungeon, which only the server reads, and each player's view of it for their client
+pub fn save_dungeon(dungeon: &MultiplayerDungeon) {
+    let dungeon_filepath = paths::multiplayer_dungeon(dungeon.crawl_id);
+    os::server::write!(&dungeon_filepath, &Versioned(dungeon))
+        .expect("Could not save dungeon file.");
+    for user_id in dungeon.player.players.keys() {
+        let view = MultiplayerDungeonView::new(dungeon, user_id);
+        let view_filepath = paths::multiplayer_dungeon_view(dungeon.crawl_id, user_id);
+        os::server::write!(&view_filepath, &Versioned(&view))
+            .expect("Could not save dungeon view file.");
+    }
 }
@@ -0,0 +1,83 @@
+use super::*;
+use std::fmt;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Command {
+    pub item: ShopItem,
+    pub daily: bool,
+    // The crawl and action count the client last saw (see `move_player`)
+    pub crawl_id: u32,
+    pub actions: u32,
+}
+impl Command {
+    pub const NAME: &'static str = "purchase";
+    pub fn new(dungeon: &Dungeon, item: ShopItem) -> Self {
+        Self {
+            item,
+            daily: dungeon.mode.is_daily(),
+            crawl_id: dungeon.crawl_id,
+            actions: dungeon.actions,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    NoCrawl,
+    Stale(StaleCommand),
+    // The shop turned the purchase down, like when the player is short on gold
+    CannotBuy(String),
+    NoReplay,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCrawl => write!(f, "No crawl in progress"),
+            Self::Stale(err) => err.fmt(f),
+            Self::CannotBuy(reason) => write!(f, "{reason}"),
+            Self::NoReplay => write!(f, "This crawl can't be recorded"),
+        }
+    }
+}
+
+#[export_name = "turbo/purchase"]
+unsafe extern "C" fn exec() -> usize {
+    // Get player id
+    let user_id = os::server::get_user_id();
+
+    // Get command data
+    let cmd = os::server::command!(Command);
+
+    results::commit(&user_id, run(&user_id, cmd).map_err(CommandError::Purchase))
+}
+
+fn run(user_id: &str, cmd: Command) -> Result<(), Error> {
+    // Load player dungeon
+    os::server::log!("Loading the dungeon for player {}...", user_id);
+    let (dungeon_filepath, _) = move_player::crawl_filepaths(user_id, cmd.daily);
+    let mut dungeon = os::server::read_else!(Versioned<Dungeon>, &dungeon_filepath, {
+        return Err(Error::NoCrawl);
+    })
+    .0;
+
+    // Cancel command if it was sent for another crawl or was already applied
+    dungeon
+        .check_command(cmd.crawl_id, cmd.actions)
+        .map_err(Error::Stale)?;
+    let replay = move_player::load_replay(user_id, cmd.daily, &dungeon).ok_or(Error::NoReplay)?;
+
+    // Validate the purchase against the shop and the player's gold
+    os::server::log!("Purchasing {:?}...", cmd.item);
+    dungeon
+        .purchase(cmd.item, os::server::log)
+        .map_err(|reason| Error::CannotBuy(reason.to_string()))?;
+
+    move_player::finish_turn(
+        user_id,
+        cmd.daily,
+        dungeon,
+        replay,
+        CrawlAction::Purchase(cmd.item),
+    );
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use super::*;
+use std::fmt;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Command {
+    pub kind: UpgradeKind,
+}
+impl Command {
+    pub const NAME: &'static str = "unlock_upgrade";
+    pub fn new(kind: UpgradeKind) -> Self {
+        Self { kind }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    // Maxed out or not enough essence
+    CannotUnlock(String),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CannotUnlock(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+#[export_name = "turbo/unlock_upgrade"]
+unsafe extern "C" fn exec() -> usize {
+    // Get player id
+    let user_id = os::server::get_user_id();
+
+    // Get command data
+    let cmd = os::server::command!(Command);
+
+    results::commit(
+        &user_id,
+        run(&user_id, cmd).map_err(CommandError::UnlockUpgrade),
+    )
+}
+
+fn run(user_id: &str, cmd: Command) -> Result<(), Error> {
+    // Essence is earned from lifetime stats
+    let total_stats = os::server::read_or!(
+        Versioned<DungeonStats>,
+        &paths::player_dungeon_stats(user_id),
+        Versioned(DungeonStats::new())
+    )
+    .0;
+    let upgrades_filepath = paths::player_upgrades(user_id);
+    let mut upgrades = os::server::read_or!(
+        Versioned<PlayerUpgrades>,
+        &upgrades_filepath,
+        Versioned(PlayerUpgrades::new())
+    )
+    .0;
+
+    // Spend essence on the next level
+    match upgrades.unlock(cmd.kind, &total_stats) {
+        Ok(cost) => os::server::log!(
+            "Unlocked {:?} level {} for {} essence",
+            cmd.kind,
+            upgrades.level(cmd.kind),
+            cost
+        ),
+        Err(err) => {
+            os::server::log!("Could not unlock {:?}: {}", cmd.kind, err);
+            return Err(Error::CannotUnlock(err.to_string()));
+        }
+    }
+
+    os::server::write!(&upgrades_filepath, &Versioned(&upgrades))
+        .expect("Could not save player upgrades.");
+
+    Ok(())
+}
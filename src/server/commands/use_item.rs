@@ -0,0 +1,83 @@
+use super::*;
+use std::fmt;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Command {
+    pub slot: usize,
+    pub daily: bool,
+    // The crawl and action count the client last saw (see `move_player`)
+    pub crawl_id: u32,
+    pub actions: u32,
+}
+impl Command {
+    pub const NAME: &'static str = "use_item";
+    pub fn new(dungeon: &Dungeon, slot: usize) -> Self {
+        Self {
+            slot,
+            daily: dungeon.mode.is_daily(),
+            crawl_id: dungeon.crawl_id,
+            actions: dungeon.actions,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum Error {
+    NoCrawl,
+    Stale(StaleCommand),
+    // The item can't be used right now, like an empty slot
+    CannotUse(String),
+    NoReplay,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCrawl => write!(f, "No crawl in progress"),
+            Self::Stale(err) => err.fmt(f),
+            Self::CannotUse(reason) => write!(f, "{reason}"),
+            Self::NoReplay => write!(f, "This crawl can't be recorded"),
+        }
+    }
+}
+
+#[export_name = "turbo/use_item"]
+unsafe extern "C" fn exec() -> usize {
+    // Get player id
+    let user_id = os::server::get_user_id();
+
+    // Get command data
+    let cmd = os::server::command!(Command);
+
+    results::commit(&user_id, run(&user_id, cmd).map_err(CommandError::UseItem))
+}
+
+fn run(user_id: &str, cmd: Command) -> Result<(), Error> {
+    // Load player dungeon
+    os::server::log!("Loading the dungeon for player {}...", user_id);
+    let (dungeon_filepath, _) = move_player::crawl_filepaths(user_id, cmd.daily);
+    let mut dungeon = os::server::read_else!(Versioned<Dungeon>, &dungeon_filepath, {
+        return Err(Error::NoCrawl);
+    })
+    .0;
+
+    // Cancel command if it was sent for another crawl or was already applied
+    dungeon
+        .check_command(cmd.crawl_id, cmd.actions)
+        .map_err(Error::Stale)?;
+    let replay = move_player::load_replay(user_id, cmd.daily, &dungeon).ok_or(Error::NoReplay)?;
+
+    // Use the item, then move monsters if player has not reached the exit
+    os::server::log!("Using item in slot {}...", cmd.slot + 1);
+    dungeon
+        .use_item(cmd.slot, os::server::log)
+        .map_err(|reason| Error::CannotUse(reason.to_string()))?;
+
+    move_player::finish_turn(
+        user_id,
+        cmd.daily,
+        dungeon,
+        replay,
+        CrawlAction::UseItem(cmd.slot),
+    );
+    Ok(())
+}
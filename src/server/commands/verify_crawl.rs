@@ -0,0 +1,84 @@
+use super::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Command {
+    pub crawl_id: u32,
+}
+impl Command {
+    pub const NAME: &'static str = "verify_crawl";
+    pub fn new(crawl_id: u32) -> Self {
+        Self { crawl_id }
+    }
+}
+
+#[export_name = "turbo/verify_crawl"]
+unsafe extern "C" fn exec() -> usize {
+    // Get player id
+    let user_id = os::server::get_user_id();
+
+    // Get command data
+    let cmd = os::server::command!(Command);
+
+    // Load the archived replay
+    os::server::log!("Loading replay for crawl {}...", cmd.crawl_id);
+    let replay_filepath = paths::crawl_replay(cmd.crawl_id);
+    let replay = os::server::read_else!(Versioned<CrawlReplay>, &replay_filepath, {
+        os::server::log!("No replay for crawl {}", cmd.crawl_id);
+        return os::server::CANCEL;
+    })
+    .0;
+
+    // Re-simulate the crawl and compare against the submitted scores
+    let leaderboard_filepath = match replay.mode {
+        DungeonMode::Classic => paths::global_leaderboard(),
+        DungeonMode::Daily(day) => paths::daily_leaderboard(day),
+    };
+    let mut leaderboard = os::server::read_or!(
+        Versioned<Leaderboard>,
+        &leaderboard_filepath,
+        Versioned(Leaderboard::new())
+    )
+    .0;
+
+    // The server queues verification as the player who finished the crawl,
+    // so nobody else can have a crawl's entries re-checked
+    let is_owner = LeaderboardKind::ALL
+        .iter()
+        .filter_map(|kind| leaderboard.find(*kind, |e| e.crawl_id == cmd.crawl_id))
+        .all(|entry| entry.name == user_id);
+    if !is_owner {
+        os::server::log!("Crawl {} belongs to another player", cmd.crawl_id);
+        return os::server::CANCEL;
+    }
+
+    let mismatches = replay.verify(&leaderboard, os::server::log);
+    if mismatches.is_empty() {
+        os::server::log!("Crawl {} verified.", cmd.crawl_id);
+        return os::server::COMMIT;
+    }
+
+    // Reject mismatched entries
+    for mismatch in &mismatches {
+        os::server::log!(
+            "Rejected {:?} entry: submitted {}, replayed {:?}",
+            mismatch.kind,
+            mismatch.submitted,
+            mismatch.replayed
+        );
+        leaderboard.remove(mismatch.kind, cmd.crawl_id);
+    }
+    os::server::write!(&leaderboard_filepath, &Versioned(&leaderboard))
+        .expect("Could not write leaderboard");
+
+    // Flag the crawl for review
+    let flagged_filepath = paths::flagged_crawls();
+    let mut flagged = os::server::read_or!(
+        BTreeMap<u32, Vec<ScoreMismatch>>,
+        &flagged_filepath,
+        BTreeMap::new()
+    );
+    flagged.insert(cmd.crawl_id, mismatches);
+    os::server::write!(&flagged_filepath, &flagged).expect("Could not write flagged crawls");
+
+    os::server::COMMIT
+}
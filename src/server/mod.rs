@@ -1,6 +1,7 @@
 use super::*;
 
 pub const PROGRAM_ID: &'static str = "dungeon_dash";
+// Files carry their own schema version (see `Schema`), so layout changes shouldn't bump this
 pub const PROGRAM_VERSION: usize = 1;
 
 pub mod paths {
@@ -8,12 +9,22 @@ pub mod paths {
     pub fn global_leaderboard() -> String {
         "leaderboard".to_string()
     }
+    pub fn flagged_crawls() -> String {
+        "flagged_crawls".to_string()
+    }
     pub fn multiplayer_dungeon_list() -> String {
         "multiplayer_dungeon_list".to_string()
     }
     pub fn multiplayer_dungeon(crawl_id: u32) -> String {
         format!("multiplayer_dungeons/v{}/{}", PROGRAM_VERSION, crawl_id)
     }
+    // Each player's view of a multiplayer dungeon (see `MultiplayerDungeonView`)
+    pub fn multiplayer_dungeon_view(crawl_id: u32, user_id: &str) -> String {
+        format!(
+            "multiplayer_dungeon_views/v{}/{}/{}",
+            PROGRAM_VERSION, crawl_id, user_id
+        )
+    }
     pub fn player_multiplayer_dungeon_manifest(user_id: &str) -> String {
         format!(
             "users/{}/v{}/multiplayer_dungeon_manifest",
@@ -23,19 +34,54 @@ pub mod paths {
     pub fn player_dungeon(user_id: &str) -> String {
         format!("users/{}/v{}/dungeon", user_id, PROGRAM_VERSION)
     }
+    // The player's view of their dungeon (see `DungeonView`)
+    pub fn player_dungeon_view(user_id: &str) -> String {
+        format!("users/{}/v{}/dungeon_view", user_id, PROGRAM_VERSION)
+    }
+    // The replay of the crawl in progress. Like the dungeon, only the server reads it
+    // (it's archived under `crawl_replay` once the crawl ends).
+    pub fn player_dungeon_replay(user_id: &str) -> String {
+        format!("users/{}/v{}/dungeon_replay", user_id, PROGRAM_VERSION)
+    }
+    pub fn player_daily_dungeon(user_id: &str) -> String {
+        format!("users/{}/v{}/daily_dungeon", user_id, PROGRAM_VERSION)
+    }
+    pub fn player_daily_dungeon_view(user_id: &str) -> String {
+        format!("users/{}/v{}/daily_dungeon_view", user_id, PROGRAM_VERSION)
+    }
+    pub fn player_daily_dungeon_replay(user_id: &str) -> String {
+        format!("users/{}/v{}/daily_dungeon_replay", user_id, PROGRAM_VERSION)
+    }
+    pub fn player_daily_attempt(user_id: &str) -> String {
+        format!("users/{}/v{}/daily_attempt", user_id, PROGRAM_VERSION)
+    }
+    pub fn daily_leaderboard(day: u32) -> String {
+        format!("daily_leaderboards/v{}/{}", PROGRAM_VERSION, day)
+    }
+    pub fn crawl_replay(crawl_id: u32) -> String {
+        format!("crawl_replays/v{}/{}", PROGRAM_VERSION, crawl_id)
+    }
     pub fn player_dungeon_stats(user_id: &str) -> String {
         format!("users/{}/v{}/stats", user_id, PROGRAM_VERSION)
     }
     pub fn player_achievements(user_id: &str) -> String {
         format!("users/{}/v{}/achievements", user_id, PROGRAM_VERSION)
     }
+    pub fn player_upgrades(user_id: &str) -> String {
+        format!("users/{}/v{}/upgrades", user_id, PROGRAM_VERSION)
+    }
     pub fn player_leaderboard(user_id: &str) -> String {
         format!("users/{}/v{}/leaderboard", user_id, PROGRAM_VERSION)
     }
+    // Why the player's last failed command was turned away (see `results::CommandFailure`)
+    pub fn player_command_failure(user_id: &str) -> String {
+        format!("users/{}/v{}/command_failure", user_id, PROGRAM_VERSION)
+    }
 }
 
 pub mod commands {
     use super::*;
+    use results::CommandError;
     pub mod create_multiplayer_dungeon_lobby;
     pub mod create_new_dungeon;
     pub mod create_new_multiplayer_dungeon;
@@ -44,9 +90,91 @@ pub mod commands {
     pub mod delete_multiplayer_dungeon_lobby;
     pub mod join_multiplayer_dungeon_lobby;
     pub mod leave_multiplayer_dungeon_lobby;
+    pub mod migrate_player_files;
     pub mod move_monsters;
     pub mod move_player;
     pub mod move_player_multiplayer_dungeon;
+    pub mod purchase;
+    pub mod unlock_upgrade;
+    pub mod use_item;
+    pub mod verify_crawl;
+}
+
+// Commands that the player's input makes fail still commit, saving only why they failed,
+// so the client can tell the player what went wrong. Storage errors cancel as before,
+// since committing would keep whatever was written before them.
+pub mod results {
+    use super::*;
+    use std::fmt;
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+    pub enum CommandError {
+        CreateNewDungeon(commands::create_new_dungeon::Error),
+        MovePlayer(commands::move_player::Error),
+        UseItem(commands::use_item::Error),
+        Purchase(commands::purchase::Error),
+        UnlockUpgrade(commands::unlock_upgrade::Error),
+        JoinMultiplayerDungeonLobby(commands::join_multiplayer_dungeon_lobby::Error),
+        CreateNewMultiplayerDungeon(commands::create_new_multiplayer_dungeon::Error),
+        MovePlayerMultiplayerDungeon(commands::move_player_multiplayer_dungeon::Error),
+        DeleteMultiplayerDungeon(commands::delete_multiplayer_dungeon::Error),
+    }
+    impl CommandError {
+        // Retries and double-sends that were already applied. The client catches up on its own,
+        // so these aren't worth telling the player about.
+        pub fn is_stale(&self) -> bool {
+            matches!(
+                self,
+                Self::CreateNewDungeon(commands::create_new_dungeon::Error::Stale(_))
+                    | Self::MovePlayer(commands::move_player::Error::Stale(_))
+                    | Self::UseItem(commands::use_item::Error::Stale(_))
+                    | Self::Purchase(commands::purchase::Error::Stale(_))
+                    | Self::MovePlayerMultiplayerDungeon(
+                        commands::move_player_multiplayer_dungeon::Error::Stale(_)
+                    )
+            )
+        }
+    }
+    impl fmt::Display for CommandError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::CreateNewDungeon(err) => err.fmt(f),
+                Self::MovePlayer(err) => err.fmt(f),
+                Self::UseItem(err) => err.fmt(f),
+                Self::Purchase(err) => err.fmt(f),
+                Self::UnlockUpgrade(err) => err.fmt(f),
+                Self::JoinMultiplayerDungeonLobby(err) => err.fmt(f),
+                Self::CreateNewMultiplayerDungeon(err) => err.fmt(f),
+                Self::MovePlayerMultiplayerDungeon(err) => err.fmt(f),
+                Self::DeleteMultiplayerDungeon(err) => err.fmt(f),
+            }
+        }
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+    pub struct CommandFailure {
+        // Goes up with every failure, so the client can tell a new one from the last
+        pub id: u32,
+        pub error: CommandError,
+    }
+    impl Schema for CommandFailure {
+        const VERSION: u16 = 1;
+    }
+
+    // Commits the command, saving why it failed if it did
+    pub fn commit(user_id: &str, result: Result<(), CommandError>) -> usize {
+        let Err(error) = result else {
+            return os::server::COMMIT;
+        };
+        os::server::log!("Command failed: {error}");
+        let filepath = paths::player_command_failure(user_id);
+        let id = os::server::read_file(&filepath)
+            .and_then(|bytes| CommandFailure::decode(&bytes))
+            .map_or(0, |failure| failure.id + 1);
+        os::server::write!(&filepath, &Versioned(&CommandFailure { id, error }))
+            .expect("Could not save command failure.");
+        os::server::COMMIT
+    }
 }
 
 pub mod deserializers {
@@ -72,7 +200,7 @@ pub mod deserializers {
         if bytes.is_empty() {
             return os::server::log!("File is empty");
         }
-        match Dungeon::try_from_slice(&bytes) {
+        match Dungeon::decode(&bytes) {
             Ok(dungeon) => os::server::log!("{:#?}", dungeon),
             Err(err) => os::server::log!("{:#?}", err),
         };
@@ -84,7 +212,7 @@ pub mod deserializers {
         if bytes.is_empty() {
             return os::server::log!("{{}}");
         }
-        let dungeon = match Dungeon::try_from_slice(&bytes) {
+        let dungeon = match Dungeon::decode(&bytes) {
             Ok(dungeon) => dungeon,
             Err(err) => return os::server::log!("{:#?}", err),
         };
@@ -92,13 +220,39 @@ pub mod deserializers {
         os::server::log!("{}", json)
     }
 
+    #[export_name = "deserializers/crawl_replay"]
+    unsafe extern "C" fn deserialize_crawl_replay() {
+        let bytes = os::server::get_command_data();
+        if bytes.is_empty() {
+            return os::server::log!("File is empty");
+        }
+        match CrawlReplay::decode(&bytes) {
+            Ok(replay) => os::server::log!("{:#?}", replay),
+            Err(err) => os::server::log!("{:#?}", err),
+        };
+    }
+
+    #[export_name = "deserializers/crawl_replay_json"]
+    unsafe extern "C" fn deserialize_crawl_replay_json() {
+        let bytes = os::server::get_command_data();
+        if bytes.is_empty() {
+            return os::server::log!("{{}}");
+        }
+        let replay = match CrawlReplay::decode(&bytes) {
+            Ok(replay) => replay,
+            Err(err) => return os::server::log!("{:#?}", err),
+        };
+        let json = json!(replay);
+        os::server::log!("{}", json)
+    }
+
     #[export_name = "deserializers/multiplayer_dungeon"]
     unsafe extern "C" fn deserialize_multiplayer_dungeon() {
         let bytes = os::server::get_command_data();
         if bytes.is_empty() {
             return os::server::log!("File is empty");
         }
-        match MultiplayerDungeon::try_from_slice(&bytes) {
+        match MultiplayerDungeon::decode(&bytes) {
             Ok(multiplayer_dungeon) => os::server::log!("{:#?}", multiplayer_dungeon),
             Err(err) => os::server::log!("{:#?}", err),
         };
@@ -124,7 +278,7 @@ pub mod deserializers {
         if bytes.is_empty() {
             return os::server::log!("{{}}");
         }
-        let leaderboard = match Leaderboard::try_from_slice(&bytes) {
+        let leaderboard = match Leaderboard::decode(&bytes) {
             Ok(leaderboard) => leaderboard,
             Err(err) => return os::server::log!("{:#?}", err),
         };
@@ -137,7 +291,7 @@ pub mod deserializers {
         if bytes.is_empty() {
             return os::server::log!("{{}}");
         }
-        let leaderboard = match Leaderboard::try_from_slice(&bytes) {
+        let leaderboard = match Leaderboard::decode(&bytes) {
             Ok(leaderboard) => leaderboard,
             Err(err) => return os::server::log!("{:#?}", err),
         };
@@ -151,7 +305,7 @@ pub mod deserializers {
         if bytes.is_empty() {
             return os::server::log!("{{}}");
         }
-        let data = match DungeonStats::try_from_slice(&bytes) {
+        let data = match DungeonStats::decode(&bytes) {
             Ok(data) => data,
             Err(err) => return os::server::log!("{:#?}", err),
         };
@@ -164,7 +318,7 @@ pub mod deserializers {
         if bytes.is_empty() {
             return os::server::log!("{{}}");
         }
-        let data = match DungeonStats::try_from_slice(&bytes) {
+        let data = match DungeonStats::decode(&bytes) {
             Ok(data) => data,
             Err(err) => return os::server::log!("{:#?}", err),
         };
@@ -172,6 +326,19 @@ pub mod deserializers {
         os::server::log!("{}", json)
     }
 
+    #[export_name = "deserializers/player_upgrades"]
+    unsafe extern "C" fn deserialize_player_upgrades() {
+        let bytes = os::server::get_command_data();
+        if bytes.is_empty() {
+            return os::server::log!("{{}}");
+        }
+        let data = match PlayerUpgrades::decode(&bytes) {
+            Ok(data) => data,
+            Err(err) => return os::server::log!("{:#?}", err),
+        };
+        os::server::log!("{:#?}", data)
+    }
+
     #[export_name = "deserializers/rankings"]
     unsafe extern "C" fn deserialize_rankings() {
         let bytes = os::server::get_command_data();
@@ -185,6 +352,19 @@ pub mod deserializers {
         os::server::log!("{:#?}", data)
     }
 
+    #[export_name = "deserializers/flagged_crawls"]
+    unsafe extern "C" fn deserialize_flagged_crawls() {
+        let bytes = os::server::get_command_data();
+        if bytes.is_empty() {
+            return os::server::log!("{{}}");
+        }
+        let data = match <BTreeMap<u32, Vec<ScoreMismatch>>>::try_from_slice(&bytes) {
+            Ok(data) => data,
+            Err(err) => return os::server::log!("{:#?}", err),
+        };
+        os::server::log!("{:#?}", data)
+    }
+
     #[export_name = "deserializers/multiplayer_dungeon_channel_in"]
     unsafe extern "C" fn deserialize_multiplayer_dungeon_channel_in() {
         let bytes = os::server::get_command_data();